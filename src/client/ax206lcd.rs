@@ -4,7 +4,6 @@ use log::{debug, error, info};
 use rusb::{Context, DeviceHandle, Direction, UsbContext};
 use std::time::Duration;
 use thiserror::Error;
-use crate::config::AppConfig;
 
 /// # AX206LCD Protocol Documentation
 ///
@@ -118,11 +117,7 @@ pub struct AX206LCD {
 }
 
 impl AX206LCD {
-    pub fn new(debug: bool) -> Result<Self, AX206Error> {
-        let config = AppConfig::new().map_err(|_| AX206Error::DeviceNotFound)?;
-        let vid = config.lcd.vid;
-        let pid = config.lcd.pid;
-
+    pub fn new(vid: u16, pid: u16, debug: bool) -> Result<Self, AX206Error> {
         let context = Context::new()?;
 
         // Find the device
@@ -186,6 +181,7 @@ impl AX206LCD {
         Ok(())
     }
 
+    #[allow(dead_code)]
     pub fn clear(&mut self, color: (u8, u8, u8)) -> Result<(), AX206Error> {
         // Convert RGB to RGB565
         let (r, g, b) = color;
@@ -297,8 +293,8 @@ impl AX206LCD {
         }
 
         // Calculate centering position
-        let x = ((self.width as u32 - resize_width) / 2) as u32;
-        let y = ((self.height as u32 - resize_height) / 2) as u32;
+        let x = (self.width as u32 - resize_width) / 2;
+        let y = (self.height as u32 - resize_height) / 2;
 
         // Copy the resized image onto the new image
         image::imageops::overlay(&mut new_image, &resized, x as i64, y as i64);
@@ -0,0 +1,79 @@
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::time::{interval, MissedTickBehavior};
+
+/// Checks too slow to run inside a frame, e.g. network requests that may time out.
+pub(crate) trait Worker: Send + 'static {
+    type Output: Clone + Send + 'static;
+
+    /// Runs whatever is due and returns every result so far.
+    fn run(&mut self) -> impl Future<Output = Self::Output> + Send;
+}
+
+/// Runs a [`Worker`] in a task of its own so collecting never waits on it: [`Background::latest`]
+/// returns the results of its last run straight away.
+pub(crate) struct Background<W: Worker> {
+    /// Taken when the task is spawned, on the first call to `latest`.
+    worker: Option<W>,
+    tick: Duration,
+    latest: Arc<Mutex<W::Output>>,
+}
+
+impl<W: Worker> Background<W> {
+    /// `initial` is returned until the first run finishes; the worker then runs every `tick`.
+    pub fn new(worker: W, initial: W::Output, tick: Duration) -> Self {
+        Self {
+            worker: Some(worker),
+            tick,
+            latest: Arc::new(Mutex::new(initial)),
+        }
+    }
+
+    pub fn latest(&mut self) -> W::Output {
+        if let Some(mut worker) = self.worker.take() {
+            // The task stops once the collector holding the results is dropped
+            let (latest, tick) = (Arc::downgrade(&self.latest), self.tick);
+            tokio::spawn(async move {
+                let mut ticks = interval(tick);
+                ticks.set_missed_tick_behavior(MissedTickBehavior::Delay);
+                loop {
+                    ticks.tick().await;
+                    let output = worker.run().await;
+                    match latest.upgrade() {
+                        Some(latest) => *latest.lock().unwrap() = output,
+                        None => break,
+                    }
+                }
+            });
+        }
+        self.latest.lock().unwrap().clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::time::sleep;
+
+    /// Counts its runs, taking a while for each.
+    struct Slow(u32);
+
+    impl Worker for Slow {
+        type Output = u32;
+
+        async fn run(&mut self) -> u32 {
+            sleep(Duration::from_millis(50)).await;
+            self.0 += 1;
+            self.0
+        }
+    }
+
+    #[tokio::test]
+    async fn test_latest() {
+        let mut background = Background::new(Slow(0), 0, Duration::from_millis(10));
+        assert_eq!(background.latest(), 0);
+        sleep(Duration::from_millis(200)).await;
+        assert!(background.latest() >= 2);
+    }
+}
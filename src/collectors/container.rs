@@ -0,0 +1,382 @@
+use crate::collectors::collector::Collector;
+use crate::collectors::process::cgroup_name;
+use crate::config::ContainersConfig;
+use crate::models::container::{CgroupKind, ContainerInfo};
+use crate::models::SystemInfo;
+use crate::utils::{cgroup, rate};
+use crate::utils::rate::RateCounter;
+use log::{debug, warn};
+use regex::Regex;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::LazyLock;
+use std::time::{Duration, Instant};
+use systemstat::ByteSize;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::UnixStream;
+use walkdir::WalkDir;
+
+/// `docker-<id>.scope` (systemd driver) or `<id>` below a `docker` cgroup (cgroupfs driver),
+/// `libpod-<id>.scope`, `crio-<id>.scope` and `cri-containerd-<id>.scope`.
+static CONTAINER_SCOPE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^(?:(docker|libpod|crio|cri-containerd)-)?([0-9a-f]{64})(?:\.scope)?$").unwrap()
+});
+
+/// Deep enough for rootless Podman:
+/// `user.slice/user-1000.slice/user@1000.service/user.slice/libpod-<id>.scope`.
+const MAX_DEPTH: usize = 6;
+const API_TIMEOUT: Duration = Duration::from_secs(1);
+
+#[derive(Default)]
+struct CgroupCounters {
+    cpu: RateCounter,
+    read: RateCounter,
+    write: RateCounter,
+}
+
+/// A container or slice found in the cgroup tree.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CgroupEntry {
+    pub path: String,
+    pub kind: CgroupKind,
+    pub id: String,
+}
+
+/// One entry of the Docker `GET /containers/json` response, which Podman serves as well.
+#[derive(Debug, Deserialize)]
+struct ApiContainer {
+    #[serde(rename = "Id")]
+    id: String,
+    #[serde(rename = "Names", default)]
+    names: Vec<String>,
+}
+
+pub(crate) struct ContainerCollector {
+    config: ContainersConfig,
+    cgroup_root: PathBuf,
+    counters: HashMap<String, CgroupCounters>,
+    /// Container names by ID, kept across collections.
+    names: HashMap<String, String>,
+}
+
+impl ContainerCollector {
+    pub fn new(config: ContainersConfig) -> Self {
+        Self {
+            config,
+            cgroup_root: cgroup::unified_root(Path::new("/sys/fs/cgroup")),
+            counters: HashMap::new(),
+            names: HashMap::new(),
+        }
+    }
+
+    /// Asks the configured sockets for the names of containers not seen before. Containers none
+    /// of them know keep their short ID, so a missing runtime is not asked again every collection.
+    async fn resolve_names(&mut self, entries: &[CgroupEntry]) {
+        let unknown: Vec<&CgroupEntry> = entries
+            .iter()
+            .filter(|entry| !entry.id.is_empty() && !self.names.contains_key(&entry.id))
+            .collect();
+        if unknown.is_empty() {
+            return;
+        }
+        if self.config.resolve_names {
+            for socket in &self.config.sockets {
+                match container_names(Path::new(socket)).await {
+                    Ok(names) => self.names.extend(names),
+                    // No such runtime on this host
+                    Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+                    Err(e) => warn!("Error listing containers from {}: {}", socket, e),
+                }
+            }
+        }
+        for entry in unknown {
+            if !self.names.contains_key(&entry.id) {
+                self.names.insert(entry.id.clone(), entry.id.chars().take(12).collect());
+            }
+        }
+    }
+}
+
+impl Collector for ContainerCollector {
+    type Config = ContainersConfig;
+    type Output = Vec<ContainerInfo>;
+
+    fn name(&self) -> &'static str {
+        "containers"
+    }
+
+    fn config(&self) -> &ContainersConfig {
+        &self.config
+    }
+
+    async fn collect(&mut self) -> Vec<ContainerInfo> {
+        let start = Instant::now();
+        let entries: Vec<CgroupEntry> = find_cgroups(&self.cgroup_root)
+            .into_iter()
+            .filter(|entry| self.config.slices || entry.kind != CgroupKind::Slice)
+            .collect();
+        self.resolve_names(&entries).await;
+
+        let now = Instant::now();
+        self.counters.retain(|path, _| entries.iter().any(|entry| entry.path == *path));
+        self.names.retain(|id, _| entries.iter().any(|entry| entry.id == *id));
+        let mut containers: Vec<ContainerInfo> = entries
+            .iter()
+            .map(|entry| {
+                let counters = self.counters.entry(entry.path.clone()).or_default();
+                let name = match self.names.get(&entry.id) {
+                    Some(name) => name.clone(),
+                    None => cgroup_name(&entry.path),
+                };
+                read_usage(&self.cgroup_root, entry, name, counters, now)
+            })
+            .collect();
+        containers.sort_by(|a, b| b.cpu_percent.total_cmp(&a.cpu_percent));
+        containers.truncate(self.config.count);
+        debug!("collect_containers took: {} ms", start.elapsed().as_millis());
+        containers
+    }
+
+    fn apply(&self, output: &Vec<ContainerInfo>, info: &mut SystemInfo) {
+        info.containers = output.clone();
+    }
+}
+
+/// Containers and systemd slices in the cgroup v2 tree below `root`.
+pub fn find_cgroups(root: &Path) -> Vec<CgroupEntry> {
+    WalkDir::new(root)
+        .min_depth(1)
+        .max_depth(MAX_DEPTH)
+        .sort_by_file_name()
+        .into_iter()
+        .filter_map(Result::ok)
+        .filter(|entry| entry.file_type().is_dir())
+        .filter_map(|entry| {
+            let path = entry.path().strip_prefix(root).ok()?.to_string_lossy().to_string();
+            let name = entry.file_name().to_string_lossy();
+            if name.ends_with(".slice") {
+                return Some(CgroupEntry { path, kind: CgroupKind::Slice, id: String::new() });
+            }
+            let captures = CONTAINER_SCOPE.captures(&name)?;
+            let kind = match captures.get(1).map(|runtime| runtime.as_str()) {
+                Some("docker") => CgroupKind::Docker,
+                Some("libpod") => CgroupKind::Podman,
+                Some(_) => CgroupKind::Kubernetes,
+                // A bare ID is only a container below `docker/`
+                None if path.starts_with("docker/") => CgroupKind::Docker,
+                None => return None,
+            };
+            Some(CgroupEntry { path, kind, id: captures[2].to_string() })
+        })
+        .collect()
+}
+
+fn read_usage(
+    root: &Path,
+    entry: &CgroupEntry,
+    name: String,
+    counters: &mut CgroupCounters,
+    now: Instant,
+) -> ContainerInfo {
+    let dir = root.join(&entry.path);
+    let read = |file: &str| fs::read_to_string(dir.join(file)).unwrap_or_default();
+    let number = |file: &str| read(file).trim().parse::<u64>().ok();
+
+    let usage_usec = keyed_value(&read("cpu.stat"), "usage_usec").unwrap_or(0);
+    // Microseconds of CPU time per second, as a share of one CPU
+    let cpu_percent = counters.cpu.update(usage_usec, now).unwrap_or(0.0) as f32 / 10_000.0;
+    let memory = number("memory.current").unwrap_or(0);
+    let memory_limit = number("memory.max");
+    let (read_bytes, write_bytes) = parse_io_stat(&read("io.stat"));
+    let read_rate = counters.read.update(read_bytes, now).unwrap_or(0.0) as u64;
+    let write_rate = counters.write.update(write_bytes, now).unwrap_or(0.0) as u64;
+
+    ContainerInfo {
+        path: entry.path.clone(),
+        name,
+        id: entry.id.clone(),
+        kind: entry.kind,
+        cpu_percent,
+        cpu_display: format!("{:.1}%", cpu_percent),
+        memory,
+        memory_limit,
+        memory_display: match memory_limit {
+            Some(limit) => format!("{}/{}", ByteSize::b(memory), ByteSize::b(limit)),
+            None => ByteSize::b(memory).to_string(),
+        },
+        read_rate,
+        write_rate,
+        io_display: rate::bytes_per_second(read_rate + write_rate),
+        pids: number("pids.current").unwrap_or(0),
+    }
+}
+
+/// The value of `key` in a flat-keyed file such as `cpu.stat` (`usage_usec 1234`).
+fn keyed_value(text: &str, key: &str) -> Option<u64> {
+    text.lines().find_map(|line| {
+        let (name, value) = line.split_once(' ')?;
+        (name == key).then(|| value.trim().parse().ok()).flatten()
+    })
+}
+
+/// Bytes read and written over all devices in `io.stat` (`8:0 rbytes=1 wbytes=2 rios=3 ...`).
+fn parse_io_stat(text: &str) -> (u64, u64) {
+    let mut totals = (0, 0);
+    for field in text.split_whitespace() {
+        let Some((key, value)) = field.split_once('=') else {
+            continue;
+        };
+        let value = value.parse::<u64>().unwrap_or(0);
+        match key {
+            "rbytes" => totals.0 += value,
+            "wbytes" => totals.1 += value,
+            _ => {}
+        }
+    }
+    totals
+}
+
+/// Container names by ID from a Docker-compatible API socket.
+pub async fn container_names(socket: &Path) -> io::Result<HashMap<String, String>> {
+    let body = tokio::time::timeout(API_TIMEOUT, get(socket, "/containers/json"))
+        .await
+        .map_err(|_| io::Error::new(io::ErrorKind::TimedOut, "no response"))??;
+    let containers: Vec<ApiContainer> = serde_json::from_str(&body)?;
+    Ok(containers
+        .into_iter()
+        .filter_map(|container| {
+            let name = container.names.first()?.trim_start_matches('/').to_string();
+            Some((container.id, name))
+        })
+        .collect())
+}
+
+/// Body of an HTTP/1.0 GET over a unix socket; 1.0 keeps the response unchunked.
+async fn get(socket: &Path, path: &str) -> io::Result<String> {
+    let mut stream = UnixStream::connect(socket).await?;
+    let request = format!("GET {} HTTP/1.0\r\nHost: localhost\r\n\r\n", path);
+    stream.write_all(request.as_bytes()).await?;
+    let mut response = String::new();
+    stream.read_to_string(&mut response).await?;
+
+    let (head, body) = response
+        .split_once("\r\n\r\n")
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "incomplete response"))?;
+    let status = head.lines().next().unwrap_or_default();
+    if status.split_whitespace().nth(1) != Some("200") {
+        return Err(io::Error::other(status.to_string()));
+    }
+    Ok(body.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::testing::write;
+    use crate::config::ContainersConfig;
+    use tokio::net::UnixListener;
+
+    const DOCKER_ID: &str = "1a2b3c4d5e6f1a2b3c4d5e6f1a2b3c4d5e6f1a2b3c4d5e6f1a2b3c4d5e6f1a2b";
+    const PODMAN_ID: &str = "9f8e7d6c5b4a9f8e7d6c5b4a9f8e7d6c5b4a9f8e7d6c5b4a9f8e7d6c5b4a9f8e";
+    const USER_SERVICE: &str = "user.slice/user-1000.slice/user@1000.service";
+
+    /// A Docker container under `system.slice` and a rootless Podman one under a user session.
+    fn cgroup_tree(root: &Path) -> PathBuf {
+        let cgroups = root.join("cgroup");
+        let docker = cgroups.join(format!("system.slice/docker-{}.scope", DOCKER_ID));
+        write(&docker, "cpu.stat", "usage_usec 1000000\nuser_usec 800000\n");
+        write(&docker, "memory.current", "52428800\n");
+        write(&docker, "memory.max", "max\n");
+        write(&docker, "io.stat", "8:0 rbytes=4096 wbytes=8192 rios=1 wios=2\n");
+        write(&docker, "pids.current", "7\n");
+        let podman = cgroups.join(format!("{}/user.slice/libpod-{}.scope", USER_SERVICE, PODMAN_ID));
+        write(&podman, "memory.max", "2000000000\n");
+        write(&podman, "memory.current", "3000000\n");
+        let conmon = cgroups.join(format!("system.slice/libpod-conmon-{}.scope", PODMAN_ID));
+        fs::create_dir_all(conmon).unwrap();
+        fs::create_dir_all(cgroups.join("system.slice/sshd.service")).unwrap();
+        cgroups
+    }
+
+    fn collector(cgroups: &Path, sockets: Vec<String>) -> ContainerCollector {
+        let mut collector =
+            ContainerCollector::new(ContainersConfig { slices: false, sockets, ..ContainersConfig::default() });
+        collector.cgroup_root = cgroups.to_path_buf();
+        collector
+    }
+
+    #[test]
+    fn test_find_cgroups() {
+        let root = tempfile::tempdir().unwrap();
+        let cgroups = cgroup_tree(root.path());
+        let paths: Vec<(String, CgroupKind)> =
+            find_cgroups(&cgroups).into_iter().map(|entry| (entry.path, entry.kind)).collect();
+        assert_eq!(
+            paths,
+            vec![
+                ("system.slice".to_string(), CgroupKind::Slice),
+                (format!("system.slice/docker-{}.scope", DOCKER_ID), CgroupKind::Docker),
+                ("user.slice".to_string(), CgroupKind::Slice),
+                ("user.slice/user-1000.slice".to_string(), CgroupKind::Slice),
+                (format!("{}/user.slice", USER_SERVICE), CgroupKind::Slice),
+                (format!("{}/user.slice/libpod-{}.scope", USER_SERVICE, PODMAN_ID), CgroupKind::Podman),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_container_names() {
+        let root = tempfile::tempdir().unwrap();
+        let cgroups = cgroup_tree(root.path());
+
+        // Stand-in for the Docker API; only the Docker container is known to it
+        let socket = root.path().join("docker.sock");
+        let listener = UnixListener::bind(&socket).unwrap();
+        tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut request = [0; 1024];
+            let _ = stream.read(&mut request).await.unwrap();
+            let body = format!(r#"[{{"Id":"{}","Names":["/web"],"Image":"nginx"}}]"#, DOCKER_ID);
+            let response = format!("HTTP/1.0 200 OK\r\nContent-Type: application/json\r\n\r\n{}", body);
+            stream.write_all(response.as_bytes()).await.unwrap();
+        });
+
+        let sockets = vec![socket.to_string_lossy().to_string(), "/nonexistent/podman.sock".to_string()];
+        let mut collector = collector(&cgroups, sockets);
+        let containers = collector.collect().await;
+        let names: Vec<&str> = containers.iter().map(|container| container.name.as_str()).collect();
+        assert_eq!(names, vec!["web", "9f8e7d6c5b4a"]);
+
+        // Names of removed containers are dropped
+        let podman = cgroups.join(format!("{}/user.slice/libpod-{}.scope", USER_SERVICE, PODMAN_ID));
+        fs::remove_dir_all(podman).unwrap();
+        assert_eq!(collector.collect().await.len(), 1);
+        assert!(!collector.names.contains_key(PODMAN_ID));
+        assert_eq!(collector.names.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_container_usage() {
+        let root = tempfile::tempdir().unwrap();
+        let cgroups = cgroup_tree(root.path());
+        let mut collector = collector(&cgroups, Vec::new());
+        let containers = collector.collect().await;
+        assert_eq!(containers.len(), 2);
+        let docker = containers.iter().find(|c| c.kind == CgroupKind::Docker).unwrap();
+        assert_eq!((docker.pids, docker.memory_limit), (7, None));
+        assert_eq!(docker.memory_display, "52.4 MB");
+        let pod = containers.iter().find(|c| c.kind == CgroupKind::Podman).unwrap();
+        assert_eq!(pod.memory_display, "3.0 MB/2.0 GB");
+
+        // Rates need a second sample
+        let docker = cgroups.join(format!("system.slice/docker-{}.scope", DOCKER_ID));
+        write(&docker, "cpu.stat", "usage_usec 3000000\n");
+        write(&docker, "io.stat", "8:0 rbytes=4096 wbytes=8192\n8:16 rbytes=100 wbytes=0\n");
+        let containers = collector.collect().await;
+        assert!(containers[0].cpu_percent > 0.0);
+        assert_eq!(containers[0].name, "1a2b3c4d5e6f");
+    }
+}
@@ -1,32 +1,291 @@
+use crate::collectors::collector::Collector;
+use crate::config::CollectorConfig;
+use crate::models::cpu::{CoreInfo, CpuInfo};
+use crate::models::SystemInfo;
+use crate::utils::hwmon::get_file_line;
 use log::{debug, error};
-use std::time::{Instant};
-use sysinfo::{System as SysInfo};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+use sysinfo::{CpuRefreshKind, RefreshKind, System as SysInfo};
 use systemstat::{Platform, System};
 
-pub async fn collect_load_aggregate(sys: &System) -> f32 {
-    let start = Instant::now();
-    let result = match sys.cpu_load_aggregate() {
-        Ok(cpu) => {
-            // Use tokio's sleep instead of thread::sleep
-            //time::sleep(Duration::from_secs(1)).await;
-            tokio::time::sleep(sysinfo::MINIMUM_CPU_UPDATE_INTERVAL).await;
-            match cpu.done() {
-                Ok(cpu) => 1.0 - cpu.idle,
-                Err(x) => {
-                    error!("CPU load measurement error: {}", x);
-                    0.0
+/// Time counters of one `cpu` line in `/proc/stat`, in clock ticks.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct CpuTimes {
+    pub user: u64,
+    pub nice: u64,
+    pub system: u64,
+    pub idle: u64,
+    pub iowait: u64,
+    pub irq: u64,
+    pub softirq: u64,
+    pub steal: u64,
+}
+
+impl CpuTimes {
+    pub fn total(&self) -> u64 {
+        self.user + self.nice + self.system + self.idle + self.iowait + self.irq + self.softirq + self.steal
+    }
+}
+
+/// Share of time (0-1) spent in each state between two samples.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct CpuUsage {
+    /// Everything but idle and iowait.
+    pub busy: f32,
+    pub user: f32,
+    pub system: f32,
+    pub iowait: f32,
+    pub steal: f32,
+}
+
+impl CpuUsage {
+    pub fn between(previous: &CpuTimes, current: &CpuTimes) -> Self {
+        let total = current.total().saturating_sub(previous.total());
+        if total == 0 {
+            return Self::default();
+        }
+        let share = |now: u64, before: u64| now.saturating_sub(before) as f32 / total as f32;
+        let idle = share(current.idle, previous.idle);
+        let iowait = share(current.iowait, previous.iowait);
+        Self {
+            busy: (1.0 - idle - iowait).clamp(0.0, 1.0),
+            user: share(current.user + current.nice, previous.user + previous.nice),
+            system: share(
+                current.system + current.irq + current.softirq,
+                previous.system + previous.irq + previous.softirq,
+            ),
+            iowait,
+            steal: share(current.steal, previous.steal),
+        }
+    }
+}
+
+/// Frequencies of one core in MHz.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct CoreFrequency {
+    pub id: usize,
+    pub current: f32,
+    pub min: f32,
+    pub max: f32,
+}
+
+/// `/proc/stat` counters: the aggregate `cpu` line and one entry per `cpuN` line.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CpuStat {
+    pub total: CpuTimes,
+    pub cores: Vec<(usize, CpuTimes)>,
+}
+
+pub(crate) struct CpuCollector {
+    config: CollectorConfig,
+    sys_stat: System,
+    /// Kept between runs for the frequency fallback and the core count.
+    sys_info: SysInfo,
+    /// Where `cpuN/cpufreq` lives, `/sys/devices/system/cpu`.
+    cpu_root: PathBuf,
+    /// Previous `/proc/stat` sample, usage is measured against it.
+    previous: Option<CpuStat>,
+}
+
+impl CpuCollector {
+    pub fn new(config: CollectorConfig) -> Self {
+        Self {
+            config,
+            sys_stat: System::new(),
+            sys_info: SysInfo::new_with_specifics(
+                RefreshKind::nothing().with_cpu(CpuRefreshKind::everything()),
+            ),
+            cpu_root: PathBuf::from("/sys/devices/system/cpu"),
+            previous: None,
+        }
+    }
+}
+
+impl Collector for CpuCollector {
+    type Config = CollectorConfig;
+    type Output = CpuInfo;
+
+    fn name(&self) -> &'static str {
+        "cpu"
+    }
+
+    fn config(&self) -> &CollectorConfig {
+        &self.config
+    }
+
+    async fn collect(&mut self) -> CpuInfo {
+        self.sys_info.refresh_cpu_all();
+
+        let (stat, cpu_temp, cpu_freq, cpu_count) = tokio::join!(
+            collect_stat(),
+            collect_temperature(&self.sys_stat),
+            collect_frequency(&self.sys_info),
+            collect_count(&self.sys_info)
+        );
+        let cpu_freq = cpu_freq as f32;
+
+        // Usage since the previous run, sysinfo's aggregate if /proc/stat can't be read
+        let previous = std::mem::replace(&mut self.previous, stat.clone());
+        let (usage, core_usage) = match (&previous, &stat) {
+            (Some(previous), Some(current)) => usage_between(previous, current),
+            _ => (
+                CpuUsage {
+                    busy: self.sys_info.global_cpu_usage() / 100.0,
+                    ..CpuUsage::default()
+                },
+                Vec::new(),
+            ),
+        };
+
+        let fallback: Vec<u64> = self.sys_info.cpus().iter().map(|cpu| cpu.frequency()).collect();
+        let frequencies = read_core_frequencies(&self.cpu_root, &fallback);
+        let cores: Vec<CoreInfo> = stat
+            .map(|stat| stat.cores)
+            .unwrap_or_default()
+            .iter()
+            .map(|(id, _)| {
+                let usage = core_usage
+                    .iter()
+                    .find(|(core, _)| core == id)
+                    .map(|(_, usage)| *usage)
+                    .unwrap_or_default();
+                let freq = frequencies.iter().find(|f| f.id == *id).map(|f| f.current).unwrap_or(0.0);
+                CoreInfo {
+                    id: *id,
+                    percent: usage.busy,
+                    user: usage.user,
+                    system: usage.system,
+                    iowait: usage.iowait,
+                    steal: usage.steal,
+                    freq,
+                    freq_display: format!("{:.1} GHz", freq / 1000.0),
                 }
-            }
+            })
+            .collect();
+
+        let min_freq = frequencies.iter().map(|f| f.min).filter(|f| *f > 0.0).reduce(f32::min).unwrap_or(0.0);
+        let max_freq = frequencies.iter().map(|f| f.max).reduce(f32::max).unwrap_or(0.0);
+
+        CpuInfo {
+            percent: usage.busy,
+            percent_display: format!("{:.1}%", usage.busy * 100.0),
+            user: usage.user,
+            system: usage.system,
+            iowait: usage.iowait,
+            steal: usage.steal,
+            freq: cpu_freq,
+            freq_display: format!("{:.1} GHz", cpu_freq / 1000.0),
+            min_freq,
+            max_freq,
+            freq_range_display: if min_freq > 0.0 {
+                format!("{:.1}-{:.1} GHz", min_freq / 1000.0, max_freq / 1000.0)
+            } else {
+                format!("{:.1} GHz", max_freq / 1000.0)
+            },
+            count: cpu_count,
+            count_display: cpu_count.to_string(),
+            cpu_temp,
+            cpu_temp_display: format!("{:.1} °C", cpu_temp),
+            cores,
+        }
+    }
+
+    fn apply(&self, output: &CpuInfo, info: &mut SystemInfo) {
+        info.cpu = output.clone();
+    }
+}
+
+/// Aggregate and per-core usage between two `/proc/stat` samples.
+pub fn usage_between(previous: &CpuStat, current: &CpuStat) -> (CpuUsage, Vec<(usize, CpuUsage)>) {
+    let cores = current
+        .cores
+        .iter()
+        .map(|(id, times)| {
+            let usage = previous
+                .cores
+                .iter()
+                .find(|(core, _)| core == id)
+                .map(|(_, before)| CpuUsage::between(before, times))
+                .unwrap_or_default();
+            (*id, usage)
+        })
+        .collect();
+    (CpuUsage::between(&previous.total, &current.total), cores)
+}
+
+/// Parses the `cpu` lines of `/proc/stat`.
+pub fn parse_proc_stat(text: &str) -> CpuStat {
+    let mut stat = CpuStat::default();
+    for line in text.lines() {
+        let mut fields = line.split_whitespace();
+        let Some(name) = fields.next() else {
+            continue;
+        };
+        let Some(id) = name.strip_prefix("cpu") else {
+            continue;
+        };
+        let values: Vec<u64> = fields.map(|f| f.parse().unwrap_or(0)).collect();
+        let value = |i: usize| values.get(i).copied().unwrap_or(0);
+        let times = CpuTimes {
+            user: value(0),
+            nice: value(1),
+            system: value(2),
+            idle: value(3),
+            iowait: value(4),
+            irq: value(5),
+            softirq: value(6),
+            steal: value(7),
+        };
+        if id.is_empty() {
+            stat.total = times;
+        } else if let Ok(id) = id.parse::<usize>() {
+            stat.cores.push((id, times));
         }
+    }
+    stat
+}
+
+pub async fn collect_stat() -> Option<CpuStat> {
+    let start = Instant::now();
+    let result = match fs::read_to_string("/proc/stat") {
+        Ok(text) => Some(parse_proc_stat(&text)),
         Err(x) => {
-            error!("CPU load: error: {}", x);
-            0.0
+            error!("CPU stat: error: {}", x);
+            None
         }
     };
-    debug!(
-        "collect_load_aggregate took: {} ms",
-        start.elapsed().as_millis()
-    );
+    debug!("collect_stat took: {} ms", start.elapsed().as_millis());
+    result
+}
+
+/// Reads the frequencies of cores `0..fallback.len()` from `cpuN/cpufreq` under `cpu_root`
+/// (`/sys/devices/system/cpu`). `fallback` holds each core's current frequency in MHz as reported
+/// by sysinfo, used where cpufreq is not available (e.g. in virtual machines).
+pub fn read_core_frequencies(cpu_root: &Path, fallback: &[u64]) -> Vec<CoreFrequency> {
+    let start = Instant::now();
+    let mhz = |path: PathBuf| {
+        get_file_line(&path, 16)
+            .and_then(|value| value.parse::<f32>().ok())
+            .map(|khz| khz / 1000.0)
+    };
+
+    let result = fallback
+        .iter()
+        .enumerate()
+        .map(|(id, fallback)| {
+            let cpufreq = cpu_root.join(format!("cpu{}", id)).join("cpufreq");
+            let current = mhz(cpufreq.join("scaling_cur_freq")).unwrap_or(*fallback as f32);
+            CoreFrequency {
+                id,
+                current,
+                min: mhz(cpufreq.join("cpuinfo_min_freq")).unwrap_or(0.0),
+                max: mhz(cpufreq.join("cpuinfo_max_freq")).unwrap_or(current),
+            }
+        })
+        .collect();
+    debug!("read_core_frequencies took: {} ms", start.elapsed().as_millis());
     result
 }
 
@@ -46,7 +305,7 @@ pub async fn collect_temperature(sys: &System) -> f32 {
 pub async fn collect_frequency(sys: &SysInfo) -> u64 {
     let start = Instant::now();
     //let result = sys.global_cpu_info().frequency();
-    let result = sys.cpus().first().unwrap().frequency();
+    let result = sys.cpus().first().map(|cpu| cpu.frequency()).unwrap_or(0);
     debug!("collect_frequency took: {} ms", start.elapsed().as_millis());
     result
 }
@@ -57,3 +316,59 @@ pub async fn collect_count(sys: &SysInfo) -> u64 {
     debug!("collect_count took: {} ms", start.elapsed().as_millis());
     result
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn test_per_core_usage() {
+        let before = parse_proc_stat(
+            "cpu  400 0 200 1300 100 0 0 0 0 0\n\
+             cpu0 300 0 100 500 100 0 0 0 0 0\n\
+             cpu1 100 0 100 800 0 0 0 0 0 0\n\
+             intr 185645 0 0\n",
+        );
+        let after = parse_proc_stat(
+            "cpu  1000 0 300 1600 200 0 0 100 0 0\n\
+             cpu0 800 0 150 500 200 0 0 50 0 0\n\
+             cpu1 200 0 150 1100 0 0 0 50 0 0\n",
+        );
+        assert_eq!(after.cores.len(), 2);
+        assert_eq!(after.total.steal, 100);
+
+        let (total, cores) = usage_between(&before, &after);
+        assert!((total.busy - 800.0 / 1200.0).abs() < 1e-6);
+        assert!((total.steal - 100.0 / 1200.0).abs() < 1e-6);
+
+        // cpu0: 700 ticks, 500 user, 50 system, 100 iowait, 50 steal
+        let (id, core) = cores[0];
+        assert_eq!(id, 0);
+        assert!((core.user - 500.0 / 700.0).abs() < 1e-6);
+        assert!((core.iowait - 100.0 / 700.0).abs() < 1e-6);
+        assert!((core.busy - 600.0 / 700.0).abs() < 1e-6);
+        // cpu1: mostly idle
+        assert!((cores[1].1.busy - 0.4).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_read_core_frequencies() {
+        let root = tempfile::tempdir().unwrap();
+        let cpufreq = root.path().join("cpu0/cpufreq");
+        fs::create_dir_all(&cpufreq).unwrap();
+        fs::write(cpufreq.join("scaling_cur_freq"), "3400000\n").unwrap();
+        fs::write(cpufreq.join("cpuinfo_min_freq"), "400000\n").unwrap();
+        fs::write(cpufreq.join("cpuinfo_max_freq"), "5100000\n").unwrap();
+
+        // cpu1 has no cpufreq directory, the fallback is used
+        let frequencies = read_core_frequencies(root.path(), &[1000, 2000]);
+        assert_eq!(
+            frequencies,
+            vec![
+                CoreFrequency { id: 0, current: 3400.0, min: 400.0, max: 5100.0 },
+                CoreFrequency { id: 1, current: 2000.0, min: 0.0, max: 2000.0 },
+            ]
+        );
+    }
+}
@@ -0,0 +1,481 @@
+use crate::collectors::background::{Background, Worker};
+use crate::collectors::collector::Collector;
+use crate::config::{CollectorConfig, CustomMetricConfig, CustomSource};
+use crate::models::custom::CustomMetric;
+use crate::models::SystemInfo;
+use futures::future::join_all;
+use indexmap::IndexMap;
+use log::debug;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+/// Formats asked for when scraping; exporters answer with whichever they support.
+const SCRAPE_ACCEPT: &str =
+    "application/openmetrics-text;version=1.0.0,text/plain;version=0.0.4;q=0.5,*/*;q=0.1";
+
+/// One line of a Prometheus or OpenMetrics text page, e.g. `queue_messages{queue="orders"} 42`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Sample {
+    pub name: String,
+    pub labels: Vec<(String, String)>,
+    pub value: f64,
+}
+
+/// One configured metric and its latest value.
+struct Metric {
+    config: CustomMetricConfig,
+    /// `labels` split into names and values.
+    matchers: Vec<(String, String)>,
+    last_run: Option<Instant>,
+    /// Previous counter value, for `rate`.
+    previous: Option<(f64, Instant)>,
+    info: CustomMetric,
+}
+
+impl Metric {
+    fn due(&self, now: Instant) -> bool {
+        self.last_run
+            .is_none_or(|last| now.saturating_duration_since(last) >= Duration::from_secs(self.config.interval))
+    }
+
+    /// Records a fetched value, or why the fetch failed.
+    fn record(&mut self, result: Result<f64, String>, now: Instant) {
+        let config = &self.config;
+        let result = match result {
+            Ok(value) if config.rate => {
+                rate(&mut self.previous, value, now).ok_or_else(|| "pending".to_string())
+            }
+            result => result,
+        }
+        .map(|value| value * config.scale);
+
+        let info = &mut self.info;
+        info.value = result.as_ref().ok().copied();
+        info.display = match &result {
+            Ok(value) => format!("{}{}", format_value(*value), config.unit),
+            Err(message) => message.clone(),
+        };
+        info.percent = info
+            .value
+            .filter(|_| config.max > 0.0)
+            .map(|value| (value / config.max).clamp(0.0, 1.0) as f32);
+    }
+}
+
+/// Every configured metric, fetched in the background so that slow services never hold up a frame.
+struct CustomMetrics {
+    metrics: IndexMap<String, Metric>,
+}
+
+impl CustomMetrics {
+    fn new(metrics: IndexMap<String, CustomMetricConfig>) -> Self {
+        let metrics = metrics
+            .into_iter()
+            .map(|(name, config)| {
+                let matchers = config.labels.iter().filter_map(|label| parse_matcher(label)).collect();
+                let label = match config.label.as_str() {
+                    "" => name.clone(),
+                    label => label.to_string(),
+                };
+                let metric = Metric {
+                    config,
+                    matchers,
+                    last_run: None,
+                    previous: None,
+                    info: CustomMetric { label, display: "pending".to_string(), ..CustomMetric::default() },
+                };
+                (name, metric)
+            })
+            .collect();
+        Self { metrics }
+    }
+
+    fn results(&self) -> IndexMap<String, CustomMetric> {
+        self.metrics.iter().map(|(name, metric)| (name.clone(), metric.info.clone())).collect()
+    }
+}
+
+impl Worker for CustomMetrics {
+    type Output = IndexMap<String, CustomMetric>;
+
+    /// Fetches the metrics that are due; the others keep their last value.
+    async fn run(&mut self) -> IndexMap<String, CustomMetric> {
+        let now = Instant::now();
+        let due: Vec<&mut Metric> = self.metrics.values_mut().filter(|metric| metric.due(now)).collect();
+
+        // Each page is scraped once, however many metrics read from it
+        let mut pages: IndexMap<&str, Duration> = IndexMap::new();
+        for metric in due.iter().filter(|metric| metric.config.source == CustomSource::Scrape) {
+            let limit = pages.entry(metric.config.url.as_str()).or_default();
+            *limit = (*limit).max(Duration::from_secs(metric.config.timeout.max(1)));
+        }
+        let scrapes = pages.iter().map(|(url, limit)| fetch(url.to_string(), None, *limit));
+        let pages: HashMap<String, Result<Vec<Sample>, String>> = pages
+            .keys()
+            .map(|url| url.to_string())
+            .zip(join_all(scrapes).await)
+            .map(|(url, page)| (url, page.map(|page| parse_exposition(&page))))
+            .collect();
+
+        let queries = due.into_iter().map(|metric| {
+            let pages = &pages;
+            async move {
+                metric.last_run = Some(now);
+                let config = &metric.config;
+                let result = match config.source {
+                    CustomSource::Scrape => pages[&config.url]
+                        .clone()
+                        .and_then(|samples| select(&samples, &config.metric, &metric.matchers)),
+                    CustomSource::Promql => query(config).await,
+                };
+                debug!("custom metric {} ({}): {:?}", metric.info.label, config.url, result);
+                metric.record(result, now);
+            }
+        });
+        join_all(queries).await;
+
+        debug!("collect_custom took: {} ms", now.elapsed().as_millis());
+        self.results()
+    }
+}
+
+/// Values from other services: samples scraped from Prometheus text endpoints, or PromQL queries.
+pub(crate) struct CustomCollector {
+    config: CollectorConfig,
+    metrics: Background<CustomMetrics>,
+}
+
+impl CustomCollector {
+    pub fn new(config: CollectorConfig, metrics: IndexMap<String, CustomMetricConfig>) -> Self {
+        let metrics = CustomMetrics::new(metrics);
+        let initial = metrics.results();
+        let tick = Duration::from_secs(config.interval.max(1));
+        Self { config, metrics: Background::new(metrics, initial, tick) }
+    }
+}
+
+impl Collector for CustomCollector {
+    type Config = CollectorConfig;
+    type Output = IndexMap<String, CustomMetric>;
+
+    fn name(&self) -> &'static str {
+        "custom"
+    }
+
+    fn config(&self) -> &CollectorConfig {
+        &self.config
+    }
+
+    /// The values of the last background fetch.
+    async fn collect(&mut self) -> IndexMap<String, CustomMetric> {
+        self.metrics.latest()
+    }
+
+    fn apply(&self, output: &IndexMap<String, CustomMetric>, info: &mut SystemInfo) {
+        info.custom = output.clone();
+    }
+}
+
+/// The value of an instant PromQL query, added up over every series it returns.
+async fn query(config: &CustomMetricConfig) -> Result<f64, String> {
+    let url = format!("{}/api/v1/query", config.url.trim_end_matches('/'));
+    let limit = Duration::from_secs(config.timeout.max(1));
+    let body = fetch(url, Some(config.query.clone()), limit).await?;
+    parse_query_result(&body)
+}
+
+/// Body of a GET, with `query` as its `query` parameter.
+async fn fetch(url: String, query: Option<String>, limit: Duration) -> Result<String, String> {
+    tokio::task::spawn_blocking(move || {
+        let agent = ureq::AgentBuilder::new().timeout(limit).build();
+        let request = match &query {
+            Some(query) => agent.get(&url).query("query", query),
+            None => agent.get(&url).set("Accept", SCRAPE_ACCEPT),
+        };
+        match request.call() {
+            Ok(response) => response.into_string().map_err(|e| e.kind().to_string()),
+            Err(ureq::Error::Status(status, _)) => Err(format!("HTTP {}", status)),
+            Err(ureq::Error::Transport(transport)) => Err(transport.kind().to_string()),
+        }
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+/// The samples of a Prometheus or OpenMetrics text page; comments and malformed lines are skipped.
+pub fn parse_exposition(page: &str) -> Vec<Sample> {
+    page.lines().filter_map(parse_sample).collect()
+}
+
+/// e.g. `http_requests_total{method="post",code="200"} 1027 1395066363000`.
+fn parse_sample(line: &str) -> Option<Sample> {
+    let line = line.trim();
+    if line.is_empty() || line.starts_with('#') {
+        return None;
+    }
+    let name_end = line.find(|c: char| c == '{' || c.is_whitespace())?;
+    let (name, rest) = line.split_at(name_end);
+    let (labels, rest) = match rest.strip_prefix('{') {
+        Some(rest) => parse_labels(rest)?,
+        None => (Vec::new(), rest),
+    };
+    // Whatever follows the value is a timestamp or an OpenMetrics exemplar
+    let value = rest.split_whitespace().next()?.parse().ok()?;
+    Some(Sample {
+        name: name.to_string(),
+        labels,
+        value,
+    })
+}
+
+/// Labels after the opening `{`, and the rest of the line after the closing `}`.
+fn parse_labels(text: &str) -> Option<(Vec<(String, String)>, &str)> {
+    let mut labels = Vec::new();
+    let mut rest = text;
+    loop {
+        rest = rest.trim_start_matches([' ', ',']);
+        if let Some(rest) = rest.strip_prefix('}') {
+            return Some((labels, rest));
+        }
+        let (name, quoted) = rest.split_once('=')?;
+        let quoted = quoted.trim_start().strip_prefix('"')?;
+        let mut value = String::new();
+        let mut chars = quoted.char_indices();
+        let end = loop {
+            match chars.next()? {
+                (i, '"') => break i,
+                (_, '\\') => match chars.next()?.1 {
+                    'n' => value.push('\n'),
+                    escaped => value.push(escaped),
+                },
+                (_, c) => value.push(c),
+            }
+        };
+        labels.push((name.trim().to_string(), value));
+        rest = &quoted[end + 1..];
+    }
+}
+
+/// `queue=orders` or `queue="orders"`.
+fn parse_matcher(label: &str) -> Option<(String, String)> {
+    let (name, value) = label.split_once('=')?;
+    Some((name.trim().to_string(), value.trim().trim_matches('"').to_string()))
+}
+
+/// Sum of the `metric` samples carrying every label in `matchers`.
+pub fn select(samples: &[Sample], metric: &str, matchers: &[(String, String)]) -> Result<f64, String> {
+    let values: Vec<f64> = samples
+        .iter()
+        .filter(|sample| sample.name == metric)
+        .filter(|sample| matchers.iter().all(|matcher| sample.labels.contains(matcher)))
+        .map(|sample| sample.value)
+        .collect();
+    if values.is_empty() {
+        return Err("no sample".to_string());
+    }
+    Ok(values.iter().sum())
+}
+
+/// The value of a `/api/v1/query` response: a scalar, or the sum of an instant vector.
+pub fn parse_query_result(body: &str) -> Result<f64, String> {
+    let json: Value = serde_json::from_str(body).map_err(|_| "invalid response".to_string())?;
+    if json["status"] != "success" {
+        return Err(json["error"].as_str().unwrap_or("query failed").to_string());
+    }
+    // Values are `[timestamp, "value"]` pairs
+    let value = |pair: &Value| pair[1].as_str().and_then(|value| value.parse::<f64>().ok());
+    let data = &json["data"];
+    match data["resultType"].as_str() {
+        Some("scalar") => value(&data["result"]).ok_or_else(|| "invalid response".to_string()),
+        Some("vector") => {
+            let series = data["result"].as_array().map(Vec::as_slice).unwrap_or_default();
+            if series.is_empty() {
+                return Err("no data".to_string());
+            }
+            series
+                .iter()
+                .map(|series| value(&series["value"]))
+                .sum::<Option<f64>>()
+                .ok_or_else(|| "invalid response".to_string())
+        }
+        Some(other) => Err(format!("{} result", other)),
+        None => Err("invalid response".to_string()),
+    }
+}
+
+/// Per-second increase since the previous value; `None` for the first value and after a reset.
+fn rate(previous: &mut Option<(f64, Instant)>, value: f64, now: Instant) -> Option<f64> {
+    let (last_value, last_time) = previous.replace((value, now))?;
+    let seconds = now.saturating_duration_since(last_time).as_secs_f64();
+    (value >= last_value && seconds > 0.0).then(|| (value - last_value) / seconds)
+}
+
+/// Compact value, e.g. `0.25`, `42`, `12.3k` or `1.25M`.
+pub fn format_value(value: f64) -> String {
+    let (value, suffix) = match value.abs() {
+        magnitude if magnitude >= 1e9 => (value / 1e9, "G"),
+        magnitude if magnitude >= 1e6 => (value / 1e6, "M"),
+        magnitude if magnitude >= 1e4 => (value / 1e3, "k"),
+        _ => (value, ""),
+    };
+    let decimals = match value.abs() {
+        _ if value.fract() == 0.0 => 0,
+        magnitude if magnitude < 10.0 => 2,
+        magnitude if magnitude < 100.0 => 1,
+        _ => 0,
+    };
+    format!("{:.*}{}", decimals, value, suffix)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    const PAGE: &str = r#"# HELP queue_messages Messages waiting.
+# TYPE queue_messages gauge
+queue_messages{queue="orders",vhost="/"} 40
+queue_messages{queue="orders",vhost="staging"} 2
+queue_messages{queue="mail \"bulk\"",vhost="/"} 7
+# TYPE http_requests counter
+http_requests_total{code="200"} 1027 1395066363000
+http_requests_total{code="500",} 3
+process_start_time_seconds 1.7e9
+# EOF
+"#;
+
+    /// Stand-in exporter and Prometheus server: `/metrics` serves `PAGE`, `/api/v1/query` the
+    /// results of `up` and `scalar(1)`.
+    async fn serve(listener: TcpListener) {
+        loop {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut request = [0; 2048];
+            let read = stream.read(&mut request).await.unwrap();
+            let request = String::from_utf8_lossy(&request[..read]);
+            let target = request.split_whitespace().nth(1).unwrap_or("").to_string();
+            let (status, body) = match target.as_str() {
+                "/metrics" => ("200 OK", PAGE),
+                "/api/v1/query?query=sum%28up%29" => (
+                    "200 OK",
+                    r#"{"status":"success","data":{"resultType":"vector","result":[
+                        {"metric":{"job":"a"},"value":[1700000000.5,"0.5"]},
+                        {"metric":{"job":"b"},"value":[1700000000.5,"0.25"]}]}}"#,
+                ),
+                "/api/v1/query?query=scalar%281%29" => (
+                    "200 OK",
+                    r#"{"status":"success","data":{"resultType":"scalar","result":[1700000000.5,"1"]}}"#,
+                ),
+                _ => ("404 Not Found", ""),
+            };
+            let response = format!(
+                "HTTP/1.1 {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                status,
+                body.len(),
+                body
+            );
+            stream.write_all(response.as_bytes()).await.unwrap();
+        }
+    }
+
+    #[test]
+    fn test_parse_exposition() {
+        let samples = parse_exposition(PAGE);
+        assert_eq!(samples.len(), 6);
+        assert_eq!(samples[2].labels[0], ("queue".to_string(), "mail \"bulk\"".to_string()));
+        assert_eq!((samples[3].name.as_str(), samples[3].value), ("http_requests_total", 1027.0));
+    }
+
+    #[test]
+    fn test_select() {
+        let samples = parse_exposition(PAGE);
+        let orders = parse_matcher("queue=\"orders\"").unwrap();
+        assert_eq!(select(&samples, "queue_messages", &[orders]), Ok(42.0));
+        assert_eq!(select(&samples, "process_start_time_seconds", &[]), Ok(1.7e9));
+        assert_eq!(select(&samples, "missing", &[]), Err("no sample".to_string()));
+    }
+
+    #[test]
+    fn test_parse_query_result() {
+        assert_eq!(
+            parse_query_result(r#"{"status":"success","data":{"resultType":"scalar","result":[1.5,"2"]}}"#),
+            Ok(2.0)
+        );
+        assert_eq!(
+            parse_query_result(r#"{"status":"error","errorType":"bad_data","error":"parse error"}"#),
+            Err("parse error".to_string())
+        );
+        assert_eq!(
+            parse_query_result(r#"{"status":"success","data":{"resultType":"vector","result":[]}}"#),
+            Err("no data".to_string())
+        );
+    }
+
+    #[test]
+    fn test_format_value() {
+        assert_eq!(format_value(12345.0), "12.3k");
+        assert_eq!(format_value(0.25), "0.25");
+        assert_eq!(format_value(42.0), "42");
+    }
+
+    #[tokio::test]
+    async fn test_custom_metrics() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(serve(listener));
+
+        let scrape = |metric: &str, labels: &[&str]| CustomMetricConfig {
+            url: format!("{}/metrics", url),
+            metric: metric.to_string(),
+            labels: labels.iter().map(|label| label.to_string()).collect(),
+            interval: 0,
+            ..CustomMetricConfig::default()
+        };
+        let promql = |query: &str| CustomMetricConfig {
+            source: CustomSource::Promql,
+            url: format!("{}/", url),
+            query: query.to_string(),
+            interval: 0,
+            ..CustomMetricConfig::default()
+        };
+        let mut metrics = IndexMap::new();
+        metrics.insert("orders".to_string(), scrape("queue_messages", &["queue=\"orders\""]));
+        let requests = CustomMetricConfig {
+            rate: true,
+            unit: " req/s".to_string(),
+            ..scrape("http_requests_total", &[])
+        };
+        metrics.insert("requests".to_string(), requests);
+        let up = CustomMetricConfig {
+            label: "Targets up".to_string(),
+            scale: 100.0,
+            max: 100.0,
+            unit: "%".to_string(),
+            ..promql("sum(up)")
+        };
+        metrics.insert("up".to_string(), up);
+        metrics.insert("one".to_string(), promql("scalar(1)"));
+        let missing = CustomMetricConfig { url: format!("{}/gone", url), ..scrape("x", &[]) };
+        metrics.insert("missing".to_string(), missing);
+
+        let mut worker = CustomMetrics::new(metrics.clone());
+        let first = worker.run().await;
+        assert_eq!((first["orders"].label.as_str(), first["orders"].value), ("orders", Some(42.0)));
+        assert_eq!(first["orders"].percent, None);
+        // A counter needs a second sample for its rate
+        assert_eq!((first["requests"].value, first["requests"].display.as_str()), (None, "pending"));
+        assert_eq!((first["up"].label.as_str(), first["up"].display.as_str()), ("Targets up", "75%"));
+        assert_eq!(first["up"].percent, Some(0.75));
+        assert_eq!(first["one"].value, Some(1.0));
+        assert_eq!((first["missing"].value, first["missing"].display.as_str()), (None, "HTTP 404"));
+
+        let second = worker.run().await;
+        assert_eq!((second["requests"].value, second["requests"].display.as_str()), (Some(0.0), "0 req/s"));
+
+        // The collector answers before the first fetch has finished
+        let mut collector = CustomCollector::new(CollectorConfig::default(), metrics);
+        let pending = collector.collect().await;
+        assert_eq!((pending["up"].label.as_str(), pending["up"].display.as_str()), ("Targets up", "pending"));
+    }
+}
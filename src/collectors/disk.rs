@@ -1,86 +1,539 @@
-use log::{debug, error};
+use crate::collectors::collector::Collector;
+use crate::config::CollectorConfig;
+use crate::models::disk::{DeviceIoInfo, DiskInfo, DiskIoInfo, MountInfo, RaidInfo, ZfsInfo, ZfsPool};
+use crate::models::SystemInfo;
+use crate::utils::rate::{self, RateCounter};
+use futures::future::join_all;
+use log::{debug, error, warn};
+use regex::Regex;
+use std::collections::{HashMap, HashSet};
+use std::ffi::CString;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, LazyLock, Mutex};
 use std::time::{Duration, Instant};
-use systemstat::{ByteSize, Platform, System};
-use tokio::time;
+use systemstat::ByteSize;
+use tokio::time::timeout;
 
-pub async fn collect_io(sys: &System, allowed: &Vec<&str>) -> Vec<ByteSize> {
-    let start = Instant::now();
-    const SECTOR_SIZE: u64 = 512;
-
-    let result = match sys.block_device_statistics() {
-        Ok(initial_stats) => {
-            let mut start_read_sectors = 0u64;
-            let mut start_write_sectors = 0u64;
-
-            for block in initial_stats.values() {
-                if allowed.contains(&block.name.as_str()) {
-                    start_read_sectors =
-                        start_read_sectors.saturating_add(block.read_sectors as u64);
-                    start_write_sectors =
-                        start_write_sectors.saturating_add(block.write_sectors as u64);
-                }
-            }
+const SECTOR_SIZE: u64 = 512;
+/// How long `statvfs` may take before a mount, e.g. an unreachable NFS share, is shown as empty.
+const STATVFS_TIMEOUT: Duration = Duration::from_secs(1);
 
-            // Wait for the specified duration
-            time::sleep(Duration::from_secs(1)).await;
-
-            // Get new statistics after the wait
-            match sys.block_device_statistics() {
-                Ok(final_stats) => {
-                    let mut end_read_sectors = 0u64;
-                    let mut end_write_sectors = 0u64;
-
-                    for block in final_stats.values() {
-                        if allowed.contains(&block.name.as_str()) {
-                            end_read_sectors =
-                                end_read_sectors.saturating_add(block.read_sectors as u64);
-                            end_write_sectors =
-                                end_write_sectors.saturating_add(block.write_sectors as u64);
-                        }
-                    }
-
-                    let read_bytes = end_read_sectors
-                        .saturating_sub(start_read_sectors)
-                        .saturating_mul(SECTOR_SIZE);
-                    let write_bytes = end_write_sectors
-                        .saturating_sub(start_write_sectors)
-                        .saturating_mul(SECTOR_SIZE);
-
-                    vec![ByteSize::b(read_bytes), ByteSize::b(write_bytes)]
-                }
-                Err(x) => {
-                    error!("Block statistics error getting final stats: {}", x);
-                    vec![ByteSize::b(0), ByteSize::b(0)]
-                }
-            }
+/// `[3/2]`: members the array should have, and those working.
+static RAID_MEMBERS: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\[(\d+)/(\d+)\] \[([U_]+)\]").unwrap());
+/// `recovery =  8.5% (83014400/976630272)`.
+static RAID_PROGRESS: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(recovery|resync|check|reshape|repair)\s*=\s*([\d.]+)%").unwrap());
+
+/// Counters of one `/proc/diskstats` line.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DiskStats {
+    pub name: String,
+    pub read_ios: u64,
+    pub read_sectors: u64,
+    pub write_ios: u64,
+    pub write_sectors: u64,
+    /// Milliseconds spent with I/O in flight.
+    pub io_ticks: u64,
+}
+
+/// One line of `/proc/self/mounts`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MountEntry {
+    pub device: String,
+    pub mount_point: String,
+    pub fs_type: String,
+    pub read_only: bool,
+}
+
+/// Block and inode counts from `statvfs`, in bytes and inodes.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct FsUsage {
+    pub total: u64,
+    pub available: u64,
+    pub inodes_total: u64,
+    pub inodes_free: u64,
+}
+
+/// Rate counters kept between runs for one block device.
+#[derive(Debug, Default)]
+struct DeviceRates {
+    read: RateCounter,
+    write: RateCounter,
+    read_ops: RateCounter,
+    write_ops: RateCounter,
+    io_ticks: RateCounter,
+}
+
+pub(crate) struct DiskCollector {
+    config: CollectorConfig,
+    disks: Vec<String>,
+    mount_points: Vec<String>,
+    proc_root: PathBuf,
+    rates: HashMap<String, DeviceRates>,
+    /// Mount points whose `statvfs` has not returned yet.
+    pending: Arc<Mutex<HashSet<String>>>,
+}
+
+impl DiskCollector {
+    pub fn new(config: CollectorConfig, disks: Vec<String>, mount_points: Vec<String>) -> Self {
+        Self {
+            config,
+            disks,
+            mount_points,
+            proc_root: PathBuf::from("/proc"),
+            rates: HashMap::new(),
+            pending: Arc::default(),
+        }
+    }
+
+    /// Per-second rates of each configured device since the previous run, zero until there is one.
+    fn device_rates(&mut self, stats: &[DiskStats]) -> Vec<DeviceIoInfo> {
+        let now = Instant::now();
+        let mut devices = Vec::new();
+        for name in &self.disks {
+            let Some(stat) = stats.iter().find(|stat| &stat.name == name) else {
+                continue;
+            };
+            let rates = self.rates.entry(name.clone()).or_default();
+            let rate = |counter: &mut RateCounter, value: u64| counter.update(value, now).unwrap_or(0.0);
+
+            let read = rate(&mut rates.read, stat.read_sectors.saturating_mul(SECTOR_SIZE)) as u64;
+            let write = rate(&mut rates.write, stat.write_sectors.saturating_mul(SECTOR_SIZE)) as u64;
+            let busy = (rate(&mut rates.io_ticks, stat.io_ticks) / 1000.0).clamp(0.0, 1.0) as f32;
+            devices.push(DeviceIoInfo {
+                name: name.clone(),
+                read,
+                read_display: rate::bytes_per_second(read),
+                write,
+                write_display: rate::bytes_per_second(write),
+                read_ops: rate(&mut rates.read_ops, stat.read_ios) as u64,
+                write_ops: rate(&mut rates.write_ops, stat.write_ios) as u64,
+                busy,
+                busy_display: format!("{:.0}%", busy * 100.0),
+            });
         }
+        devices
+    }
+}
+
+impl Collector for DiskCollector {
+    type Config = CollectorConfig;
+    type Output = (DiskInfo, DiskIoInfo);
+
+    fn name(&self) -> &'static str {
+        "disk"
+    }
+
+    fn config(&self) -> &CollectorConfig {
+        &self.config
+    }
+
+    async fn collect(&mut self) -> (DiskInfo, DiskIoInfo) {
+        let (stats, mounts) = tokio::join!(
+            collect_io(),
+            collect_block_info(&self.proc_root, &self.mount_points, &self.pending)
+        );
+
+        let devices = self.device_rates(&stats);
+        let read = devices.iter().map(|d| d.read).sum();
+        let write = devices.iter().map(|d| d.write).sum();
+        let disk_io = DiskIoInfo {
+            read,
+            read_display: rate::bytes_per_second(read),
+            write,
+            write_display: rate::bytes_per_second(write),
+            read_ops: devices.iter().map(|d| d.read_ops).sum(),
+            write_ops: devices.iter().map(|d| d.write_ops).sum(),
+            busy: devices.iter().map(|d| d.busy).fold(0.0, f32::max),
+            devices,
+        };
+
+        let used: u64 = mounts.iter().map(|m| m.used).sum();
+        let total: u64 = mounts.iter().map(|m| m.total).sum();
+        let block_percent = if total > 0 { used as f32 / total as f32 } else { 0.0 };
+        let disk = DiskInfo {
+            percent: block_percent,
+            percent_display: format!("{:.1}%", block_percent * 100.0),
+            used,
+            used_display: ByteSize::b(used).to_string(),
+            total,
+            total_display: ByteSize::b(total).to_string(),
+            mounts,
+            raid: collect_raid(&self.proc_root),
+            zfs: collect_zfs(&self.proc_root.join("spl/kstat/zfs")),
+        };
+        (disk, disk_io)
+    }
+
+    fn apply(&self, output: &(DiskInfo, DiskIoInfo), info: &mut SystemInfo) {
+        info.disk = output.0.clone();
+        info.disk_io = output.1.clone();
+    }
+}
+
+/// Parses `/proc/diskstats`.
+pub fn parse_diskstats(text: &str) -> Vec<DiskStats> {
+    text.lines()
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            if fields.len() < 13 {
+                return None;
+            }
+            let value = |i: usize| fields[i].parse::<u64>().unwrap_or(0);
+            Some(DiskStats {
+                name: fields[2].to_string(),
+                read_ios: value(3),
+                read_sectors: value(5),
+                write_ios: value(7),
+                write_sectors: value(9),
+                io_ticks: value(12),
+            })
+        })
+        .collect()
+}
+
+/// Counters of every block device so far.
+pub async fn collect_io() -> Vec<DiskStats> {
+    let start = Instant::now();
+    let result = match fs::read_to_string("/proc/diskstats") {
+        Ok(text) => parse_diskstats(&text),
         Err(x) => {
-            error!("Block statistics error getting initial stats: {}", x);
-            vec![ByteSize::b(0), ByteSize::b(0)]
+            error!("Block statistics error: {}", x);
+            Vec::new()
         }
     };
     debug!("collect_io took: {} ms", start.elapsed().as_millis());
     result
 }
 
-pub async fn collect_block_info(disks: &sysinfo::Disks, allowed: &Vec<&str>) -> Vec<ByteSize> {
+/// Space and inodes used on each of the `allowed` mount points that is mounted, in that order.
+pub async fn collect_block_info(
+    proc_root: &Path,
+    allowed: &[String],
+    pending: &Arc<Mutex<HashSet<String>>>,
+) -> Vec<MountInfo> {
     let start = Instant::now();
-    let mut disk_total = 0;
-    let mut disk_used = 0;
-
-    for disk in disks {
-        if let Some(mount_str) = disk.mount_point().to_str() {
-            if !disk.is_removable() && allowed.contains(&mount_str) {
-                disk_total += disk.total_space();
-                disk_used += disk.total_space() - disk.available_space();
-            }
+    let entries = match fs::read_to_string(proc_root.join("self/mounts")) {
+        Ok(text) => parse_mounts(&text),
+        Err(x) => {
+            error!("Mount table error: {}", x);
+            Vec::new()
         }
-    }
+    };
+
+    // The last mount on a path hides the earlier ones
+    let mounted: Vec<&MountEntry> = allowed
+        .iter()
+        .filter_map(|mount_point| entries.iter().rev().find(|entry| &entry.mount_point == mount_point))
+        .collect();
+    let usages = join_all(mounted.iter().map(|entry| usage(&entry.mount_point, pending))).await;
+    let mounts = mounted
+        .into_iter()
+        .zip(usages)
+        .map(|(entry, usage)| mount_info(entry, usage.unwrap_or_default()))
+        .collect();
 
-    let result = vec![ByteSize::b(disk_used), ByteSize::b(disk_total)];
     debug!(
         "collect_block_info took: {} ms",
         start.elapsed().as_millis()
     );
-    result
+    mounts
+}
+
+/// Parses `/proc/self/mounts`, whose fields escape spaces and tabs as `\040` and `\011`.
+pub fn parse_mounts(text: &str) -> Vec<MountEntry> {
+    let unescape = |field: &str| field.replace("\\040", " ").replace("\\011", "\t").replace("\\134", "\\");
+    text.lines()
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            if fields.len() < 4 {
+                return None;
+            }
+            Some(MountEntry {
+                device: unescape(fields[0]),
+                mount_point: unescape(fields[1]),
+                fs_type: fields[2].to_string(),
+                read_only: fields[3].split(',').any(|option| option == "ro"),
+            })
+        })
+        .collect()
+}
+
+/// `statvfs` on a blocking thread, given up on after `STATVFS_TIMEOUT`. A mount whose previous call
+/// is still hanging is not asked again until that call returns.
+async fn usage(mount_point: &str, pending: &Arc<Mutex<HashSet<String>>>) -> Option<FsUsage> {
+    if !pending.lock().unwrap().insert(mount_point.to_string()) {
+        return None;
+    }
+    let (path, done) = (mount_point.to_string(), Arc::clone(pending));
+    let call = tokio::task::spawn_blocking(move || {
+        let usage = statvfs(&path);
+        done.lock().unwrap().remove(&path);
+        usage
+    });
+    match timeout(STATVFS_TIMEOUT, call).await {
+        Ok(usage) => usage.ok().flatten(),
+        Err(_) => {
+            warn!("statvfs on {} did not return within {:?}", mount_point, STATVFS_TIMEOUT);
+            None
+        }
+    }
+}
+
+fn statvfs(path: &str) -> Option<FsUsage> {
+    let path = CString::new(path).ok()?;
+    let mut stat: libc::statvfs = unsafe { std::mem::zeroed() };
+    if unsafe { libc::statvfs(path.as_ptr(), &mut stat) } != 0 {
+        return None;
+    }
+    let fragment = stat.f_frsize as u64;
+    Some(FsUsage {
+        total: stat.f_blocks as u64 * fragment,
+        available: stat.f_bavail as u64 * fragment,
+        inodes_total: stat.f_files as u64,
+        inodes_free: stat.f_ffree as u64,
+    })
+}
+
+pub fn mount_info(entry: &MountEntry, usage: FsUsage) -> MountInfo {
+    let total = usage.total;
+    let used = total.saturating_sub(usage.available);
+    let percent = if total > 0 { used as f32 / total as f32 } else { 0.0 };
+    let inodes_used = usage.inodes_total.saturating_sub(usage.inodes_free);
+    let inodes_percent = if usage.inodes_total > 0 {
+        inodes_used as f32 / usage.inodes_total as f32
+    } else {
+        0.0
+    };
+    MountInfo {
+        mount_point: entry.mount_point.clone(),
+        device: entry.device.clone(),
+        fs_type: entry.fs_type.clone(),
+        read_only: entry.read_only,
+        percent,
+        percent_display: format!("{:.1}%", percent * 100.0),
+        used,
+        used_display: ByteSize::b(used).to_string(),
+        total,
+        total_display: ByteSize::b(total).to_string(),
+        inodes_used,
+        inodes_total: usage.inodes_total,
+        inodes_percent,
+        inodes_display: format!("{:.1}%", inodes_percent * 100.0),
+    }
+}
+
+/// md RAID arrays, empty without the md driver.
+fn collect_raid(proc_root: &Path) -> Vec<RaidInfo> {
+    fs::read_to_string(proc_root.join("mdstat"))
+        .map(|text| parse_mdstat(&text))
+        .unwrap_or_default()
+}
+
+/// Parses `/proc/mdstat`: an `md0 : active raid1 sdb1[1] sda1[0]` line per array, followed by
+/// indented lines with its member status and any running recovery, resync or check.
+pub fn parse_mdstat(text: &str) -> Vec<RaidInfo> {
+    let mut arrays: Vec<RaidInfo> = Vec::new();
+    for line in text.lines() {
+        if let Some((name, description)) = line.split_once(" : ").filter(|(name, _)| name.starts_with("md")) {
+            let mut fields = description.split_whitespace();
+            let active = fields.next() == Some("active");
+            let rest: Vec<&str> = fields.collect();
+            arrays.push(RaidInfo {
+                name: name.trim().to_string(),
+                // Members look like `sda1[0]`; flags like `(auto-read-only)` come before the level
+                level: rest
+                    .iter()
+                    .find(|field| !field.contains('[') && !field.starts_with('('))
+                    .map(|level| level.to_string())
+                    .unwrap_or_default(),
+                active,
+                failed: rest.iter().filter(|field| field.ends_with("(F)")).count() as u32,
+                ..RaidInfo::default()
+            });
+            continue;
+        }
+        let Some(array) = arrays.last_mut().filter(|_| line.starts_with(' ')) else {
+            continue;
+        };
+        if let Some(captures) = RAID_MEMBERS.captures(line) {
+            array.devices = captures[1].parse().unwrap_or(0);
+            array.working = captures[2].parse().unwrap_or(0);
+            array.display = format!("[{}]", &captures[3]);
+        }
+        if let Some(captures) = RAID_PROGRESS.captures(line) {
+            array.action = Some(captures[1].to_string());
+            array.progress = captures[2].parse::<f32>().ok().map(|percent| percent / 100.0);
+        }
+    }
+
+    for array in &mut arrays {
+        if !array.active {
+            array.display = "inactive".to_string();
+        } else if let (Some(action), Some(progress)) = (&array.action, array.progress) {
+            array.display = format!("{} {} {:.1}%", array.display, action, progress * 100.0);
+        }
+    }
+    arrays
+}
+
+/// Pool health and ARC statistics under `kstat_root` (`/proc/spl/kstat/zfs`), `None` without ZFS.
+pub fn collect_zfs(kstat_root: &Path) -> Option<ZfsInfo> {
+    let arcstats = parse_kstat(&fs::read_to_string(kstat_root.join("arcstats")).ok()?);
+
+    // One directory per imported pool, with its health in `state` (OpenZFS 0.8 and later)
+    let mut pools: Vec<ZfsPool> = fs::read_dir(kstat_root)
+        .map(|dir| {
+            dir.flatten()
+                .filter_map(|entry| {
+                    let health = fs::read_to_string(entry.path().join("state")).ok()?;
+                    Some(ZfsPool {
+                        name: entry.file_name().to_string_lossy().to_string(),
+                        health: health.trim().to_string(),
+                    })
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    pools.sort_by(|a, b| a.name.cmp(&b.name));
+
+    let value = |name: &str| arcstats.get(name).copied().unwrap_or(0);
+    let lookups = value("hits") + value("misses");
+    let arc_hit_ratio = if lookups > 0 { value("hits") as f32 / lookups as f32 } else { 0.0 };
+    let (arc_size, arc_max) = (value("size"), value("c_max"));
+    Some(ZfsInfo {
+        pools,
+        arc_size,
+        arc_max,
+        arc_hit_ratio,
+        arc_display: format!(
+            "ARC {}/{} | {:.0}% hit",
+            ByteSize::b(arc_size),
+            ByteSize::b(arc_max),
+            arc_hit_ratio * 100.0
+        ),
+    })
+}
+
+/// Parses a named kstat such as `arcstats`: a header line, a `name type data` line, then one
+/// `name type value` line per statistic.
+pub fn parse_kstat(text: &str) -> HashMap<String, u64> {
+    text.lines()
+        .skip(2)
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            Some((fields.first()?.to_string(), fields.get(2)?.parse().ok()?))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_diskstats() {
+        let stats = parse_diskstats(
+            " 259       0 nvme0n1 48123 12010 3921542 9120 90211 51234 6283104 61822 0 70440 72350 0 0 0 0 2211 1408\n\
+             \x20  8       1 sda1 512 0 4096 100 8 2 80 12 0 96 112\n\
+             \x20  7       0 loop0 bad\n",
+        );
+        assert_eq!(
+            stats,
+            vec![
+                DiskStats {
+                    name: "nvme0n1".to_string(),
+                    read_ios: 48123,
+                    read_sectors: 3921542,
+                    write_ios: 90211,
+                    write_sectors: 6283104,
+                    io_ticks: 70440,
+                },
+                DiskStats {
+                    name: "sda1".to_string(),
+                    read_ios: 512,
+                    read_sectors: 4096,
+                    write_ios: 8,
+                    write_sectors: 80,
+                    io_ticks: 96,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_mounts() {
+        let entries = parse_mounts(include_str!("../../tests/fixtures/disk/mounts"));
+        assert_eq!(entries.len(), 8);
+        let usb = &entries[6];
+        assert_eq!((usb.mount_point.as_str(), usb.fs_type.as_str()), ("/media/usb stick", "vfat"));
+        assert!(usb.read_only);
+    }
+
+    #[test]
+    fn test_mount_info() {
+        let entries = parse_mounts(include_str!("../../tests/fixtures/disk/mounts"));
+        let usage = FsUsage {
+            total: 4_000_000_000,
+            available: 1_000_000_000,
+            inodes_total: 1000,
+            inodes_free: 100,
+        };
+        let root = mount_info(&entries[2], usage);
+        assert_eq!((root.device.as_str(), root.fs_type.as_str()), ("/dev/nvme0n1p2", "ext4"));
+        assert!(!root.read_only);
+        assert_eq!((root.percent, root.used_display.as_str()), (0.75, "3.0 GB"));
+        assert_eq!((root.inodes_used, root.inodes_display.as_str()), (900, "90.0%"));
+    }
+
+    #[tokio::test]
+    async fn test_collect_block_info() {
+        let proc_root = tempfile::tempdir().unwrap();
+        fs::create_dir(proc_root.path().join("self")).unwrap();
+        fs::write(proc_root.path().join("self/mounts"), "/dev/vda1 / ext4 rw,relatime 0 0\n").unwrap();
+        let allowed = ["/".to_string(), "/mnt/nas".to_string()];
+
+        let pending = Arc::default();
+        let mounts = collect_block_info(proc_root.path(), &allowed, &pending).await;
+        assert_eq!(mounts.len(), 1);
+        assert!(mounts[0].total > 0);
+        assert!(pending.lock().unwrap().is_empty());
+
+        // A mount still stuck in an earlier call is shown as empty rather than asked again
+        pending.lock().unwrap().insert("/".to_string());
+        let mounts = collect_block_info(proc_root.path(), &allowed, &pending).await;
+        assert_eq!((mounts[0].device.as_str(), mounts[0].total), ("/dev/vda1", 0));
+    }
+
+    #[test]
+    fn test_parse_mdstat() {
+        let raid = parse_mdstat(include_str!("../../tests/fixtures/disk/mdstat"));
+        assert_eq!(raid.len(), 4);
+        assert_eq!((raid[0].level.as_str(), raid[0].display.as_str()), ("raid1", "[UU]"));
+        assert!(!raid[0].degraded());
+        let md1 = &raid[1];
+        assert!(md1.degraded());
+        assert_eq!((md1.devices, md1.working, md1.failed), (3, 2, 1));
+        assert_eq!((md1.action.as_deref(), md1.display.as_str()), (Some("recovery"), "[U_U] recovery 8.5%"));
+        assert_eq!((raid[2].progress, raid[2].degraded()), (Some(0.672), false));
+        assert_eq!((raid[3].level.as_str(), raid[3].display.as_str()), ("", "inactive"));
+        assert!(!raid[3].active && raid[3].degraded());
+    }
+
+    #[test]
+    fn test_collect_zfs() {
+        let kstat = tempfile::tempdir().unwrap();
+        fs::write(kstat.path().join("arcstats"), include_str!("../../tests/fixtures/disk/arcstats")).unwrap();
+        for (pool, state) in [("tank", "ONLINE\n"), ("backup", "DEGRADED\n")] {
+            fs::create_dir(kstat.path().join(pool)).unwrap();
+            fs::write(kstat.path().join(pool).join("state"), state).unwrap();
+        }
+        let zfs = collect_zfs(kstat.path()).unwrap();
+        assert_eq!(zfs.pools.iter().map(|pool| pool.online()).collect::<Vec<_>>(), vec![false, true]);
+        assert_eq!(zfs.pools[0].health, "DEGRADED");
+        assert_eq!((zfs.arc_size, zfs.arc_hit_ratio), (3_221_225_472, 0.97));
+        assert_eq!(zfs.arc_display, "ARC 3.2 GB/8.6 GB | 97% hit");
+        assert!(collect_zfs(&kstat.path().join("missing")).is_none());
+    }
 }
@@ -0,0 +1,262 @@
+use crate::collectors::collector::Collector;
+use crate::config::CollectorConfig;
+use crate::models::gpu::{GpuInfo, GpuVendor};
+use crate::models::SystemInfo;
+use crate::utils::hwmon::get_file_line;
+use crate::utils::rate::RateCounter;
+use log::debug;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::Instant;
+use systemstat::ByteSize;
+
+/// Counters kept between two runs to turn Intel's cumulative values into rates.
+#[derive(Debug, Default)]
+pub struct GpuCounters {
+    /// RC6 (or xe `gtidle`) residency in milliseconds.
+    idle: RateCounter,
+    /// hwmon `energy1_input` in microjoules.
+    energy: RateCounter,
+}
+
+/// AMD (amdgpu) and Intel (i915, xe) GPUs read from sysfs, alongside `nvidia-smi`.
+pub(crate) struct GpuCollector {
+    config: CollectorConfig,
+    drm_root: PathBuf,
+    counters: HashMap<String, GpuCounters>,
+}
+
+impl GpuCollector {
+    pub fn new(config: CollectorConfig) -> Self {
+        Self {
+            config,
+            drm_root: PathBuf::from("/sys/class/drm"),
+            counters: HashMap::new(),
+        }
+    }
+}
+
+impl Collector for GpuCollector {
+    type Config = CollectorConfig;
+    type Output = Vec<GpuInfo>;
+
+    fn name(&self) -> &'static str {
+        "gpu"
+    }
+
+    fn config(&self) -> &CollectorConfig {
+        &self.config
+    }
+
+    async fn collect(&mut self) -> Vec<GpuInfo> {
+        read_gpus(&self.drm_root, &mut self.counters, Instant::now())
+    }
+
+    /// Raw GPU list; labels are assigned from `[RESOURCES.sensors]` in `collector::finalise`.
+    fn apply(&self, output: &Vec<GpuInfo>, info: &mut SystemInfo) {
+        info.gpus.extend(output.iter().cloned());
+    }
+}
+
+/// Reads every `cardN` under `drm_root` (`/sys/class/drm`) driven by amdgpu, i915 or xe.
+/// GPUs are named `<driver> <card>`, e.g. `amdgpu card1`.
+pub fn read_gpus(drm_root: &Path, counters: &mut HashMap<String, GpuCounters>, now: Instant) -> Vec<GpuInfo> {
+    let start = Instant::now();
+    let mut cards: Vec<String> = fs::read_dir(drm_root)
+        .map(|dir| {
+            dir.flatten()
+                .map(|entry| entry.file_name().to_string_lossy().to_string())
+                .filter(|name| name.starts_with("card") && !name.contains('-'))
+                .collect()
+        })
+        .unwrap_or_default();
+    cards.sort();
+
+    let mut gpus = Vec::new();
+    for card in cards {
+        let card_dir = drm_root.join(&card);
+        let Some(driver) = fs::read_link(card_dir.join("device/driver"))
+            .ok()
+            .and_then(|path| path.file_name().map(|name| name.to_string_lossy().to_string()))
+        else {
+            continue;
+        };
+        let name = format!("{} {}", driver, card);
+        let gpu = match driver.as_str() {
+            "amdgpu" => read_amdgpu(&card_dir, name),
+            "i915" | "xe" => {
+                let counters = counters.entry(card.clone()).or_default();
+                read_intel(&card_dir, &driver, name, counters, now)
+            }
+            _ => continue,
+        };
+        gpus.push(with_displays(gpu));
+    }
+    debug!("read_gpus took: {} ms", start.elapsed().as_millis());
+    gpus
+}
+
+fn read_amdgpu(card_dir: &Path, name: String) -> GpuInfo {
+    let device = card_dir.join("device");
+    let hwmon = first_hwmon(&device);
+    let hwmon_value = |file: &str| hwmon.as_ref().and_then(|dir| number::<f64>(&dir.join(file)));
+
+    GpuInfo {
+        name,
+        vendor: GpuVendor::Amd,
+        load: number::<f32>(&device.join("gpu_busy_percent")).unwrap_or(0.0) / 100.0,
+        memory_used: number(&device.join("mem_info_vram_used")).unwrap_or(0),
+        memory_total: number(&device.join("mem_info_vram_total")).unwrap_or(0),
+        temperature: hwmon_value("temp1_input").map(|mc| mc / 1000.0).unwrap_or(0.0) as f32,
+        // Microwatts; older kernels only have the average
+        power: hwmon_value("power1_input")
+            .or_else(|| hwmon_value("power1_average"))
+            .map(|uw| uw / 1_000_000.0)
+            .unwrap_or(0.0) as f32,
+        // Hz: freq1 is the shader clock, freq2 the memory clock
+        clock: hwmon_value("freq1_input").map(|hz| hz / 1_000_000.0).unwrap_or(0.0) as f32,
+        memory_clock: hwmon_value("freq2_input").map(|hz| hz / 1_000_000.0).unwrap_or(0.0) as f32,
+        ..GpuInfo::default()
+    }
+}
+
+fn read_intel(card_dir: &Path, driver: &str, name: String, counters: &mut GpuCounters, now: Instant) -> GpuInfo {
+    let device = card_dir.join("device");
+    let (clock, idle_ms) = if driver == "xe" {
+        let gt = device.join("tile0/gt0");
+        (
+            number::<f32>(&gt.join("freq0/act_freq")),
+            number::<u64>(&gt.join("gtidle/idle_residency_ms")),
+        )
+    } else {
+        (
+            number::<f32>(&card_dir.join("gt_act_freq_mhz")),
+            number::<u64>(&card_dir.join("power/rc6_residency_ms")),
+        )
+    };
+
+    // Residency grows by up to 1000 ms per second
+    let rc6 = idle_ms
+        .and_then(|ms| counters.idle.update(ms, now))
+        .map(|rate| (rate / 1000.0).clamp(0.0, 1.0) as f32);
+
+    // Discrete cards report energy, not power
+    let hwmon = first_hwmon(&device);
+    let power = hwmon
+        .as_ref()
+        .and_then(|dir| number::<u64>(&dir.join("energy1_input")))
+        .and_then(|uj| counters.energy.update(uj, now))
+        .map(|uj_per_s| uj_per_s / 1_000_000.0);
+
+    GpuInfo {
+        name,
+        vendor: GpuVendor::Intel,
+        load: rc6.map(|rc6| 1.0 - rc6).unwrap_or(0.0),
+        rc6: rc6.unwrap_or(0.0),
+        clock: clock.unwrap_or(0.0),
+        power: power.unwrap_or(0.0) as f32,
+        temperature: hwmon
+            .as_ref()
+            .and_then(|dir| number::<f32>(&dir.join("temp1_input")))
+            .map(|mc| mc / 1000.0)
+            .unwrap_or(0.0),
+        ..GpuInfo::default()
+    }
+}
+
+fn with_displays(gpu: GpuInfo) -> GpuInfo {
+    let memory_percent = if gpu.memory_total > 0 {
+        gpu.memory_used as f32 / gpu.memory_total as f32 * 100.0
+    } else {
+        0.0
+    };
+    GpuInfo {
+        temperature_display: format!("{:.0} °C", gpu.temperature),
+        load_display: format!("{:.1}%", gpu.load * 100.0),
+        memory_used_display: ByteSize::b(gpu.memory_used).to_string(),
+        memory_total_display: ByteSize::b(gpu.memory_total).to_string(),
+        memory_percent,
+        memory_percent_display: format!("{:.1}%", memory_percent),
+        power_display: format!("{:.0} W", gpu.power),
+        clock_display: format!("{:.0} MHz", gpu.clock),
+        ..gpu
+    }
+}
+
+/// The hwmon directory of a DRM device, e.g. `device/hwmon/hwmon3`.
+fn first_hwmon(device: &Path) -> Option<PathBuf> {
+    let mut dirs: Vec<PathBuf> = fs::read_dir(device.join("hwmon")).ok()?.flatten().map(|entry| entry.path()).collect();
+    dirs.sort();
+    dirs.into_iter().next()
+}
+
+fn number<T: FromStr>(path: &Path) -> Option<T> {
+    get_file_line(path, 24)?.parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::testing::write;
+    use std::os::unix::fs::symlink;
+    use std::time::Duration;
+
+    fn card(root: &Path, card: &str, driver: &str) {
+        fs::create_dir_all(root.join("drivers").join(driver)).unwrap();
+        fs::create_dir_all(root.join("drm").join(card).join("device")).unwrap();
+        symlink(root.join("drivers").join(driver), root.join("drm").join(card).join("device/driver")).unwrap();
+    }
+
+    #[test]
+    fn test_read_sysfs_gpus() {
+        let root = tempfile::tempdir().unwrap();
+        let sys = root.path();
+        let drm = sys.join("drm");
+
+        card(sys, "card0", "amdgpu");
+        write(&drm, "card0/device/gpu_busy_percent", "42");
+        write(&drm, "card0/device/mem_info_vram_used", "1073741824");
+        write(&drm, "card0/device/mem_info_vram_total", "4294967296");
+        write(&drm, "card0/device/hwmon/hwmon4/temp1_input", "51000");
+        write(&drm, "card0/device/hwmon/hwmon4/power1_average", "35000000");
+        write(&drm, "card0/device/hwmon/hwmon4/freq1_input", "1800000000");
+        write(&drm, "card0/device/hwmon/hwmon4/freq2_input", "875000000");
+        // Connectors are not GPUs
+        fs::create_dir_all(drm.join("card0-DP-1")).unwrap();
+
+        card(sys, "card1", "i915");
+        write(&drm, "card1/gt_act_freq_mhz", "1300");
+        write(&drm, "card1/power/rc6_residency_ms", "10000");
+        write(&drm, "card1/device/hwmon/hwmon5/energy1_input", "1000000");
+
+        let mut counters = HashMap::new();
+        let now = Instant::now();
+        let gpus = read_gpus(&drm, &mut counters, now);
+        assert_eq!(gpus.len(), 2);
+
+        let amd = &gpus[0];
+        assert_eq!(amd.name, "amdgpu card0");
+        assert_eq!(amd.vendor, GpuVendor::Amd);
+        assert_eq!(amd.load, 0.42);
+        assert_eq!(amd.memory_percent, 25.0);
+        assert_eq!(amd.temperature, 51.0);
+        assert_eq!(amd.power_display, "35 W");
+        assert_eq!(amd.clock, 1800.0);
+        assert_eq!(amd.memory_clock, 875.0);
+
+        // Intel rates need a second sample
+        assert_eq!(gpus[1].name, "i915 card1");
+        assert_eq!(gpus[1].load, 0.0);
+        write(&drm, "card1/power/rc6_residency_ms", "10250");
+        write(&drm, "card1/device/hwmon/hwmon5/energy1_input", "6000000");
+        let gpus = read_gpus(&drm, &mut counters, now + Duration::from_secs(1));
+        let intel = &gpus[1];
+        assert_eq!(intel.rc6, 0.25);
+        assert_eq!(intel.load, 0.75);
+        assert_eq!(intel.power, 5.0);
+        assert_eq!(intel.clock_display, "1300 MHz");
+        assert_eq!(intel.memory_total, 0);
+    }
+}
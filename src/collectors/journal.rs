@@ -0,0 +1,196 @@
+use log::{debug, error};
+use serde_json::Value;
+use std::time::Instant;
+use tokio::process::Command;
+
+/// One journal entry, as much of it as the log panel needs.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct JournalEntry {
+    /// 0 (emerg) to 7 (debug).
+    pub priority: u8,
+    pub identifier: String,
+    pub pid: Option<u32>,
+    pub unit: String,
+    pub message: String,
+}
+
+impl JournalEntry {
+    /// `identifier[pid]: message`, like a syslog line without timestamp and host.
+    pub fn to_line(&self) -> String {
+        match self.pid {
+            Some(pid) => format!("{}[{}]: {}", self.identifier, pid, self.message),
+            None if self.identifier.is_empty() => self.message.clone(),
+            None => format!("{}: {}", self.identifier, self.message),
+        }
+    }
+}
+
+/// Which entries are shown. Empty lists match everything.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct JournalFilter {
+    /// Highest (least severe) priority shown.
+    pub priority: Option<u8>,
+    pub units: Vec<String>,
+    pub identifiers: Vec<String>,
+}
+
+impl JournalFilter {
+    pub fn matches(&self, entry: &JournalEntry) -> bool {
+        self.priority.is_none_or(|priority| entry.priority <= priority)
+            && (self.units.is_empty() || self.units.iter().any(|unit| unit_matches(unit, &entry.unit)))
+            && (self.identifiers.is_empty() || self.identifiers.contains(&entry.identifier))
+    }
+
+    /// The same filter as `journalctl` arguments, so that `-n` counts only matching entries.
+    pub fn to_args(&self) -> Vec<String> {
+        let mut args = Vec::new();
+        if let Some(priority) = self.priority {
+            args.push(format!("--priority={}", priority));
+        }
+        for unit in &self.units {
+            args.push(format!("--unit={}", unit));
+        }
+        for identifier in &self.identifiers {
+            args.push(format!("--identifier={}", identifier));
+        }
+        args
+    }
+}
+
+/// `nginx` matches `nginx.service`, as with `journalctl -u`.
+fn unit_matches(wanted: &str, unit: &str) -> bool {
+    unit == wanted || unit.strip_suffix(".service") == Some(wanted)
+}
+
+/// Accepts a syslog priority name (`err`, `warning`, ...) or number.
+pub fn parse_priority(text: &str) -> Option<u8> {
+    const NAMES: [&str; 8] = ["emerg", "alert", "crit", "err", "warning", "notice", "info", "debug"];
+    let text = text.trim().to_lowercase();
+    match text.as_str() {
+        "error" => Some(3),
+        "warn" => Some(4),
+        _ => NAMES
+            .iter()
+            .position(|name| *name == text)
+            .map(|p| p as u8)
+            .or_else(|| text.parse::<u8>().ok().filter(|p| *p <= 7)),
+    }
+}
+
+/// Parses `journalctl -o json` output, one JSON object per line.
+pub fn parse_json(text: &str) -> Vec<JournalEntry> {
+    text.lines()
+        .filter_map(|line| serde_json::from_str::<Value>(line).ok())
+        .filter_map(|value| {
+            let message = field(&value, "MESSAGE")?;
+            Some(JournalEntry {
+                priority: field(&value, "PRIORITY").and_then(|p| p.parse().ok()).unwrap_or(6),
+                identifier: field(&value, "SYSLOG_IDENTIFIER")
+                    .or_else(|| field(&value, "_COMM"))
+                    .unwrap_or_default(),
+                pid: field(&value, "SYSLOG_PID")
+                    .or_else(|| field(&value, "_PID"))
+                    .and_then(|pid| pid.parse().ok()),
+                unit: field(&value, "_SYSTEMD_UNIT").unwrap_or_default(),
+                message: message.chars().filter(|c| !c.is_control()).collect(),
+            })
+        })
+        .collect()
+}
+
+/// A field of a JSON journal entry. Binary values are exported as byte arrays and repeated
+/// fields as arrays of strings (the last one is used); `null` marks a value too large to export.
+fn field(entry: &Value, name: &str) -> Option<String> {
+    match entry.get(name)? {
+        Value::String(s) => Some(s.clone()),
+        Value::Array(items) if items.iter().all(Value::is_u64) => {
+            let bytes: Vec<u8> = items.iter().filter_map(|b| b.as_u64().map(|b| b as u8)).collect();
+            Some(String::from_utf8_lossy(&bytes).to_string())
+        }
+        Value::Array(items) => items.iter().rev().find_map(|item| item.as_str().map(str::to_string)),
+        _ => None,
+    }
+}
+
+/// The last `lines` entries matching `filter`, oldest first.
+pub async fn collect(lines: usize, filter: &JournalFilter) -> Result<Vec<JournalEntry>, String> {
+    let start = Instant::now();
+    let cmd = Command::new("journalctl")
+        .args(["--output=json", "--no-pager", "--quiet", &format!("--lines={}", lines)])
+        .args(filter.to_args())
+        .output()
+        .await;
+    debug!("journalctl command execution took: {} ms", start.elapsed().as_millis());
+
+    match cmd {
+        Ok(output) if output.status.success() => {
+            let entries: Vec<JournalEntry> = parse_json(&String::from_utf8_lossy(&output.stdout))
+                .into_iter()
+                .filter(|entry| filter.matches(entry))
+                .collect();
+            debug!("collect (journal) took: {} ms", start.elapsed().as_millis());
+            Ok(entries)
+        }
+        Ok(output) => {
+            let message = String::from_utf8_lossy(&output.stderr).trim().to_string();
+            error!("journalctl failed: {}", message);
+            Err(message)
+        }
+        Err(e) => {
+            error!("Error running journalctl: {}", e);
+            Err(e.to_string())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXPORT: &str = include_str!("../../tests/fixtures/journal/export.json");
+
+    #[test]
+    fn test_parse_json() {
+        let entries = parse_json(EXPORT);
+        // The entry whose MESSAGE is null is skipped
+        assert_eq!(entries.len(), 5);
+        assert_eq!(entries[0].to_line(), "systemd[1]: Started nginx.service - A high performance web server.");
+        assert_eq!(entries[1].priority, 3);
+        assert_eq!(entries[1].unit, "nginx.service");
+        assert_eq!(entries[2].to_line(), "kernel: nvme nvme0: I/O 12 QID 3 timeout, completion polled");
+        // Binary message with an escape sequence
+        assert_eq!(entries[3].message, "Accepted publickey for deploy[0m");
+    }
+
+    #[test]
+    fn test_filter() {
+        let entries = parse_json(EXPORT);
+        let lines = |filter: &JournalFilter| -> Vec<String> {
+            entries.iter().filter(|e| filter.matches(e)).map(|e| e.identifier.clone()).collect()
+        };
+
+        let warnings = JournalFilter {
+            priority: parse_priority("warning"),
+            ..JournalFilter::default()
+        };
+        assert_eq!(lines(&warnings), vec!["nginx", "kernel"]);
+
+        let units = JournalFilter {
+            units: vec!["nginx".to_string(), "ssh.service".to_string()],
+            ..JournalFilter::default()
+        };
+        assert_eq!(lines(&units), vec!["nginx", "sshd"]);
+
+        let identifiers = JournalFilter {
+            priority: Some(6),
+            identifiers: vec!["app".to_string(), "systemd".to_string()],
+            ..JournalFilter::default()
+        };
+        assert_eq!(lines(&identifiers), vec!["systemd"]);
+        assert_eq!(identifiers.to_args(), vec!["--priority=6", "--identifier=app", "--identifier=systemd"]);
+
+        assert_eq!(parse_priority("err"), Some(3));
+        assert_eq!(parse_priority("5"), Some(5));
+        assert_eq!(parse_priority("loud"), None);
+    }
+}
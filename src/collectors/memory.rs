@@ -1,37 +1,210 @@
+use crate::collectors::collector::Collector;
+use crate::config::CollectorConfig;
+use crate::models::memory::{MemoryInfo, SwapMemoryInfo};
+use crate::models::SystemInfo;
 use log::{debug, error};
+use std::fs;
+use std::path::{Path, PathBuf};
 use std::time::Instant;
-use systemstat::{ByteSize, Platform, System};
+use systemstat::ByteSize;
 
-pub async fn collect_ram(sys: &System) -> Vec<ByteSize> {
-    let start = Instant::now();
-    let result = match sys.memory() {
-        Ok(mem) => {
-            let used_bytes = mem.total.as_u64().saturating_sub(mem.free.as_u64());
-            let total_bytes = mem.total.as_u64();
-            vec![ByteSize::b(used_bytes), ByteSize::b(total_bytes)]
+/// The parts of `/proc/meminfo` the dashboard uses, in bytes.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MemInfo {
+    pub total: u64,
+    pub free: u64,
+    pub available: u64,
+    pub buffers: u64,
+    pub cached: u64,
+    pub shared: u64,
+    pub slab: u64,
+    pub slab_reclaimable: u64,
+    pub dirty: u64,
+    pub writeback: u64,
+    pub swap_total: u64,
+    pub swap_free: u64,
+    pub hugepages_total: u64,
+    pub hugepages_free: u64,
+}
+
+pub(crate) struct MemoryCollector {
+    config: CollectorConfig,
+    meminfo_path: PathBuf,
+}
+
+impl MemoryCollector {
+    pub fn new(config: CollectorConfig) -> Self {
+        Self {
+            config,
+            meminfo_path: PathBuf::from("/proc/meminfo"),
         }
-        Err(x) => {
-            error!("Memory statistics error getting stats: {}", x);
-            vec![ByteSize::b(0), ByteSize::b(0)]
+    }
+}
+
+impl Collector for MemoryCollector {
+    type Config = CollectorConfig;
+    type Output = (MemoryInfo, SwapMemoryInfo);
+
+    fn name(&self) -> &'static str {
+        "memory"
+    }
+
+    fn config(&self) -> &CollectorConfig {
+        &self.config
+    }
+
+    async fn collect(&mut self) -> (MemoryInfo, SwapMemoryInfo) {
+        let meminfo = collect_meminfo(&self.meminfo_path);
+        (memory_info(&meminfo), swap_info(&meminfo))
+    }
+
+    fn apply(&self, output: &(MemoryInfo, SwapMemoryInfo), info: &mut SystemInfo) {
+        info.memory = output.0.clone();
+        info.swap_memory = output.1.clone();
+    }
+}
+
+pub fn collect_meminfo(path: &Path) -> MemInfo {
+    let start = Instant::now();
+    let result = match fs::read_to_string(path) {
+        Ok(text) => parse_meminfo(&text),
+        Err(e) => {
+            error!("Memory statistics error reading {}: {}", path.display(), e);
+            MemInfo::default()
         }
     };
-    debug!("collect_ram took: {} ms", start.elapsed().as_millis());
+    debug!("collect_meminfo took: {} ms", start.elapsed().as_millis());
     result
 }
 
-pub async fn collect_swap(sys: &System) -> Vec<ByteSize> {
-    let start = Instant::now();
-    let result = match sys.swap() {
-        Ok(swap) => {
-            let used_bytes = swap.total.as_u64().saturating_sub(swap.free.as_u64());
-            let total_bytes = swap.total.as_u64();
-            vec![ByteSize::b(used_bytes), ByteSize::b(total_bytes)]
-        }
-        Err(x) => {
-            error!("Swap statistics error getting stats: {}", x);
-            vec![ByteSize::b(0), ByteSize::b(0)]
+/// Parses `/proc/meminfo`, where sizes are in kB and hugepage counts are in pages.
+pub fn parse_meminfo(text: &str) -> MemInfo {
+    let mut info = MemInfo::default();
+    let mut hugepage_size = 0;
+    let (mut hugepages_total, mut hugepages_free) = (0, 0);
+    for line in text.lines() {
+        let Some((key, value)) = line.split_once(':') else {
+            continue;
+        };
+        let mut parts = value.split_whitespace();
+        let Some(number) = parts.next().and_then(|n| n.parse::<u64>().ok()) else {
+            continue;
+        };
+        let bytes = if parts.next() == Some("kB") { number * 1024 } else { number };
+        match key {
+            "MemTotal" => info.total = bytes,
+            "MemFree" => info.free = bytes,
+            "MemAvailable" => info.available = bytes,
+            "Buffers" => info.buffers = bytes,
+            "Cached" => info.cached = bytes,
+            "Shmem" => info.shared = bytes,
+            "Slab" => info.slab = bytes,
+            "SReclaimable" => info.slab_reclaimable = bytes,
+            "Dirty" => info.dirty = bytes,
+            "Writeback" => info.writeback = bytes,
+            "SwapTotal" => info.swap_total = bytes,
+            "SwapFree" => info.swap_free = bytes,
+            "HugePages_Total" => hugepages_total = number,
+            "HugePages_Free" => hugepages_free = number,
+            "Hugepagesize" => hugepage_size = bytes,
+            _ => {}
         }
+    }
+    info.hugepages_total = hugepages_total * hugepage_size;
+    info.hugepages_free = hugepages_free * hugepage_size;
+    info
+}
+
+/// Used memory is what isn't available (`MemTotal - MemAvailable`), so page cache the kernel
+/// can drop does not count; it is reported as `cache` instead. `used + cache + free = total`.
+pub fn memory_info(meminfo: &MemInfo) -> MemoryInfo {
+    let total = meminfo.total;
+    let used = total.saturating_sub(meminfo.available);
+    let cache = meminfo.available.saturating_sub(meminfo.free);
+    let fraction = |bytes: u64| if total > 0 { bytes as f32 / total as f32 } else { 0.0 };
+    let percent = fraction(used);
+
+    MemoryInfo {
+        percent,
+        percent_display: format!("{:.1}%", percent * 100.0),
+        used,
+        used_display: ByteSize::b(used).to_string(),
+        total,
+        total_display: ByteSize::b(total).to_string(),
+        available: meminfo.available,
+        available_display: ByteSize::b(meminfo.available).to_string(),
+        free: meminfo.free,
+        cache,
+        cache_percent: fraction(cache),
+        cache_display: ByteSize::b(cache).to_string(),
+        buffers: meminfo.buffers,
+        cached: meminfo.cached,
+        shared: meminfo.shared,
+        slab: meminfo.slab,
+        dirty: meminfo.dirty,
+        writeback: meminfo.writeback,
+        hugepages_total: meminfo.hugepages_total,
+        hugepages_free: meminfo.hugepages_free,
+    }
+}
+
+pub fn swap_info(meminfo: &MemInfo) -> SwapMemoryInfo {
+    let used = meminfo.swap_total.saturating_sub(meminfo.swap_free);
+    // No swap configured is 0%, not NaN
+    let percent = if meminfo.swap_total > 0 {
+        used as f32 / meminfo.swap_total as f32
+    } else {
+        0.0
     };
-    debug!("collect_swap took: {} ms", start.elapsed().as_millis());
-    result
+    SwapMemoryInfo {
+        percent,
+        percent_display: format!("{:.1}%", percent * 100.0),
+        used,
+        used_display: ByteSize::b(used).to_string(),
+        total: meminfo.swap_total,
+        total_display: ByteSize::b(meminfo.swap_total).to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MEMINFO: &str = "MemTotal:        8000000 kB
+MemFree:          500000 kB
+MemAvailable:    5000000 kB
+Buffers:          100000 kB
+Cached:          4200000 kB
+SwapCached:            0 kB
+Dirty:              2048 kB
+Writeback:             0 kB
+Shmem:             90000 kB
+Slab:             300000 kB
+SReclaimable:     250000 kB
+SwapTotal:       2000000 kB
+SwapFree:        1500000 kB
+HugePages_Total:      16
+HugePages_Free:        4
+Hugepagesize:       2048 kB
+";
+
+    #[test]
+    fn test_memory_breakdown() {
+        let meminfo = parse_meminfo(MEMINFO);
+        assert_eq!(meminfo.available, 5_000_000 * 1024);
+        assert_eq!(meminfo.dirty, 2048 * 1024);
+        assert_eq!(meminfo.hugepages_total, 16 * 2048 * 1024);
+        assert_eq!(meminfo.hugepages_free, 4 * 2048 * 1024);
+
+        let memory = memory_info(&meminfo);
+        // Page cache is not "used"
+        assert_eq!(memory.used, 3_000_000 * 1024);
+        assert_eq!(memory.percent_display, "37.5%");
+        assert_eq!(memory.cache, 4_500_000 * 1024);
+        assert_eq!(memory.used + memory.cache + memory.free, memory.total);
+
+        let swap = swap_info(&meminfo);
+        assert_eq!(swap.percent, 0.25);
+        assert_eq!(swap_info(&MemInfo::default()).percent, 0.0);
+    }
 }
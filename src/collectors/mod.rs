@@ -1,7 +1,20 @@
 pub mod cpu;
 pub mod disk;
 pub mod memory;
+pub mod pressure;
 pub mod network;
 pub mod system;
+pub mod selector;
+pub mod syslog;
+pub mod systemd;
+pub mod journal;
 pub mod nvidia;
+pub mod gpu;
+pub mod power;
+pub mod container;
+pub mod probe;
+pub mod custom;
+pub mod process;
+pub mod background;
 pub mod collector;
+pub mod registry;
@@ -1,65 +1,472 @@
-use log::{debug, error};
-use std::collections::BTreeMap;
+use crate::collectors::collector::Collector;
+use crate::collectors::process::parse_net_tcp;
+use crate::config::CollectorConfig;
+use crate::models::network::{InterfaceInfo, NetworkInfo, TcpStates, WirelessInfo};
+use crate::models::SystemInfo;
+use crate::utils::hwmon::get_file_line;
+use crate::utils::rate::{self, RateCounter};
+use log::debug;
+use std::collections::HashMap;
+use std::ffi::CStr;
+use std::fs;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use std::path::{Path, PathBuf};
 use std::time::{Duration, Instant};
-use systemstat::{ByteSize, Network, Platform, System};
-use tokio::time;
+use tokio::process::Command;
 
-pub async fn collect_io(sys: &System, allowed: &Vec<&str>) -> Vec<ByteSize> {
+/// How often `iw` is asked for the SSID and bitrate, which change far less often than the signal.
+const IW_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Counters from `/sys/class/net/<interface>/statistics`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct InterfaceStats {
+    pub name: String,
+    pub rx_bytes: u64,
+    pub tx_bytes: u64,
+    pub rx_packets: u64,
+    pub tx_packets: u64,
+    pub rx_errors: u64,
+    pub tx_errors: u64,
+    pub rx_dropped: u64,
+    pub tx_dropped: u64,
+}
+
+/// Link state from `/sys/class/net/<interface>`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct LinkState {
+    pub operstate: String,
+    pub speed: Option<u32>,
+    pub duplex: String,
+    pub wireless: bool,
+}
+
+/// Rate counters kept between runs for one interface.
+#[derive(Debug, Default)]
+struct InterfaceRates {
+    recv: RateCounter,
+    sent: RateCounter,
+    recv_packets: RateCounter,
+    sent_packets: RateCounter,
+}
+
+pub(crate) struct NetworkCollector {
+    config: CollectorConfig,
+    networks: Vec<String>,
+    /// `/sys/class/net`.
+    net_root: PathBuf,
+    /// `/proc`, for `net/tcp{,6}` and `net/wireless`.
+    proc_root: PathBuf,
+    rates: HashMap<String, InterfaceRates>,
+    /// Last `iw dev <interface> link` of each wireless interface and when it was read.
+    iw_links: HashMap<String, (WirelessInfo, Instant)>,
+}
+
+impl NetworkCollector {
+    pub fn new(config: CollectorConfig, networks: Vec<String>) -> Self {
+        Self {
+            config,
+            networks,
+            net_root: PathBuf::from("/sys/class/net"),
+            proc_root: PathBuf::from("/proc"),
+            rates: HashMap::new(),
+            iw_links: HashMap::new(),
+        }
+    }
+
+    /// SSID and bitrate from `iw`, read every `IW_INTERVAL`, with the signal from `/proc/net/wireless`
+    /// of this run when there is one.
+    async fn wireless(&mut self, name: &str, signal: Option<(f32, i32)>) -> WirelessInfo {
+        let now = Instant::now();
+        let due = self.iw_links.get(name).is_none_or(|(_, read)| now.duration_since(*read) >= IW_INTERVAL);
+        if due {
+            self.iw_links.insert(name.to_string(), (read_iw_link(name).await, now));
+        }
+        let mut wireless = self.iw_links[name].0.clone();
+        if let Some((quality, level)) = signal {
+            wireless.quality = Some(quality);
+            wireless.signal = Some(level);
+        }
+        wireless.display = wireless_display(&wireless);
+        wireless
+    }
+}
+
+impl Collector for NetworkCollector {
+    type Config = CollectorConfig;
+    type Output = NetworkInfo;
+
+    fn name(&self) -> &'static str {
+        "network"
+    }
+
+    fn config(&self) -> &CollectorConfig {
+        &self.config
+    }
+
+    async fn collect(&mut self) -> NetworkInfo {
+        let stats = collect_io(&self.net_root, &self.networks).await;
+        let mut addresses = interface_addresses();
+        let signals = fs::read_to_string(self.proc_root.join("net/wireless"))
+            .map(|text| parse_proc_wireless(&text))
+            .unwrap_or_default();
+        let mut links = HashMap::new();
+        for stat in &stats {
+            let link = read_link_state(&self.net_root, &stat.name);
+            let wireless = if link.wireless || signals.contains_key(&stat.name) {
+                Some(self.wireless(&stat.name, signals.get(&stat.name).copied()).await)
+            } else {
+                None
+            };
+            links.insert(stat.name.clone(), (link, wireless));
+        }
+
+        // Rates since the previous run, zero until there is one
+        let now = Instant::now();
+        let interfaces: Vec<InterfaceInfo> = stats
+            .iter()
+            .map(|stat| {
+                let rates = self.rates.entry(stat.name.clone()).or_default();
+                let rate = |counter: &mut RateCounter, value: u64| counter.update(value, now).unwrap_or(0.0) as u64;
+                let recv = rate(&mut rates.recv, stat.rx_bytes);
+                let sent = rate(&mut rates.sent, stat.tx_bytes);
+                let (link, wireless) = links.remove(&stat.name).unwrap_or_default();
+                let addresses = sort_addresses(addresses.remove(&stat.name).unwrap_or_default());
+                InterfaceInfo {
+                    name: stat.name.clone(),
+                    recv,
+                    recv_display: rate::bytes_per_second(recv),
+                    sent,
+                    sent_display: rate::bytes_per_second(sent),
+                    recv_packets: rate(&mut rates.recv_packets, stat.rx_packets),
+                    sent_packets: rate(&mut rates.sent_packets, stat.tx_packets),
+                    recv_errors: stat.rx_errors,
+                    sent_errors: stat.tx_errors,
+                    recv_drops: stat.rx_dropped,
+                    sent_drops: stat.tx_dropped,
+                    address_display: address_display(&addresses),
+                    addresses,
+                    operstate: link.operstate,
+                    speed: link.speed,
+                    speed_display: link.speed.map(speed_display).unwrap_or_default(),
+                    duplex: link.duplex,
+                    wireless,
+                }
+            })
+            .collect();
+
+        let recv = interfaces.iter().map(|i| i.recv).sum();
+        let sent = interfaces.iter().map(|i| i.sent).sum();
+        NetworkInfo {
+            recv,
+            recv_display: rate::bytes_per_second(recv),
+            sent,
+            sent_display: rate::bytes_per_second(sent),
+            interfaces,
+            tcp: collect_tcp_states(&self.proc_root),
+        }
+    }
+
+    fn apply(&self, output: &NetworkInfo, info: &mut SystemInfo) {
+        info.network = output.clone();
+    }
+}
+
+/// Counters of the `allowed` interfaces under `net_root` (`/sys/class/net`), in that order.
+/// Interfaces that don't exist (unplugged USB adapters) are left out.
+pub async fn collect_io(net_root: &Path, allowed: &[String]) -> Vec<InterfaceStats> {
     let start = Instant::now();
-    let result = match sys.networks() {
-        Ok(initial_networks) => {
-            let initial_results = get_aggregated_stats(sys, initial_networks, allowed);
-            let initial_rx_bytes = initial_results[0].as_u64();
-            let initial_tx_bytes = initial_results[1].as_u64();
+    let result = allowed
+        .iter()
+        .filter_map(|name| read_interface_stats(net_root, name))
+        .collect();
+    debug!("collect_io took: {} ms", start.elapsed().as_millis());
+    result
+}
 
-            time::sleep(Duration::from_secs(1)).await;
+pub fn read_interface_stats(net_root: &Path, name: &str) -> Option<InterfaceStats> {
+    let statistics = net_root.join(name).join("statistics");
+    if !statistics.is_dir() {
+        return None;
+    }
+    let counter = |file: &str| {
+        get_file_line(&statistics.join(file), 24)
+            .and_then(|value| value.parse::<u64>().ok())
+            .unwrap_or(0)
+    };
+    Some(InterfaceStats {
+        name: name.to_string(),
+        rx_bytes: counter("rx_bytes"),
+        tx_bytes: counter("tx_bytes"),
+        rx_packets: counter("rx_packets"),
+        tx_packets: counter("tx_packets"),
+        rx_errors: counter("rx_errors"),
+        tx_errors: counter("tx_errors"),
+        rx_dropped: counter("rx_dropped"),
+        tx_dropped: counter("tx_dropped"),
+    })
+}
 
-            // Get new statistics after a second wait
-            match sys.networks() {
-                Ok(final_networks) => {
-                    let final_results = get_aggregated_stats(sys, final_networks, allowed);
-                    let final_rx_bytes = final_results[0].as_u64();
-                    let final_tx_bytes = final_results[1].as_u64();
+/// `operstate`, `speed` and `duplex` of an interface. Reading `speed` fails while the link is
+/// down, and virtual interfaces report `-1`.
+pub fn read_link_state(net_root: &Path, name: &str) -> LinkState {
+    let dir = net_root.join(name);
+    let line = |file: &str| get_file_line(&dir.join(file), 24).unwrap_or_default();
+    LinkState {
+        operstate: line("operstate"),
+        speed: line("speed").parse::<i64>().ok().filter(|speed| *speed > 0).map(|speed| speed as u32),
+        duplex: match line("duplex").as_str() {
+            "unknown" => String::new(),
+            duplex => duplex.to_string(),
+        },
+        wireless: dir.join("wireless").is_dir() || dir.join("phy80211").exists(),
+    }
+}
 
-                    let rx_bytes = final_rx_bytes.saturating_sub(initial_rx_bytes);
-                    let tx_bytes = final_tx_bytes.saturating_sub(initial_tx_bytes);
+fn speed_display(speed: u32) -> String {
+    if speed >= 1000 && speed.is_multiple_of(1000) {
+        format!("{} Gb/s", speed / 1000)
+    } else if speed >= 1000 {
+        format!("{:.1} Gb/s", speed as f32 / 1000.0)
+    } else {
+        format!("{} Mb/s", speed)
+    }
+}
 
-                    vec![ByteSize::b(rx_bytes), ByteSize::b(tx_bytes)]
+/// Addresses of every interface by name, from `getifaddrs(3)`.
+pub fn interface_addresses() -> HashMap<String, Vec<IpAddr>> {
+    let mut result: HashMap<String, Vec<IpAddr>> = HashMap::new();
+    let mut list: *mut libc::ifaddrs = std::ptr::null_mut();
+    if unsafe { libc::getifaddrs(&mut list) } != 0 {
+        return result;
+    }
+    let mut current = list;
+    while !current.is_null() {
+        // SAFETY: a node of the list getifaddrs returned, freed only after the loop
+        let entry = unsafe { &*current };
+        current = entry.ifa_next;
+        if entry.ifa_addr.is_null() {
+            continue;
+        }
+        // SAFETY: ifa_addr points to a sockaddr of the size its family says
+        let address = unsafe {
+            match (*entry.ifa_addr).sa_family as i32 {
+                libc::AF_INET => {
+                    let address = &*(entry.ifa_addr as *const libc::sockaddr_in);
+                    IpAddr::V4(Ipv4Addr::from(u32::from_be(address.sin_addr.s_addr)))
                 }
-                Err(x) => {
-                    error!("Network statistics error getting final stats: {}", x);
-                    vec![ByteSize::b(0), ByteSize::b(0)]
+                libc::AF_INET6 => {
+                    let address = &*(entry.ifa_addr as *const libc::sockaddr_in6);
+                    IpAddr::V6(Ipv6Addr::from(address.sin6_addr.s6_addr))
                 }
+                _ => continue,
             }
-        }
-        Err(x) => {
-            error!("Network statistics error getting initial stats: {}", x);
-            vec![ByteSize::b(0), ByteSize::b(0)]
-        }
-    };
-    debug!("collect_io took: {} ms", start.elapsed().as_millis());
+        };
+        let name = unsafe { CStr::from_ptr(entry.ifa_name) }.to_string_lossy().to_string();
+        result.entry(name).or_default().push(address);
+    }
+    unsafe { libc::freeifaddrs(list) };
     result
 }
 
-fn get_aggregated_stats(
-    sys: &System,
-    networks: BTreeMap<String, Network>,
-    allowed: &Vec<&str>,
-) -> Vec<ByteSize> {
+/// IPv4 first, then IPv6 with link-local (`fe80::/10`) addresses last.
+fn sort_addresses(mut addresses: Vec<IpAddr>) -> Vec<IpAddr> {
+    addresses.sort_by_key(|address| match address {
+        IpAddr::V4(_) => 0,
+        IpAddr::V6(address) if is_link_local(address) => 2,
+        IpAddr::V6(_) => 1,
+    });
+    addresses
+}
+
+fn address_display(addresses: &[IpAddr]) -> String {
+    addresses
+        .iter()
+        .find(|address| match address {
+            IpAddr::V4(_) => true,
+            IpAddr::V6(address) => !is_link_local(address),
+        })
+        .map(IpAddr::to_string)
+        .unwrap_or_default()
+}
+
+fn is_link_local(address: &Ipv6Addr) -> bool {
+    address.segments()[0] & 0xffc0 == 0xfe80
+}
+
+/// Link quality (0 to 1) and signal level (dBm) by interface, from `/proc/net/wireless`:
+///
+/// ```text
+/// Inter-| sta-|   Quality        |   Discarded packets               | Missed | WE
+///  face | tus | link level noise |  nwid  crypt   frag  retry   misc | beacon | 22
+/// wlan0: 0000   54.  -56.  -256        0      0      0      0     13        0
+/// ```
+pub fn parse_proc_wireless(text: &str) -> HashMap<String, (f32, i32)> {
+    text.lines()
+        .skip(2)
+        .filter_map(|line| {
+            let (name, fields) = line.split_once(':')?;
+            let fields: Vec<&str> = fields.split_whitespace().collect();
+            let number = |index: usize| fields.get(index)?.trim_end_matches('.').parse::<f32>().ok();
+            // Link quality is out of 70 for most drivers
+            let quality = (number(1)? / 70.0).clamp(0.0, 1.0);
+            Some((name.trim().to_string(), (quality, number(2)? as i32)))
+        })
+        .collect()
+}
+
+/// SSID, signal and bitrate from `iw dev <interface> link`; nothing when `iw` is missing.
+async fn read_iw_link(name: &str) -> WirelessInfo {
     let start = Instant::now();
-    let mut rx_bytes = 0u64;
-    let mut tx_bytes = 0u64;
-
-    for net in networks.values() {
-        if allowed.contains(&net.name.as_str()) {
-            if let Ok(stats) = sys.network_stats(&net.name) {
-                rx_bytes = rx_bytes.saturating_add(stats.rx_bytes.as_u64());
-                tx_bytes = tx_bytes.saturating_add(stats.tx_bytes.as_u64());
-            }
+    let output = Command::new("iw").args(["dev", name, "link"]).output().await;
+    debug!("iw command execution took: {} ms", start.elapsed().as_millis());
+    match output {
+        Ok(output) if output.status.success() => parse_iw_link(&String::from_utf8_lossy(&output.stdout)),
+        _ => WirelessInfo::default(),
+    }
+}
+
+/// Parses `iw dev <interface> link`, which prints `Not connected.` when not associated.
+pub fn parse_iw_link(text: &str) -> WirelessInfo {
+    let mut wireless = WirelessInfo::default();
+    for line in text.lines().map(str::trim) {
+        let number = |value: &str| value.split_whitespace().next().and_then(|n| n.parse::<f32>().ok());
+        if let Some(ssid) = line.strip_prefix("SSID: ") {
+            wireless.ssid = ssid.to_string();
+        } else if let Some(signal) = line.strip_prefix("signal: ") {
+            wireless.signal = number(signal).map(|signal| signal as i32);
+        } else if let Some(bitrate) = line.strip_prefix("tx bitrate: ") {
+            wireless.bitrate = number(bitrate);
         }
     }
+    wireless.display = wireless_display(&wireless);
+    wireless
+}
 
-    let result = vec![ByteSize::b(rx_bytes), ByteSize::b(tx_bytes)];
-    debug!("get_aggregated_stats took: {} ms", start.elapsed().as_millis());
-    result
-}
\ No newline at end of file
+fn wireless_display(wireless: &WirelessInfo) -> String {
+    let mut parts = Vec::new();
+    if !wireless.ssid.is_empty() {
+        parts.push(wireless.ssid.clone());
+    }
+    if let Some(signal) = wireless.signal {
+        parts.push(format!("{} dBm", signal));
+    }
+    if let Some(bitrate) = wireless.bitrate {
+        parts.push(format!("{:.0} Mb/s", bitrate));
+    }
+    parts.join(" ")
+}
+
+/// Socket counts per state over `proc_root/net/tcp` and `proc_root/net/tcp6`.
+pub fn collect_tcp_states(proc_root: &Path) -> TcpStates {
+    let start = Instant::now();
+    let sockets = ["net/tcp", "net/tcp6"]
+        .iter()
+        .filter_map(|file| fs::read_to_string(proc_root.join(file)).ok())
+        .flat_map(|text| parse_net_tcp(&text));
+    let mut states = TcpStates::default();
+    for socket in sockets {
+        let count = match socket.state {
+            0x01 => &mut states.established,
+            0x02 => &mut states.syn_sent,
+            0x03 => &mut states.syn_recv,
+            0x04 => &mut states.fin_wait1,
+            0x05 => &mut states.fin_wait2,
+            0x06 => &mut states.time_wait,
+            0x07 => &mut states.close,
+            0x08 => &mut states.close_wait,
+            0x09 => &mut states.last_ack,
+            0x0a => &mut states.listen,
+            0x0b => &mut states.closing,
+            _ => continue,
+        };
+        *count += 1;
+    }
+    states.display = format!(
+        "TCP {} est {} listen {} tw {} cw",
+        states.established, states.listen, states.time_wait, states.close_wait
+    );
+    debug!("collect_tcp_states took: {} ms", start.elapsed().as_millis());
+    states
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::testing::write;
+    use std::fs;
+
+    #[test]
+    fn test_read_interface_stats() {
+        let root = tempfile::tempdir().unwrap();
+        let statistics = root.path().join("eth0/statistics");
+        fs::create_dir_all(&statistics).unwrap();
+        for (file, value) in [("rx_bytes", "1048576"), ("tx_bytes", "2048"), ("rx_packets", "900"), ("rx_dropped", "3")] {
+            fs::write(statistics.join(file), format!("{}\n", value)).unwrap();
+        }
+
+        let stats = read_interface_stats(root.path(), "eth0").unwrap();
+        assert_eq!(stats.rx_bytes, 1048576);
+        assert_eq!(stats.tx_bytes, 2048);
+        assert_eq!(stats.rx_packets, 900);
+        assert_eq!(stats.rx_dropped, 3);
+        assert_eq!(stats.tx_errors, 0);
+
+        assert!(read_interface_stats(root.path(), "wlan0").is_none());
+    }
+
+    #[test]
+    fn test_read_link_state() {
+        let root = tempfile::tempdir().unwrap();
+        write(root.path(), "eth0/operstate", "up");
+        write(root.path(), "eth0/speed", "2500");
+        write(root.path(), "eth0/duplex", "full");
+        fs::create_dir_all(root.path().join("wlan0/wireless")).unwrap();
+
+        let link = read_link_state(root.path(), "eth0");
+        assert_eq!((link.operstate.as_str(), link.speed, link.duplex.as_str()), ("up", Some(2500), "full"));
+        assert_eq!(speed_display(2500), "2.5 Gb/s");
+        assert!(!link.wireless && read_link_state(root.path(), "wlan0").wireless);
+    }
+
+    #[test]
+    fn test_sort_addresses() {
+        let addresses = sort_addresses(vec![
+            "fe80::1".parse().unwrap(),
+            "2001:db8::5".parse().unwrap(),
+            "192.168.1.10".parse().unwrap(),
+        ]);
+        assert_eq!(addresses[0].to_string(), "192.168.1.10");
+        assert_eq!(address_display(&addresses[1..]), "2001:db8::5");
+        assert_eq!(address_display(&addresses[2..]), "");
+    }
+
+    #[test]
+    fn test_parse_wireless() {
+        let wireless = "Inter-| sta-|   Quality        |   Discarded packets               | Missed | WE
+ face | tus | link level noise |  nwid  crypt   frag  retry   misc | beacon | 22
+wlan0: 0000   56.  -54.  -256        0      0      0      0     13        0
+";
+        assert_eq!(parse_proc_wireless(wireless)["wlan0"], (0.8, -54));
+        let link = parse_iw_link(
+            "Connected to aa:bb:cc:dd:ee:ff (on wlan0)\n\tSSID: HomeNet\n\tfreq: 5180\n\tsignal: -56 dBm\n\
+             \trx bitrate: 433.3 MBit/s VHT-MCS 9\n\ttx bitrate: 390.0 MBit/s VHT-MCS 8\n",
+        );
+        assert_eq!(link.display, "HomeNet -56 dBm 390 Mb/s");
+        assert_eq!(parse_iw_link("Not connected.\n"), WirelessInfo::default());
+    }
+
+    #[test]
+    fn test_collect_tcp_states() {
+        let tcp = "  sl  local_address rem_address   st tx_queue rx_queue tr tm->when retrnsmt   uid  timeout inode
+   0: 00000000:0016 00000000:0000 0A 00000000:00000000 00:00000000 00000000     0        0 1001 1
+   1: 0100007F:1F90 0100007F:C350 01 00000000:00000000 00:00000000 00000000  1000        0 1002 1
+   2: 0100007F:1F90 0100007F:C352 06 00000000:00000000 03:00000F9B 00000000     0        0 0 3
+";
+        let root = tempfile::tempdir().unwrap();
+        write(root.path(), "net/tcp", tcp);
+        write(root.path(), "net/tcp6", tcp);
+        let states = collect_tcp_states(root.path());
+        assert_eq!((states.established, states.listen, states.time_wait), (2, 2, 2));
+        assert_eq!(states.display, "TCP 2 est 2 listen 2 tw 0 cw");
+    }
+}
@@ -1,84 +1,263 @@
-use crate::models::nvidia::NvidiaInfo;
+use crate::collectors::collector::Collector;
+use crate::config::CollectorConfig;
+use crate::models::gpu::{GpuInfo, GpuProcess, GpuVendor};
+use crate::models::SystemInfo;
+use crate::utils::rate;
 use log::{debug, error};
+use roxmltree::{Document, Node};
 use std::time::Instant;
 use systemstat::ByteSize;
 use tokio::process::Command;
 
-pub async fn collect() -> Vec<NvidiaInfo> {
+const MIB: f64 = 1024.0 * 1024.0;
+
+pub(crate) struct NvidiaCollector {
+    config: CollectorConfig,
+}
+
+impl NvidiaCollector {
+    pub fn new(config: CollectorConfig) -> Self {
+        Self { config }
+    }
+}
+
+impl Collector for NvidiaCollector {
+    type Config = CollectorConfig;
+    type Output = Vec<GpuInfo>;
+
+    fn name(&self) -> &'static str {
+        "nvidia"
+    }
+
+    fn config(&self) -> &CollectorConfig {
+        &self.config
+    }
+
+    async fn collect(&mut self) -> Vec<GpuInfo> {
+        collect().await
+    }
+
+    /// Raw GPU list; labels are assigned from `[RESOURCES.sensors]` in `collector::finalise`.
+    fn apply(&self, output: &Vec<GpuInfo>, info: &mut SystemInfo) {
+        info.gpus.extend(output.iter().cloned());
+    }
+}
+
+/// Every GPU reported by `nvidia-smi -q -x`; none when it is missing or fails.
+pub async fn collect() -> Vec<GpuInfo> {
     let start = Instant::now();
 
     let cmd_start = Instant::now();
-    let cmd = Command::new("nvidia-smi")
-        .args(&[
-            "--query-gpu=gpu_name,temperature.gpu,utilization.gpu,memory.used,memory.total",
-            "--format=csv,noheader,nounits",
-        ])
-        .output()
-        .await;
+    let cmd = Command::new("nvidia-smi").args(["-q", "-x"]).output().await;
     debug!("nvidia-smi command execution took: {} ms", cmd_start.elapsed().as_millis());
 
     let result = match cmd {
+        Ok(output) if output.status.success() => {
+            let parse_start = Instant::now();
+            let gpus = match parse_xml(&String::from_utf8_lossy(&output.stdout)) {
+                Ok(gpus) => gpus,
+                Err(e) => {
+                    error!("Error parsing nvidia-smi output: {}", e);
+                    Vec::new()
+                }
+            };
+            debug!("Nvidia GPU data parsing took: {} ms", parse_start.elapsed().as_millis());
+            gpus
+        }
         Ok(output) => {
-            if output.status.success() {
-                let parse_start = Instant::now();
-                let output_str = String::from_utf8_lossy(&output.stdout);
-                // Split by newlines to handle multiple GPUs
-                let info: Vec<NvidiaInfo> = output_str
-                    .lines()
-                    .filter_map(|line| {
-                        let values: Vec<&str> = line.split(',').map(|s| s.trim()).collect();
-
-                        if values.len() == 5 {
-                            let name = values[0];
-                            let temperature = values[1].parse::<f32>().unwrap_or(0.0);
-                            let raw_load = values[2].parse::<f32>().unwrap_or(0.0);
-
-                            // Ensure a GPU load is in range 0-100
-                            let load = if raw_load > 0.0 && raw_load <= 1.0 {
-                                raw_load
-                            } else {
-                                raw_load / 100.0
-                            };
-
-                            let memory_used = ByteSize::mib(values[3].parse::<f32>().unwrap_or(0.0) as u64);
-                            let memory_total = ByteSize::mib(values[4].parse::<f32>().unwrap_or(0.0) as u64);
-
-                            let mem_percent = if memory_total.as_u64() > 0 {
-                                (memory_used.as_u64() as f32 / memory_total.as_u64() as f32) * 100.0
-                            } else {
-                                0.0
-                            };
-
-                            Some(NvidiaInfo {
-                                name: name.to_string(),
-                                temperature,
-                                temperature_display: format!("{} °C", temperature),
-                                load,
-                                load_display: format!("{:.1}%", load * 100.0),
-                                memory_used: memory_used.as_u64(),
-                                memory_used_display: memory_used.to_string(),
-                                memory_total: memory_total.as_u64(),
-                                memory_total_display: memory_total.to_string(),
-                                memory_percent: mem_percent,
-                                memory_percent_display: format!("{:.1}%", mem_percent),
-                            })
-                        } else {
-                            None
-                        }
-                    })
-                    .collect();
-                debug!("Nvidia GPU data parsing took: {} ms", parse_start.elapsed().as_millis());
-                info
-            } else {
-                vec![NvidiaInfo::default()]
-            }
+            error!("nvidia-smi failed: {}", String::from_utf8_lossy(&output.stdout).trim());
+            Vec::new()
         }
         Err(e) => {
             error!("Error getting NVIDIA GPU info: {}", e);
-            vec![NvidiaInfo::default()]
+            Vec::new()
         }
     };
 
     debug!("collect (total Nvidia GPU info collection) took: {} ms", start.elapsed().as_millis());
     result
-}
\ No newline at end of file
+}
+
+/// Parses `nvidia-smi -q -x`. Element names differ between driver versions
+/// (`power_readings`/`gpu_power_readings`, `clocks_throttle_reasons`/`clocks_event_reasons`),
+/// and unsupported values read `N/A`.
+pub fn parse_xml(xml: &str) -> Result<Vec<GpuInfo>, roxmltree::Error> {
+    let document = Document::parse_with_options(
+        xml,
+        roxmltree::ParsingOptions {
+            allow_dtd: true,
+            ..roxmltree::ParsingOptions::default()
+        },
+    )?;
+    Ok(document
+        .root_element()
+        .children()
+        .filter(|node| node.has_tag_name("gpu"))
+        .map(|gpu| parse_gpu(&gpu))
+        .collect())
+}
+
+fn parse_gpu(gpu: &Node) -> GpuInfo {
+    let value = |path: &str| text(gpu, path).and_then(quantity);
+    let first = |paths: &[&str]| paths.iter().find_map(|path| value(path)).unwrap_or(0.0) as f32;
+    let power = first(&[
+        "gpu_power_readings/instant_power_draw",
+        "gpu_power_readings/power_draw",
+        "gpu_power_readings/average_power_draw",
+        "power_readings/power_draw",
+    ]);
+    let power_limit = first(&[
+        "gpu_power_readings/current_power_limit",
+        "power_readings/power_limit",
+        "power_readings/enforced_power_limit",
+    ]);
+
+    let memory_used = (value("fb_memory_usage/used").unwrap_or(0.0) * MIB) as u64;
+    let memory_total = (value("fb_memory_usage/total").unwrap_or(0.0) * MIB) as u64;
+    let memory_percent = if memory_total > 0 {
+        memory_used as f32 / memory_total as f32 * 100.0
+    } else {
+        0.0
+    };
+    let fan = value("fan_speed").map(|percent| percent as f32 / 100.0);
+    let temperature = value("temperature/gpu_temp").unwrap_or(0.0) as f32;
+    let load = value("utilization/gpu_util").unwrap_or(0.0) as f32 / 100.0;
+    let clock = value("clocks/graphics_clock").unwrap_or(0.0) as f32;
+    // KB/s
+    let pcie_rx = (value("pci/rx_util").unwrap_or(0.0) * 1024.0) as u64;
+    let pcie_tx = (value("pci/tx_util").unwrap_or(0.0) * 1024.0) as u64;
+
+    GpuInfo {
+        name: text(gpu, "product_name").unwrap_or_default().to_string(),
+        vendor: GpuVendor::Nvidia,
+        temperature,
+        temperature_display: format!("{} °C", temperature),
+        load,
+        load_display: format!("{:.1}%", load * 100.0),
+        memory_used,
+        memory_used_display: ByteSize::b(memory_used).to_string(),
+        memory_total,
+        memory_total_display: ByteSize::b(memory_total).to_string(),
+        memory_percent,
+        memory_percent_display: format!("{:.1}%", memory_percent),
+        power,
+        power_display: format!("{:.0} W", power),
+        power_limit,
+        fan: fan.unwrap_or(0.0),
+        fan_display: fan.map(|fan| format!("{:.0}%", fan * 100.0)).unwrap_or_else(|| "N/A".to_string()),
+        clock,
+        clock_display: format!("{:.0} MHz", clock),
+        memory_clock: value("clocks/mem_clock").unwrap_or(0.0) as f32,
+        encoder: value("utilization/encoder_util").unwrap_or(0.0) as f32 / 100.0,
+        decoder: value("utilization/decoder_util").unwrap_or(0.0) as f32 / 100.0,
+        pcie_rx,
+        pcie_tx,
+        pcie_display: format!(
+            "↓{} ↑{}",
+            rate::bytes_per_second(pcie_rx),
+            rate::bytes_per_second(pcie_tx)
+        ),
+        processes: child(gpu, "processes")
+            .map(|processes| {
+                processes
+                    .children()
+                    .filter(|node| node.has_tag_name("process_info"))
+                    .map(|process| GpuProcess {
+                        pid: text(&process, "pid").and_then(|pid| pid.parse().ok()).unwrap_or(0),
+                        name: text(&process, "process_name").unwrap_or_default().to_string(),
+                        memory_used: text(&process, "used_memory")
+                            .and_then(quantity)
+                            .map(|mib| (mib * MIB) as u64)
+                            .unwrap_or(0),
+                    })
+                    .collect()
+            })
+            .unwrap_or_default(),
+        throttle_reasons: throttle_reasons(gpu),
+        ..GpuInfo::default()
+    }
+}
+
+/// Active clock event (throttle) reasons, leaving out `gpu_idle`, which is not a limit.
+fn throttle_reasons(gpu: &Node) -> Vec<String> {
+    let reasons = child(gpu, "clocks_event_reasons").or_else(|| child(gpu, "clocks_throttle_reasons"));
+    let Some(reasons) = reasons else {
+        return Vec::new();
+    };
+    reasons
+        .children()
+        .filter(|node| node.is_element() && node.text().map(str::trim) == Some("Active"))
+        .map(|node| {
+            let name = node.tag_name().name();
+            name.strip_prefix("clocks_event_reason_")
+                .or_else(|| name.strip_prefix("clocks_throttle_reason_"))
+                .unwrap_or(name)
+                .to_string()
+        })
+        .filter(|reason| reason != "gpu_idle")
+        .collect()
+}
+
+fn child<'a, 'input>(node: &Node<'a, 'input>, name: &str) -> Option<Node<'a, 'input>> {
+    node.children().find(|child| child.has_tag_name(name))
+}
+
+/// Text of the element at `path` (`a/b`) below `node`.
+fn text<'a>(node: &Node<'a, '_>, path: &str) -> Option<&'a str> {
+    let mut current = *node;
+    for name in path.split('/') {
+        current = child(&current, name)?;
+    }
+    current.text().map(str::trim)
+}
+
+/// The number in values such as `68.12 W`, `3071 MiB` or `30 %`; `None` for `N/A`.
+fn quantity(text: &str) -> Option<f64> {
+    text.split_whitespace().next()?.parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_xml() {
+        let gpus = parse_xml(include_str!("../../tests/fixtures/nvidia/a2000.xml")).unwrap();
+        assert_eq!(gpus.len(), 1);
+        let gpu = &gpus[0];
+        assert_eq!(gpu.name, "NVIDIA RTX A2000 12GB");
+        assert_eq!(gpu.temperature, 68.0);
+        assert_eq!(gpu.load, 0.87);
+        assert_eq!(gpu.memory_used, 3071 * 1024 * 1024);
+        assert_eq!(gpu.memory_total, 12282 * 1024 * 1024);
+        assert_eq!(gpu.power, 69.87);
+        assert_eq!(gpu.power_limit, 70.0);
+        assert_eq!(gpu.fan_display, "30%");
+        assert_eq!((gpu.clock, gpu.memory_clock), (1560.0, 7000.0));
+        assert_eq!((gpu.encoder, gpu.decoder), (0.12, 0.0));
+        assert_eq!((gpu.pcie_rx, gpu.pcie_tx), (512 * 1024, 2150 * 1024));
+        assert_eq!(gpu.throttle_reasons, vec!["sw_power_cap", "sw_thermal_slowdown"]);
+        assert_eq!(
+            gpu.processes,
+            vec![
+                GpuProcess { pid: 2184, name: "/usr/lib/xorg/Xorg".to_string(), memory_used: 212 * 1024 * 1024 },
+                GpuProcess { pid: 40122, name: "python3".to_string(), memory_used: 2790 * 1024 * 1024 },
+            ]
+        );
+
+        // Older drivers: power_readings, clocks_throttle_reasons and N/A values
+        let gpus = parse_xml(include_str!("../../tests/fixtures/nvidia/older-two-gpus.xml")).unwrap();
+        assert_eq!(gpus.len(), 2);
+        assert_eq!(gpus[0].name, "Tesla T4");
+        assert_eq!(gpus[0].power, 9.83);
+        assert_eq!(gpus[0].fan_display, "N/A");
+        assert!(gpus[0].throttle_reasons.is_empty());
+        assert!(gpus[0].processes.is_empty());
+        assert_eq!(gpus[1].load, 0.0);
+        assert_eq!(gpus[1].power, 0.0);
+        assert_eq!(gpus[1].fan, 0.45);
+        assert_eq!(gpus[1].memory_percent_display, "12.6%");
+
+        assert!(parse_xml("NVIDIA-SMI has failed because it couldn't communicate with the driver.").is_err());
+    }
+}
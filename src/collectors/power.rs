@@ -0,0 +1,282 @@
+use crate::collectors::collector::Collector;
+use crate::config::CollectorConfig;
+use crate::models::power::{BatteryInfo, PowerInfo, RaplDomain};
+use crate::models::SystemInfo;
+use crate::utils::hwmon::get_file_line;
+use crate::utils::rate::RateCounter;
+use log::debug;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::Instant;
+
+/// Batteries and AC adapters from `/sys/class/power_supply`, and RAPL package/DRAM power from
+/// `/sys/class/powercap`.
+pub(crate) struct PowerCollector {
+    config: CollectorConfig,
+    power_supply_root: PathBuf,
+    powercap_root: PathBuf,
+    /// RAPL `energy_uj` counters by zone, e.g. `intel-rapl:0`.
+    counters: HashMap<String, RateCounter>,
+}
+
+impl PowerCollector {
+    pub fn new(config: CollectorConfig) -> Self {
+        Self {
+            config,
+            power_supply_root: PathBuf::from("/sys/class/power_supply"),
+            powercap_root: PathBuf::from("/sys/class/powercap"),
+            counters: HashMap::new(),
+        }
+    }
+}
+
+impl Collector for PowerCollector {
+    type Config = CollectorConfig;
+    type Output = PowerInfo;
+
+    fn name(&self) -> &'static str {
+        "power"
+    }
+
+    fn config(&self) -> &CollectorConfig {
+        &self.config
+    }
+
+    async fn collect(&mut self) -> PowerInfo {
+        let start = Instant::now();
+        let (batteries, ac_online) = read_power_supplies(&self.power_supply_root);
+        let rapl = read_rapl(&self.powercap_root, &mut self.counters, Instant::now());
+        let info = with_display(PowerInfo {
+            batteries,
+            ac_online,
+            rapl,
+            display: String::new(),
+        });
+        debug!("collect_power took: {} ms", start.elapsed().as_millis());
+        info
+    }
+
+    fn apply(&self, output: &PowerInfo, info: &mut SystemInfo) {
+        info.power = output.clone();
+    }
+}
+
+/// System batteries, and whether any mains or USB adapter is online (`None` without adapters).
+pub fn read_power_supplies(root: &Path) -> (Vec<BatteryInfo>, Option<bool>) {
+    let mut supplies: Vec<PathBuf> = fs::read_dir(root)
+        .map(|dir| dir.flatten().map(|entry| entry.path()).collect())
+        .unwrap_or_default();
+    supplies.sort();
+
+    let mut batteries = Vec::new();
+    let mut ac_online = None;
+    for supply in supplies {
+        match get_file_line(&supply.join("type"), 16).as_deref() {
+            Some("Mains") | Some("USB") => {
+                if let Some(online) = number::<u8>(&supply.join("online")) {
+                    ac_online = Some(ac_online.unwrap_or(false) || online == 1);
+                }
+            }
+            // Wireless mice and keyboards report their batteries with the `Device` scope
+            Some("Battery") if get_file_line(&supply.join("scope"), 16).as_deref() != Some("Device") => {
+                batteries.push(read_battery(&supply));
+            }
+            _ => {}
+        }
+    }
+    (batteries, ac_online)
+}
+
+/// Reads a battery reporting either energy (µWh, µW) or charge (µAh, µA) with the voltage (µV).
+fn read_battery(supply: &Path) -> BatteryInfo {
+    let value = |file: &str| number::<f64>(&supply.join(file));
+    let voltage = value("voltage_now").map(|uv| uv / 1_000_000.0);
+    let from_charge = |file: &str| Some(value(file)? * voltage?);
+
+    let energy = value("energy_now").or_else(|| from_charge("charge_now"));
+    let energy_full = value("energy_full").or_else(|| from_charge("charge_full"));
+    // Some drivers report a negative current while discharging
+    let power = value("power_now").or_else(|| from_charge("current_now")).map(f64::abs);
+
+    let capacity = match (value("capacity"), energy, energy_full) {
+        (Some(percent), _, _) => percent / 100.0,
+        (None, Some(energy), Some(full)) if full > 0.0 => energy / full,
+        _ => 0.0,
+    };
+    let status = get_file_line(&supply.join("status"), 32).unwrap_or_else(|| "Unknown".to_string());
+
+    let hours = match (status.as_str(), energy, energy_full, power) {
+        (_, _, _, Some(power)) if power <= 0.0 => None,
+        ("Discharging", Some(energy), _, Some(power)) => Some(energy / power),
+        ("Charging", Some(energy), Some(full), Some(power)) => Some((full - energy).max(0.0) / power),
+        _ => None,
+    };
+    let time_remaining = match status.as_str() {
+        "Discharging" => number(&supply.join("time_to_empty_now")),
+        "Charging" => number(&supply.join("time_to_full_now")),
+        _ => None,
+    }
+    .or(hours.map(|hours| (hours * 3600.0) as u64));
+
+    let power = power.unwrap_or(0.0) / 1_000_000.0;
+    let time_display = match time_remaining {
+        Some(seconds) if status == "Charging" => format!("{} to full", hours_minutes(seconds)),
+        Some(seconds) => format!("{} left", hours_minutes(seconds)),
+        None => String::new(),
+    };
+    BatteryInfo {
+        name: supply.file_name().unwrap_or_default().to_string_lossy().to_string(),
+        capacity: capacity.clamp(0.0, 1.0) as f32,
+        capacity_display: format!("{:.0}%", capacity * 100.0),
+        status,
+        power: power as f32,
+        power_display: format!("{:.1} W", power),
+        energy: (energy.unwrap_or(0.0) / 1_000_000.0) as f32,
+        energy_full: (energy_full.unwrap_or(0.0) / 1_000_000.0) as f32,
+        time_remaining,
+        time_display,
+    }
+}
+
+/// Package and DRAM power from the RAPL zones under `root`, e.g. `intel-rapl:0` and
+/// `intel-rapl:0:2`. Zones without a previous sample, or whose counters only root can read, are
+/// left out.
+pub fn read_rapl(root: &Path, counters: &mut HashMap<String, RateCounter>, now: Instant) -> Vec<RaplDomain> {
+    let mut zones: Vec<String> = fs::read_dir(root)
+        .map(|dir| {
+            dir.flatten()
+                .map(|entry| entry.file_name().to_string_lossy().to_string())
+                .filter(|name| name.starts_with("intel-rapl:"))
+                .collect()
+        })
+        .unwrap_or_default();
+    zones.sort();
+
+    let mut domains = Vec::new();
+    for zone in zones {
+        let zone_dir = root.join(&zone);
+        let Some(name) = get_file_line(&zone_dir.join("name"), 32) else {
+            continue;
+        };
+        if !name.starts_with("package") && name != "dram" {
+            continue;
+        }
+        // The counter wraps at max_energy_range_uj, which skips one sample
+        let Some(power) = number::<u64>(&zone_dir.join("energy_uj"))
+            .and_then(|uj| counters.entry(zone).or_default().update(uj, now))
+            .map(|uj_per_s| (uj_per_s / 1_000_000.0) as f32)
+        else {
+            continue;
+        };
+        domains.push(RaplDomain {
+            power_display: format!("{:.1} W", power),
+            name,
+            power,
+        });
+    }
+    domains
+}
+
+fn with_display(info: PowerInfo) -> PowerInfo {
+    let mut parts = Vec::new();
+    match info.ac_online {
+        Some(true) => parts.push("AC".to_string()),
+        Some(false) => parts.push("On battery".to_string()),
+        None => {}
+    }
+    if !info.rapl.is_empty() {
+        let rapl: Vec<String> = info
+            .rapl
+            .iter()
+            .map(|domain| format!("{} {}", domain.name, domain.power_display))
+            .collect();
+        parts.push(rapl.join(" "));
+    }
+    PowerInfo {
+        display: parts.join(" | "),
+        ..info
+    }
+}
+
+/// e.g. `2h 05m` or `45m`.
+fn hours_minutes(seconds: u64) -> String {
+    let (hours, minutes) = (seconds / 3600, seconds % 3600 / 60);
+    if hours > 0 {
+        format!("{}h {:02}m", hours, minutes)
+    } else {
+        format!("{}m", minutes)
+    }
+}
+
+fn number<T: FromStr>(path: &Path) -> Option<T> {
+    get_file_line(path, 24)?.parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::testing::write;
+    use std::time::Duration;
+
+    #[test]
+    fn test_read_power_supplies() {
+        let root = tempfile::tempdir().unwrap();
+        let supplies = root.path().join("power_supply");
+
+        write(&supplies, "AC/type", "Mains");
+        write(&supplies, "AC/online", "0");
+        // Energy reporting battery, discharging
+        write(&supplies, "BAT0/type", "Battery");
+        write(&supplies, "BAT0/status", "Discharging");
+        write(&supplies, "BAT0/capacity", "80");
+        write(&supplies, "BAT0/energy_now", "40000000");
+        write(&supplies, "BAT0/energy_full", "50000000");
+        write(&supplies, "BAT0/power_now", "16000000");
+        // Charge reporting battery, charging
+        write(&supplies, "BAT1/type", "Battery");
+        write(&supplies, "BAT1/status", "Charging");
+        write(&supplies, "BAT1/charge_now", "1000000");
+        write(&supplies, "BAT1/charge_full", "4000000");
+        write(&supplies, "BAT1/current_now", "-2000000");
+        write(&supplies, "BAT1/voltage_now", "12000000");
+        // A wireless mouse
+        write(&supplies, "hid-mouse/type", "Battery");
+        write(&supplies, "hid-mouse/scope", "Device");
+
+        let (batteries, ac_online) = read_power_supplies(&supplies);
+        assert_eq!(ac_online, Some(false));
+        assert_eq!(batteries.len(), 2);
+        let bat0 = &batteries[0];
+        assert_eq!((bat0.name.as_str(), bat0.capacity, bat0.energy), ("BAT0", 0.8, 40.0));
+        assert_eq!((bat0.power_display.as_str(), bat0.time_remaining), ("16.0 W", Some(9_000)));
+        assert_eq!(bat0.time_display, "2h 30m left");
+        let bat1 = &batteries[1];
+        assert!(bat1.charging());
+        assert_eq!((bat1.capacity_display.as_str(), bat1.energy_full, bat1.power), ("25%", 48.0, 24.0));
+        assert_eq!(bat1.time_display, "1h 30m to full");
+    }
+
+    #[test]
+    fn test_read_rapl() {
+        let root = tempfile::tempdir().unwrap();
+        let powercap = root.path().join("powercap");
+        write(&powercap, "intel-rapl:0/name", "package-0");
+        write(&powercap, "intel-rapl:0/energy_uj", "1000000");
+        write(&powercap, "intel-rapl:0:0/name", "core");
+        write(&powercap, "intel-rapl:0:0/energy_uj", "1000000");
+        write(&powercap, "intel-rapl:0:2/name", "dram");
+        write(&powercap, "intel-rapl:0:2/energy_uj", "5000000");
+
+        // Power needs a second sample
+        let (mut counters, now) = (HashMap::new(), Instant::now());
+        assert!(read_rapl(&powercap, &mut counters, now).is_empty());
+        write(&powercap, "intel-rapl:0/energy_uj", "13500000");
+        write(&powercap, "intel-rapl:0:2/energy_uj", "6000000");
+        let rapl = read_rapl(&powercap, &mut counters, now + Duration::from_secs(1));
+        assert_eq!(rapl.len(), 2);
+        let info = with_display(PowerInfo { ac_online: Some(false), rapl, ..PowerInfo::default() });
+        assert_eq!(info.display, "On battery | package-0 12.5 W dram 1.0 W");
+    }
+}
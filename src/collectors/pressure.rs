@@ -0,0 +1,189 @@
+use crate::collectors::collector::Collector;
+use crate::collectors::process::cgroup_name;
+use crate::config::PressureConfig;
+use crate::models::pressure::{CgroupPressure, Pressure, PressureInfo, ResourcePressure};
+use crate::models::SystemInfo;
+use crate::utils::cgroup;
+use log::{debug, warn};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+pub(crate) struct PressureCollector {
+    config: PressureConfig,
+    proc_pressure: PathBuf,
+    cgroup_root: PathBuf,
+    /// Whether the missing `/proc/pressure` was already reported.
+    warned: bool,
+}
+
+impl PressureCollector {
+    pub fn new(config: PressureConfig) -> Self {
+        Self {
+            config,
+            proc_pressure: PathBuf::from("/proc/pressure"),
+            cgroup_root: cgroup::unified_root(Path::new("/sys/fs/cgroup")),
+            warned: false,
+        }
+    }
+}
+
+impl Collector for PressureCollector {
+    type Config = PressureConfig;
+    type Output = PressureInfo;
+
+    fn name(&self) -> &'static str {
+        "pressure"
+    }
+
+    fn config(&self) -> &PressureConfig {
+        &self.config
+    }
+
+    async fn collect(&mut self) -> PressureInfo {
+        let info = collect_pressure(&self.proc_pressure, &self.cgroup_root, &self.config.cgroups);
+        if info.system.cpu.is_none() && !self.warned {
+            warn!("No pressure stall information in {}", self.proc_pressure.display());
+            self.warned = true;
+        }
+        info
+    }
+
+    fn apply(&self, output: &PressureInfo, info: &mut SystemInfo) {
+        info.pressure = output.clone();
+    }
+}
+
+/// System pressure from `proc_pressure/{cpu,memory,io}` and each cgroup's `{cpu,memory,io}.pressure`.
+pub fn collect_pressure(proc_pressure: &Path, cgroup_root: &Path, cgroups: &[String]) -> PressureInfo {
+    let start = Instant::now();
+    let system = read_resources(|resource| proc_pressure.join(resource));
+    let cgroups = cgroups
+        .iter()
+        .map(|path| {
+            let path = path.trim_matches('/');
+            let dir = cgroup_root.join(path);
+            CgroupPressure {
+                path: path.to_string(),
+                name: cgroup_name(path),
+                pressure: read_resources(|resource| dir.join(format!("{}.pressure", resource))),
+            }
+        })
+        .collect();
+    debug!("collect_pressure took: {} ms", start.elapsed().as_millis());
+    PressureInfo { system, cgroups }
+}
+
+fn read_resources(file: impl Fn(&str) -> PathBuf) -> ResourcePressure {
+    let read = |resource: &str| {
+        fs::read_to_string(file(resource))
+            .ok()
+            .and_then(|text| parse_pressure(&text))
+    };
+    let mut pressure = ResourcePressure {
+        cpu: read("cpu"),
+        memory: read("memory"),
+        io: read("io"),
+        display: String::new(),
+    };
+    pressure.display = [("cpu", pressure.cpu), ("mem", pressure.memory), ("io", pressure.io)]
+        .iter()
+        .filter_map(|(name, pressure)| {
+            pressure.map(|pressure| format!("{} {:.1}%", name, pressure.some.avg10))
+        })
+        .collect::<Vec<_>>()
+        .join(" ");
+    pressure
+}
+
+/// Parses a PSI file:
+///
+/// ```text
+/// some avg10=5.39 avg60=3.86 avg300=3.49 total=136889064
+/// full avg10=0.00 avg60=0.00 avg300=0.00 total=0
+/// ```
+///
+/// `full` is absent for system CPU pressure on kernels before 5.13 and reads as zero.
+pub fn parse_pressure(text: &str) -> Option<Pressure> {
+    let mut pressure = Pressure::default();
+    let mut found = false;
+    for line in text.lines() {
+        let mut fields = line.split_whitespace();
+        let averages = match fields.next() {
+            Some("some") => &mut pressure.some,
+            Some("full") => &mut pressure.full,
+            _ => continue,
+        };
+        found = true;
+        for field in fields {
+            let Some((key, value)) = field.split_once('=') else {
+                continue;
+            };
+            match key {
+                "avg10" => averages.avg10 = value.parse().unwrap_or(0.0),
+                "avg60" => averages.avg60 = value.parse().unwrap_or(0.0),
+                "avg300" => averages.avg300 = value.parse().unwrap_or(0.0),
+                "total" => averages.total = value.parse().unwrap_or(0),
+                _ => {}
+            }
+        }
+    }
+    found.then_some(pressure)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::testing::write;
+    use crate::models::pressure::PressureAverages;
+
+    #[test]
+    fn test_parse_pressure() {
+        let memory = parse_pressure(
+            "some avg10=0.76 avg60=0.41 avg300=0.27 total=14987575\n\
+             full avg10=0.27 avg60=0.15 avg300=0.14 total=11175614\n",
+        )
+        .unwrap();
+        assert_eq!((memory.some.avg10, memory.some.total), (0.76, 14987575));
+        assert_eq!(memory.full.avg300, 0.14);
+        // The CPU line has no `full` before Linux 5.13
+        let cpu = parse_pressure("some avg10=5.39 avg60=3.86 avg300=3.49 total=136889064\n").unwrap();
+        assert_eq!(cpu.full, PressureAverages::default());
+        assert_eq!(parse_pressure(""), None);
+    }
+
+    #[test]
+    fn test_system_pressure() {
+        let root = tempfile::tempdir().unwrap();
+        let proc_pressure = root.path().join("pressure");
+        write(&proc_pressure, "cpu", "some avg10=5.39 avg60=3.86 avg300=3.49 total=136889064\n");
+        write(&proc_pressure, "memory", "some avg10=0.76 avg60=0.41 avg300=0.27 total=14987575\n");
+
+        let info = collect_pressure(&proc_pressure, &root.path().join("cgroup"), &[]);
+        assert_eq!(info.system.cpu.unwrap().some.avg10, 5.39);
+        assert_eq!(info.system.io, None);
+        assert_eq!(info.system.display, "cpu 5.4% mem 0.8%");
+        assert_eq!(info.system.highest(), 0.0539);
+        assert!(info.cgroups.is_empty());
+    }
+
+    #[test]
+    fn test_cgroup_pressure() {
+        let root = tempfile::tempdir().unwrap();
+        let cgroups = root.path().join("cgroup");
+        write(
+            &cgroups,
+            "system.slice/docker.service/io.pressure",
+            "some avg10=12.50 avg60=8.00 avg300=2.00 total=900\n\
+             full avg10=10.00 avg60=6.00 avg300=1.00 total=700\n",
+        );
+
+        let watched = ["/system.slice/docker.service".to_string()];
+        let info = collect_pressure(&root.path().join("pressure"), &cgroups, &watched);
+        let docker = &info.cgroups[0];
+        assert_eq!(docker.path, "system.slice/docker.service");
+        assert_eq!(docker.name, "docker.service");
+        assert_eq!(docker.pressure.io.unwrap().full.avg10, 10.0);
+        assert_eq!(docker.pressure.display, "io 12.5%");
+    }
+}
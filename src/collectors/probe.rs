@@ -0,0 +1,282 @@
+use crate::collectors::background::{Background, Worker};
+use crate::collectors::collector::Collector;
+use crate::config::{ProbeConfig, ProbeKind, ProbesConfig};
+use crate::models::probe::ProbeInfo;
+use crate::models::SystemInfo;
+use futures::future::join_all;
+use indexmap::IndexMap;
+use log::debug;
+use std::time::{Duration, Instant};
+use tokio::net::{lookup_host, TcpStream};
+use tokio::process::Command;
+use tokio::time::timeout;
+
+/// One configured probe and its results so far.
+struct Probe {
+    config: ProbeConfig,
+    last_run: Option<Instant>,
+    info: ProbeInfo,
+}
+
+impl Probe {
+    fn due(&self, now: Instant) -> bool {
+        self.last_run
+            .is_none_or(|last| now.saturating_duration_since(last) >= Duration::from_secs(self.config.interval))
+    }
+
+    /// Records a check that took `result` milliseconds, or failed with that message.
+    fn record(&mut self, result: Result<f32, String>, history: usize) {
+        let info = &mut self.info;
+        info.up = result.is_ok();
+        info.latency = result.as_ref().ok().copied();
+        info.latency_display = match &result {
+            Ok(latency) => format!("{:.0} ms", latency),
+            Err(message) => message.clone(),
+        };
+        info.history.push(info.latency);
+        let excess = info.history.len().saturating_sub(history);
+        info.history.drain(..excess);
+    }
+}
+
+/// Every configured probe, checked in the background so that unreachable targets never hold up a frame.
+struct Probes {
+    probes: Vec<Probe>,
+    history: usize,
+}
+
+impl Probes {
+    fn new(probes: IndexMap<String, ProbeConfig>, history: usize) -> Self {
+        let probes = probes
+            .into_iter()
+            .map(|(name, config)| Probe {
+                info: ProbeInfo {
+                    name,
+                    target: config.target.clone(),
+                    latency_display: "pending".to_string(),
+                    ..ProbeInfo::default()
+                },
+                config,
+                last_run: None,
+            })
+            .collect();
+        Self { probes, history }
+    }
+
+    fn results(&self) -> Vec<ProbeInfo> {
+        self.probes.iter().map(|probe| probe.info.clone()).collect()
+    }
+}
+
+impl Worker for Probes {
+    type Output = Vec<ProbeInfo>;
+
+    /// Runs the probes that are due concurrently; the others keep their last result.
+    async fn run(&mut self) -> Vec<ProbeInfo> {
+        let now = Instant::now();
+        let history = self.history;
+        let checks = self.probes.iter_mut().filter(|probe| probe.due(now)).map(|probe| async move {
+            probe.last_run = Some(now);
+            let result = check(&probe.config).await;
+            debug!("probe {} ({}): {:?}", probe.info.name, probe.config.target, result);
+            probe.record(result, history);
+        });
+        join_all(checks).await;
+        debug!("collect_probes took: {} ms", now.elapsed().as_millis());
+        self.results()
+    }
+}
+
+pub(crate) struct ProbeCollector {
+    config: ProbesConfig,
+    probes: Background<Probes>,
+}
+
+impl ProbeCollector {
+    pub fn new(config: ProbesConfig, probes: IndexMap<String, ProbeConfig>) -> Self {
+        let probes = Probes::new(probes, config.history);
+        let initial = probes.results();
+        let tick = Duration::from_secs(config.interval.max(1));
+        Self { config, probes: Background::new(probes, initial, tick) }
+    }
+}
+
+impl Collector for ProbeCollector {
+    type Config = ProbesConfig;
+    type Output = Vec<ProbeInfo>;
+
+    fn name(&self) -> &'static str {
+        "probes"
+    }
+
+    fn config(&self) -> &ProbesConfig {
+        &self.config
+    }
+
+    /// The results of the last background run.
+    async fn collect(&mut self) -> Vec<ProbeInfo> {
+        self.probes.latest()
+    }
+
+    fn apply(&self, output: &Vec<ProbeInfo>, info: &mut SystemInfo) {
+        info.probes = output.clone();
+    }
+}
+
+/// Latency of one check in milliseconds, or why it failed.
+pub async fn check(config: &ProbeConfig) -> Result<f32, String> {
+    let limit = Duration::from_secs(config.timeout.max(1));
+    let target = config.target.as_str();
+    let start = Instant::now();
+    let elapsed = || start.elapsed().as_secs_f32() * 1000.0;
+
+    match config.kind {
+        ProbeKind::Tcp => match timeout(limit, TcpStream::connect(target)).await {
+            Ok(Ok(_)) => Ok(elapsed()),
+            Ok(Err(e)) => Err(e.kind().to_string()),
+            Err(_) => Err("timeout".to_string()),
+        },
+        ProbeKind::Dns => match timeout(limit, lookup_host((target, 0))).await {
+            Ok(Ok(mut addresses)) => match addresses.next() {
+                Some(_) => Ok(elapsed()),
+                None => Err("no addresses".to_string()),
+            },
+            Ok(Err(_)) => Err("not resolved".to_string()),
+            Err(_) => Err("timeout".to_string()),
+        },
+        ProbeKind::Http => {
+            let (url, expect) = (config.target.clone(), config.expect);
+            let status = tokio::task::spawn_blocking(move || http_status(&url, limit))
+                .await
+                .map_err(|e| e.to_string())??;
+            let expected = if expect == 0 { status < 400 } else { status == expect };
+            if expected {
+                Ok(elapsed())
+            } else {
+                Err(format!("HTTP {}", status))
+            }
+        }
+        ProbeKind::Icmp => ping(target, config.timeout.max(1)).await,
+    }
+}
+
+/// Status code of a GET, following redirects.
+fn http_status(url: &str, limit: Duration) -> Result<u16, String> {
+    let agent = ureq::AgentBuilder::new().timeout(limit).build();
+    match agent.get(url).call() {
+        Ok(response) => Ok(response.status()),
+        Err(ureq::Error::Status(status, _)) => Ok(status),
+        Err(ureq::Error::Transport(transport)) => Err(transport.kind().to_string()),
+    }
+}
+
+/// Round trip time of one echo request, as reported by `ping`.
+async fn ping(host: &str, timeout_secs: u64) -> Result<f32, String> {
+    let output = Command::new("ping")
+        .args(["-n", "-c", "1", "-W", &timeout_secs.to_string(), host])
+        .output()
+        .await
+        .map_err(|e| match e.kind() {
+            std::io::ErrorKind::NotFound => "ping missing".to_string(),
+            kind => format!("ping: {}", kind),
+        })?;
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    match parse_ping_time(&stdout) {
+        Some(time) if output.status.success() => Ok(time),
+        // Exit status 1: no reply; 2: other errors such as "Operation not permitted"
+        _ if output.status.code() == Some(1) => Err("no reply".to_string()),
+        _ => Err(String::from_utf8_lossy(&output.stderr).trim().to_string()),
+    }
+}
+
+/// The `time=` of a reply line, e.g. `64 bytes from 1.1.1.1: icmp_seq=1 ttl=57 time=12.3 ms`.
+pub fn parse_ping_time(output: &str) -> Option<f32> {
+    output
+        .split_whitespace()
+        .find_map(|field| field.strip_prefix("time="))
+        .and_then(|time| time.parse().ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    fn probe(kind: ProbeKind, target: &str) -> ProbeConfig {
+        ProbeConfig {
+            kind,
+            target: target.to_string(),
+            timeout: 2,
+            ..ProbeConfig::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_http_probe() {
+        // Stand-in HTTP server answering every request with 204, then 503
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            for status in ["204 No Content", "503 Service Unavailable", "503 Service Unavailable"] {
+                let (mut stream, _) = listener.accept().await.unwrap();
+                let mut request = [0; 1024];
+                let _ = stream.read(&mut request).await.unwrap();
+                let response = format!("HTTP/1.1 {}\r\nContent-Length: 0\r\nConnection: close\r\n\r\n", status);
+                stream.write_all(response.as_bytes()).await.unwrap();
+            }
+        });
+
+        let url = format!("http://{}/health", address);
+        assert!(check(&probe(ProbeKind::Http, &url)).await.is_ok());
+        assert_eq!(check(&probe(ProbeKind::Http, &url)).await, Err("HTTP 503".to_string()));
+        let expect_503 = ProbeConfig { expect: 503, ..probe(ProbeKind::Http, &url) };
+        assert!(check(&expect_503).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_tcp_probe() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let open = probe(ProbeKind::Tcp, &listener.local_addr().unwrap().to_string());
+        assert!(check(&open).await.is_ok());
+        drop(listener);
+        assert_eq!(check(&open).await, Err("connection refused".to_string()));
+    }
+
+    #[tokio::test]
+    async fn test_dns_probe() {
+        assert!(check(&probe(ProbeKind::Dns, "localhost")).await.is_ok());
+        assert!(check(&probe(ProbeKind::Dns, "nonexistent.invalid")).await.is_err());
+    }
+
+    #[test]
+    fn test_parse_ping_time() {
+        assert_eq!(parse_ping_time("64 bytes from 1.1.1.1: icmp_seq=1 ttl=57 time=12.3 ms\n"), Some(12.3));
+        assert_eq!(parse_ping_time("1 packets transmitted, 0 received, 100% packet loss\n"), None);
+    }
+
+    #[tokio::test]
+    async fn test_probe_intervals() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let open = probe(ProbeKind::Tcp, &listener.local_addr().unwrap().to_string());
+        let closed = TcpListener::bind("127.0.0.1:0").await.unwrap().local_addr().unwrap().to_string();
+
+        // Each probe keeps its own interval and a bounded history
+        let mut probes = IndexMap::new();
+        probes.insert("open".to_string(), open);
+        probes.insert("closed".to_string(), ProbeConfig { interval: 0, ..probe(ProbeKind::Tcp, &closed) });
+        let mut worker = Probes::new(probes.clone(), 2);
+        for _ in 0..3 {
+            worker.run().await;
+        }
+        let results = worker.run().await;
+        assert_eq!((results[0].name.as_str(), results[0].up, results[0].history.len()), ("open", true, 1));
+        assert_eq!(results[1].history, vec![None, None]);
+        assert_eq!(results[1].latency_display, "connection refused");
+
+        // The collector answers before the first checks have finished
+        let mut collector = ProbeCollector::new(ProbesConfig::default(), probes);
+        let pending = collector.collect().await;
+        assert_eq!((pending[0].name.as_str(), pending[0].latency_display.as_str()), ("open", "pending"));
+    }
+}
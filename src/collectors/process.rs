@@ -0,0 +1,554 @@
+use crate::collectors::collector::Collector;
+use crate::collectors::cpu;
+use crate::config::{ProcessGrouping, ProcessesConfig};
+use crate::models::system::ProcessInfo;
+use crate::models::SystemInfo;
+use crate::utils::rate;
+use log::{debug, error};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+use systemstat::ByteSize;
+
+/// Fields of `/proc/[pid]/stat` used here.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ProcStat {
+    pub pid: u32,
+    pub comm: String,
+    pub state: char,
+    pub ppid: u32,
+    /// User plus system time, in clock ticks.
+    pub cpu_ticks: u64,
+    pub threads: u64,
+    /// Clock ticks after boot the process started, tells a reused pid apart.
+    pub start_time: u64,
+}
+
+/// TCP state `LISTEN` in `/proc/net/tcp`.
+const TCP_LISTEN: u8 = 0x0a;
+
+/// One socket of `/proc/net/tcp` or `/proc/net/tcp6`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TcpSocket {
+    /// Kernel TCP state, `0x01` established to `0x0b` closing.
+    pub state: u8,
+    pub inode: u64,
+}
+
+/// Rankings the processes panel can show.
+#[derive(Debug, Clone, Default)]
+pub struct TopProcesses {
+    pub cpu: Vec<ProcessInfo>,
+    pub memory: Vec<ProcessInfo>,
+    pub io: Vec<ProcessInfo>,
+    pub connections: Vec<ProcessInfo>,
+}
+
+/// What doesn't change during a process' lifetime is read once per process; the counters are
+/// those of the previous run.
+#[derive(Debug, Clone, Default)]
+struct Known {
+    start_time: u64,
+    cpu_ticks: u64,
+    read_bytes: u64,
+    write_bytes: u64,
+    name: String,
+    user: String,
+    cgroup: String,
+}
+
+/// Top processes by CPU, memory, disk I/O and network connections, read from `/proc`.
+pub(crate) struct ProcessCollector {
+    config: ProcessesConfig,
+    /// Rankings shown on the dashboard; I/O and connections are only read when shown.
+    rankings: Vec<String>,
+    proc_root: PathBuf,
+    page_size: u64,
+    /// Processes seen in the previous run, by pid.
+    known: HashMap<u32, Known>,
+    /// Aggregate CPU time of the previous run, CPU % is measured against it.
+    previous_total: Option<u64>,
+    previous_time: Option<Instant>,
+    users: HashMap<u32, String>,
+}
+
+impl ProcessCollector {
+    pub fn new(config: ProcessesConfig, rankings: Vec<String>) -> Self {
+        // SAFETY: sysconf only reads a system constant
+        let page_size = unsafe { libc::sysconf(libc::_SC_PAGESIZE) };
+        Self {
+            config,
+            rankings,
+            proc_root: PathBuf::from("/proc"),
+            page_size: if page_size > 0 { page_size as u64 } else { 4096 },
+            known: HashMap::new(),
+            previous_total: None,
+            previous_time: None,
+            users: parse_passwd(&fs::read_to_string("/etc/passwd").unwrap_or_default()),
+        }
+    }
+}
+
+impl Collector for ProcessCollector {
+    type Config = ProcessesConfig;
+    type Output = TopProcesses;
+
+    fn name(&self) -> &'static str {
+        "processes"
+    }
+
+    fn config(&self) -> &ProcessesConfig {
+        &self.config
+    }
+
+    async fn collect(&mut self) -> TopProcesses {
+        let start = Instant::now();
+        let with_io = self.rankings.iter().any(|r| r == "io");
+        let with_connections = self.rankings.iter().any(|r| r == "connections");
+        let seconds = self
+            .previous_time
+            .replace(start)
+            .map(|previous| start.duration_since(previous).as_secs_f64())
+            .unwrap_or(0.0);
+
+        // Inodes of TCP sockets that aren't listening, matched against each process' descriptors
+        let connections: HashSet<u64> = if with_connections {
+            ["net/tcp", "net/tcp6"]
+                .iter()
+                .filter_map(|file| fs::read_to_string(self.proc_root.join(file)).ok())
+                .flat_map(|text| parse_net_tcp(&text))
+                .filter(|socket| socket.state != TCP_LISTEN)
+                .map(|socket| socket.inode)
+                .collect()
+        } else {
+            HashSet::new()
+        };
+
+        let stat = fs::read_to_string(self.proc_root.join("stat"))
+            .map(|text| cpu::parse_proc_stat(&text))
+            .unwrap_or_default();
+        let total = stat.total.total();
+        let cpus = stat.cores.len().max(1) as f32;
+        // Ticks one CPU could have run since the previous sample
+        let elapsed = self
+            .previous_total
+            .map(|previous| total.saturating_sub(previous) as f32 / cpus)
+            .unwrap_or(0.0);
+        self.previous_total = Some(total);
+
+        let memory_total = fs::read_to_string(self.proc_root.join("meminfo"))
+            .ok()
+            .and_then(|text| parse_mem_total(&text))
+            .unwrap_or(0);
+
+        let mut known = HashMap::new();
+        let mut processes = Vec::new();
+        for pid in list_pids(&self.proc_root) {
+            let dir = self.proc_root.join(pid.to_string());
+            let Some(stat) = fs::read_to_string(dir.join("stat")).ok().and_then(|text| parse_stat(&text)) else {
+                continue; // Exited meanwhile
+            };
+            let rss = fs::read_to_string(dir.join("statm"))
+                .ok()
+                .and_then(|text| parse_statm(&text))
+                .unwrap_or(0)
+                .saturating_mul(self.page_size);
+
+            let previous = self.known.remove(&pid).filter(|p| p.start_time == stat.start_time);
+            let cpu_percent = match &previous {
+                Some(previous) if elapsed > 0.0 => {
+                    stat.cpu_ticks.saturating_sub(previous.cpu_ticks) as f32 / elapsed * 100.0
+                }
+                _ => 0.0,
+            };
+            let (read_bytes, write_bytes) = if with_io {
+                fs::read_to_string(dir.join("io")).ok().and_then(|text| parse_io(&text)).unwrap_or_default()
+            } else {
+                (0, 0)
+            };
+            let (read_rate, write_rate) = match &previous {
+                Some(previous) if seconds > 0.0 => (
+                    (read_bytes.saturating_sub(previous.read_bytes) as f64 / seconds) as u64,
+                    (write_bytes.saturating_sub(previous.write_bytes) as f64 / seconds) as u64,
+                ),
+                _ => (0, 0),
+            };
+            let sockets = if with_connections && !connections.is_empty() {
+                count_sockets(&dir.join("fd"), &connections)
+            } else {
+                0
+            };
+
+            let process = previous.unwrap_or_else(|| {
+                let status = fs::read_to_string(dir.join("status")).unwrap_or_default();
+                let uid = parse_uid(&status).unwrap_or(0);
+                Known {
+                    start_time: stat.start_time,
+                    cpu_ticks: 0,
+                    read_bytes: 0,
+                    write_bytes: 0,
+                    name: process_name(&stat.comm, &fs::read(dir.join("cmdline")).unwrap_or_default()),
+                    user: self.users.get(&uid).cloned().unwrap_or_else(|| uid.to_string()),
+                    cgroup: parse_cgroup(&fs::read_to_string(dir.join("cgroup")).unwrap_or_default()),
+                }
+            });
+
+            processes.push(ProcessInfo {
+                pid,
+                name: process.name.clone(),
+                memory_percent: if memory_total > 0 {
+                    rss as f32 / memory_total as f32 * 100.0
+                } else {
+                    0.0
+                },
+                cpu_percent,
+                user: process.user.clone(),
+                threads: stat.threads,
+                rss,
+                rss_display: ByteSize::b(rss).to_string(),
+                state: stat.state,
+                count: 1,
+                cgroup: process.cgroup.clone(),
+                read_rate,
+                write_rate,
+                io_display: rate::bytes_per_second(read_rate + write_rate),
+                connections: sockets,
+            });
+            known.insert(
+                pid,
+                Known {
+                    cpu_ticks: stat.cpu_ticks,
+                    read_bytes,
+                    write_bytes,
+                    ..process
+                },
+            );
+        }
+        self.known = known;
+
+        let processes = group(processes, &self.config.group_by);
+        let result = top(processes, self.config.count);
+        debug!("collect_processes took: {} ms", start.elapsed().as_millis());
+        result
+    }
+
+    fn apply(&self, output: &TopProcesses, info: &mut SystemInfo) {
+        info.top_cpu_processes = output.cpu.clone();
+        info.top_memory_processes = output.memory.clone();
+        info.top_io_processes = output.io.clone();
+        info.top_connection_processes = output.connections.clone();
+    }
+}
+
+/// The `count` highest processes of each ranking. Processes without any I/O or connections are
+/// left out of those rankings.
+fn top(processes: Vec<ProcessInfo>, count: usize) -> TopProcesses {
+    let mut cpu = processes.clone();
+    cpu.sort_by(|a, b| b.cpu_percent.total_cmp(&a.cpu_percent));
+    cpu.truncate(count);
+
+    let mut memory = processes.clone();
+    memory.sort_by(|a, b| b.memory_percent.total_cmp(&a.memory_percent));
+    memory.truncate(count);
+
+    let mut io: Vec<ProcessInfo> = processes.iter().filter(|p| p.read_rate + p.write_rate > 0).cloned().collect();
+    io.sort_by_key(|p| std::cmp::Reverse(p.read_rate + p.write_rate));
+    io.truncate(count);
+
+    let mut connections: Vec<ProcessInfo> = processes.into_iter().filter(|p| p.connections > 0).collect();
+    connections.sort_by_key(|p| std::cmp::Reverse(p.connections));
+    connections.truncate(count);
+
+    TopProcesses {
+        cpu,
+        memory,
+        io,
+        connections,
+    }
+}
+
+/// Merges processes sharing an executable name or cgroup into one entry whose usage is the sum
+/// of its members. The entry keeps the lowest pid, usually the parent.
+pub fn group(processes: Vec<ProcessInfo>, grouping: &ProcessGrouping) -> Vec<ProcessInfo> {
+    let key = |process: &ProcessInfo| match grouping {
+        ProcessGrouping::None => None,
+        ProcessGrouping::Executable => Some(process.name.clone()),
+        ProcessGrouping::Cgroup => Some(process.cgroup.clone()),
+    };
+
+    let mut groups: Vec<ProcessInfo> = Vec::new();
+    let mut index: HashMap<String, usize> = HashMap::new();
+    for process in processes {
+        let Some(key) = key(&process) else {
+            groups.push(process);
+            continue;
+        };
+        match index.get(&key) {
+            Some(&i) => {
+                let group = &mut groups[i];
+                group.pid = group.pid.min(process.pid);
+                group.cpu_percent += process.cpu_percent;
+                group.memory_percent += process.memory_percent;
+                group.threads += process.threads;
+                group.rss += process.rss;
+                group.rss_display = ByteSize::b(group.rss).to_string();
+                group.count += process.count;
+                group.read_rate += process.read_rate;
+                group.write_rate += process.write_rate;
+                group.io_display = rate::bytes_per_second(group.read_rate + group.write_rate);
+                group.connections += process.connections;
+            }
+            None => {
+                index.insert(key.clone(), groups.len());
+                let name = match grouping {
+                    ProcessGrouping::Cgroup => cgroup_name(&key),
+                    _ => process.name.clone(),
+                };
+                groups.push(ProcessInfo { name, ..process });
+            }
+        }
+    }
+    groups
+}
+
+/// Last component of a cgroup path, e.g. `docker-1a2b.scope` for
+/// `/system.slice/docker-1a2b.scope`, or `/` for the root cgroup.
+pub fn cgroup_name(path: &str) -> String {
+    path.rsplit('/')
+        .find(|part| !part.is_empty())
+        .unwrap_or("/")
+        .to_string()
+}
+
+fn list_pids(proc_root: &Path) -> Vec<u32> {
+    match fs::read_dir(proc_root) {
+        Ok(entries) => entries
+            .flatten()
+            .filter_map(|entry| entry.file_name().to_str().and_then(|name| name.parse().ok()))
+            .collect(),
+        Err(e) => {
+            error!("Error listing processes: {}", e);
+            Vec::new()
+        }
+    }
+}
+
+/// Parses `/proc/[pid]/stat`. The command name is enclosed in parentheses and may itself contain
+/// spaces and parentheses, so fields are counted from the last `)`.
+pub fn parse_stat(text: &str) -> Option<ProcStat> {
+    let open = text.find('(')?;
+    let close = text.rfind(')')?;
+    let pid = text[..open].trim().parse().ok()?;
+    let comm = text.get(open + 1..close)?.to_string();
+    // Fields after the name, starting with field 3 (state)
+    let fields: Vec<&str> = text[close + 1..].split_whitespace().collect();
+    let field = |n: usize| fields.get(n - 3).and_then(|value| value.parse::<u64>().ok()).unwrap_or(0);
+
+    Some(ProcStat {
+        pid,
+        comm,
+        state: fields.first()?.chars().next()?,
+        ppid: field(4) as u32,
+        cpu_ticks: field(14) + field(15),
+        threads: field(20),
+        start_time: field(22),
+    })
+}
+
+/// Bytes read from and written to storage so far, from `/proc/[pid]/io`. Only readable for
+/// other users' processes when running as root.
+pub fn parse_io(text: &str) -> Option<(u64, u64)> {
+    let field = |name: &str| {
+        text.lines()
+            .find_map(|line| line.strip_prefix(name))
+            .and_then(|value| value.trim().parse::<u64>().ok())
+    };
+    Some((field("read_bytes:")?, field("write_bytes:")?))
+}
+
+/// Parses `/proc/net/tcp` or `/proc/net/tcp6`.
+pub fn parse_net_tcp(text: &str) -> Vec<TcpSocket> {
+    text.lines()
+        .skip(1)
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            Some(TcpSocket {
+                state: u8::from_str_radix(fields.get(3)?, 16).ok()?,
+                inode: fields.get(9)?.parse().ok()?,
+            })
+        })
+        .collect()
+}
+
+/// Number of descriptors in `fd_dir` (`/proc/[pid]/fd`) that are one of the `sockets`.
+fn count_sockets(fd_dir: &Path, sockets: &HashSet<u64>) -> usize {
+    let Ok(entries) = fs::read_dir(fd_dir) else {
+        return 0;
+    };
+    entries
+        .flatten()
+        .filter_map(|entry| entry.path().read_link().ok())
+        .filter_map(|target| {
+            let target = target.to_str()?;
+            target.strip_prefix("socket:[")?.strip_suffix(']')?.parse::<u64>().ok()
+        })
+        .filter(|inode| sockets.contains(inode))
+        .count()
+}
+
+/// Resident set size in pages, from `/proc/[pid]/statm`.
+pub fn parse_statm(text: &str) -> Option<u64> {
+    text.split_whitespace().nth(1)?.parse().ok()
+}
+
+/// Real user id, from `/proc/[pid]/status`.
+pub fn parse_uid(status: &str) -> Option<u32> {
+    status
+        .lines()
+        .find_map(|line| line.strip_prefix("Uid:"))
+        .and_then(|uids| uids.split_whitespace().next())
+        .and_then(|uid| uid.parse().ok())
+}
+
+/// The process' cgroup path from `/proc/[pid]/cgroup`: the unified (v2) hierarchy when present,
+/// otherwise the first v1 hierarchy listed.
+pub fn parse_cgroup(text: &str) -> String {
+    let paths: Vec<(&str, &str)> = text
+        .lines()
+        .filter_map(|line| {
+            let mut parts = line.splitn(3, ':');
+            let id = parts.next()?;
+            parts.next()?;
+            Some((id, parts.next()?))
+        })
+        .collect();
+    paths
+        .iter()
+        .find(|(id, _)| *id == "0")
+        .or(paths.first())
+        .map(|(_, path)| path.to_string())
+        .unwrap_or_default()
+}
+
+/// `MemTotal` from `/proc/meminfo`, in bytes.
+fn parse_mem_total(meminfo: &str) -> Option<u64> {
+    meminfo
+        .lines()
+        .find_map(|line| line.strip_prefix("MemTotal:"))
+        .and_then(|value| value.split_whitespace().next())
+        .and_then(|kb| kb.parse::<u64>().ok())
+        .map(|kb| kb * 1024)
+}
+
+/// User names by uid, from `/etc/passwd`.
+fn parse_passwd(text: &str) -> HashMap<u32, String> {
+    text.lines()
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split(':').collect();
+            Some((fields.get(2)?.parse().ok()?, fields.first()?.to_string()))
+        })
+        .collect()
+}
+
+/// The kernel truncates `comm` to 15 characters, so the executable's file name from the command
+/// line is preferred when it starts with `comm`. Kernel threads have no command line.
+pub fn process_name(comm: &str, cmdline: &[u8]) -> String {
+    let executable = cmdline.split(|b| *b == 0).next().unwrap_or_default();
+    let executable = String::from_utf8_lossy(executable);
+    let file_name = executable.rsplit('/').next().unwrap_or_default();
+    if !comm.is_empty() && file_name.starts_with(comm) {
+        file_name.to_string()
+    } else {
+        comm.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_stat() {
+        let stat = parse_stat(
+            "4242 (Web Content (x) 2) S 1187 1100 1100 0 -1 4194560 23212 0 69 0 1500 320 0 0 20 0 31 0 99121 \
+             24727552 2402 18446744073709551615 1 1 0 0 0 0 0 4096 1088 0 0 0 17 3 0 0 0 0 0\n",
+        )
+        .unwrap();
+        assert_eq!(
+            stat,
+            ProcStat {
+                pid: 4242,
+                comm: "Web Content (x) 2".to_string(),
+                state: 'S',
+                ppid: 1187,
+                cpu_ticks: 1820,
+                threads: 31,
+                start_time: 99121,
+            }
+        );
+
+        assert_eq!(parse_statm("6037 2437 1685 1632 0 3955 0\n"), Some(2437));
+        assert_eq!(parse_uid("Name:\tbash\nUid:\t1000\t1000\t1000\t1000\n"), Some(1000));
+        assert_eq!(parse_cgroup("1:cpu:/\n0::/system.slice/docker-1a2b.scope\n"), "/system.slice/docker-1a2b.scope");
+        assert_eq!(process_name("chrome_crashpad", b"/opt/google/chrome/chrome_crashpad_handler\0--monitor\0"), "chrome_crashpad_handler");
+        assert_eq!(process_name("kworker/u4:0", b""), "kworker/u4:0");
+    }
+
+    #[test]
+    fn test_parse_io_and_sockets() {
+        let io = "rchar: 3980\nwchar: 120\nsyscr: 9\nsyscw: 1\nread_bytes: 8192\nwrite_bytes: 4096\ncancelled_write_bytes: 0\n";
+        assert_eq!(parse_io(io), Some((8192, 4096)));
+        assert_eq!(parse_io("rchar: 1\n"), None);
+
+        let tcp = "  sl  local_address rem_address   st tx_queue rx_queue tr tm->when retrnsmt   uid  timeout inode\n\
+            \x20  0: 00000000:07E8 00000000:0000 0A 00000000:00000000 00:00000000 00000000     0        0 662 1 0000000058b7850e 100 0 0 10 0\n\
+            \x20  1: 0100007F:BC8F 0100007F:9FA2 01 00000000:00000000 00:00000000 00000000 65534        0 15721 2 00000000d97c020a 20 4 0 20 -1\n";
+        assert_eq!(
+            parse_net_tcp(tcp),
+            vec![
+                TcpSocket { state: TCP_LISTEN, inode: 662 },
+                TcpSocket { state: 0x01, inode: 15721 },
+            ]
+        );
+
+        // A fake fd directory: one connected socket, one listening socket and a file
+        let fd = tempfile::tempdir().unwrap();
+        std::os::unix::fs::symlink("socket:[15721]", fd.path().join("3")).unwrap();
+        std::os::unix::fs::symlink("socket:[662]", fd.path().join("4")).unwrap();
+        std::os::unix::fs::symlink("/dev/null", fd.path().join("5")).unwrap();
+        assert_eq!(count_sockets(fd.path(), &HashSet::from([15721])), 1);
+    }
+
+    #[test]
+    fn test_group_processes() {
+        let process = |pid: u32, name: &str, cgroup: &str, cpu_percent: f32| ProcessInfo {
+            pid,
+            name: name.to_string(),
+            cpu_percent,
+            threads: 2,
+            rss: 1024,
+            count: 1,
+            cgroup: cgroup.to_string(),
+            ..ProcessInfo::default()
+        };
+        let processes = vec![
+            process(300, "chrome", "/user.slice/app.scope", 10.0),
+            process(120, "chrome", "/user.slice/app.scope", 5.0),
+            process(50, "postgres", "/system.slice/postgresql.service", 2.0),
+        ];
+
+        let by_executable = group(processes.clone(), &ProcessGrouping::Executable);
+        assert_eq!(by_executable.len(), 2);
+        assert_eq!(by_executable[0].pid, 120);
+        assert_eq!(by_executable[0].count, 2);
+        assert_eq!(by_executable[0].threads, 4);
+        assert!((by_executable[0].cpu_percent - 15.0).abs() < 1e-6);
+
+        let by_cgroup = group(processes.clone(), &ProcessGrouping::Cgroup);
+        let names: Vec<&str> = by_cgroup.iter().map(|p| p.name.as_str()).collect();
+        assert_eq!(names, vec!["app.scope", "postgresql.service"]);
+
+        assert_eq!(group(processes, &ProcessGrouping::None).len(), 3);
+    }
+}
@@ -0,0 +1,267 @@
+use crate::collectors::collector::{self, Collector};
+use crate::collectors::{
+    container, cpu, custom, disk, gpu, memory, network, nvidia, power, pressure, probe, process, syslog,
+    system, systemd,
+};
+use crate::config::{AppConfig, CollectorSettings};
+use crate::models::{AllowedResources, SystemInfo};
+use futures::future::{join_all, BoxFuture};
+use log::debug;
+use std::time::{Duration, Instant};
+
+/// Type-erased view of a registered collector and its cached output.
+trait Scheduled: Send {
+    /// Runs the collector if its interval has elapsed.
+    fn run(&mut self) -> BoxFuture<'_, ()>;
+    fn apply(&self, info: &mut SystemInfo);
+    /// Collector name and how long its last run took, once it has run.
+    fn timing(&self) -> Option<(&'static str, Duration)>;
+}
+
+struct Slot<C: Collector> {
+    collector: C,
+    output: Option<C::Output>,
+    last_run: Option<Instant>,
+    duration: Option<Duration>,
+}
+
+impl<C: Collector> Scheduled for Slot<C> {
+    fn run(&mut self) -> BoxFuture<'_, ()> {
+        Box::pin(async move {
+            let due = self
+                .last_run
+                .is_none_or(|last| last.elapsed() >= self.collector.interval());
+            if !due {
+                return;
+            }
+
+            let start = Instant::now();
+            let output = self.collector.collect().await;
+            self.output = Some(output);
+            self.last_run = Some(start);
+            self.duration = Some(start.elapsed());
+            debug!("{} collector took: {} ms", self.collector.name(), start.elapsed().as_millis());
+        })
+    }
+
+    fn apply(&self, info: &mut SystemInfo) {
+        if let Some(output) = &self.output {
+            self.collector.apply(output, info);
+        }
+    }
+
+    fn timing(&self) -> Option<(&'static str, Duration)> {
+        Some((self.collector.name(), self.duration?))
+    }
+}
+
+/// Runs the enabled collectors concurrently and assembles their outputs into a [`SystemInfo`].
+pub(crate) struct Registry {
+    slots: Vec<Box<dyn Scheduled>>,
+    allowed_resources: AllowedResources,
+}
+
+impl Registry {
+    pub fn new(allowed_resources: AllowedResources) -> Self {
+        Self {
+            slots: Vec::new(),
+            allowed_resources,
+        }
+    }
+
+    /// Registry with every built-in collector, skipping those disabled in `[COLLECTORS]`.
+    pub fn from_config(config: &AppConfig, allowed_resources: AllowedResources) -> Self {
+        let collectors = &config.collectors;
+        let mut registry = Self::new(allowed_resources.clone());
+        // Collectors feeding a single optional widget only run while it is shown, or for `/metrics`
+        let shown = |widget: &str, widgets: &[String]| {
+            let shown = config.metrics.enabled || widgets.iter().any(|entry| entry == widget);
+            if !shown {
+                debug!("{} is not on the dashboard, skipping its collector", widget);
+            }
+            shown
+        };
+
+        registry.register(cpu::CpuCollector::new(collectors.cpu.clone()));
+        registry.register(memory::MemoryCollector::new(collectors.memory.clone()));
+        registry.register(pressure::PressureCollector::new(collectors.pressure.clone()));
+        registry.register(disk::DiskCollector::new(
+            collectors.disk.clone(),
+            allowed_resources.disks.clone(),
+            allowed_resources.mount_points.clone(),
+        ));
+        registry.register(network::NetworkCollector::new(
+            collectors.network.clone(),
+            allowed_resources.networks.clone(),
+        ));
+        registry.register(system::SystemCollector::new(collectors.system.clone()));
+        registry.register(system::SensorCollector::new(
+            collectors.sensors.clone(),
+            allowed_resources.sensors.clone(),
+        ));
+        registry.register(nvidia::NvidiaCollector::new(collectors.nvidia.clone()));
+        registry.register(gpu::GpuCollector::new(collectors.gpu.clone()));
+        registry.register(power::PowerCollector::new(collectors.power.clone()));
+        registry.register(process::ProcessCollector::new(
+            collectors.processes.clone(),
+            config.dashboard.processes.clone(),
+        ));
+        if shown("containers", &config.dashboard.processes) {
+            registry.register(container::ContainerCollector::new(collectors.containers.clone()));
+        }
+        if shown("units", &config.dashboard.bars) {
+            registry.register(systemd::SystemdCollector::new(collectors.systemd.clone()));
+        }
+        registry.register(probe::ProbeCollector::new(collectors.probes.clone(), config.probes.clone()));
+        registry.register(custom::CustomCollector::new(collectors.custom.clone(), config.custom.clone()));
+        registry.register(syslog::SyslogCollector::new(collectors.syslog.clone()));
+
+        registry
+    }
+
+    pub fn register<C: Collector>(&mut self, collector: C) {
+        if !collector.config().enabled() {
+            debug!("{} collector is disabled", collector.name());
+            return;
+        }
+        self.slots.push(Box::new(Slot {
+            collector,
+            output: None,
+            last_run: None,
+            duration: None,
+        }));
+    }
+
+    pub async fn collect(&mut self) -> SystemInfo {
+        let start = Instant::now();
+        join_all(self.slots.iter_mut().map(|slot| slot.run())).await;
+
+        let mut info = SystemInfo::default();
+        for slot in &self.slots {
+            slot.apply(&mut info);
+        }
+        collector::finalise(&mut info, &self.allowed_resources);
+
+        debug!("collect took: {} ms", start.elapsed().as_millis());
+        debug!("{:?}", info);
+        info
+    }
+
+    /// How long each collector's last run took.
+    pub fn timings(&self) -> Vec<(&'static str, Duration)> {
+        self.slots.iter().filter_map(|slot| slot.timing()).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use indexmap::IndexMap;
+    use std::sync::atomic::{AtomicU32, Ordering};
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    struct Settings {
+        enabled: bool,
+        interval: Duration,
+    }
+
+    impl CollectorSettings for Settings {
+        fn enabled(&self) -> bool {
+            self.enabled
+        }
+
+        fn interval(&self) -> Duration {
+            self.interval
+        }
+    }
+
+    /// Counts its runs.
+    struct Counting {
+        config: Settings,
+        runs: Arc<AtomicU32>,
+    }
+
+    impl Counting {
+        fn new(enabled: bool, interval: Duration) -> (Self, Arc<AtomicU32>) {
+            let runs = Arc::new(AtomicU32::new(0));
+            let config = Settings { enabled, interval };
+            (Self { config, runs: runs.clone() }, runs)
+        }
+    }
+
+    impl Collector for Counting {
+        type Config = Settings;
+        type Output = u32;
+
+        fn name(&self) -> &'static str {
+            "counting"
+        }
+
+        fn config(&self) -> &Settings {
+            &self.config
+        }
+
+        async fn collect(&mut self) -> u32 {
+            self.runs.fetch_add(1, Ordering::SeqCst) + 1
+        }
+
+        fn apply(&self, _output: &u32, _info: &mut SystemInfo) {}
+    }
+
+    fn allowed_resources() -> AllowedResources {
+        AllowedResources {
+            disks: Vec::new(),
+            networks: Vec::new(),
+            mount_points: Vec::new(),
+            sensors: IndexMap::new(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_interval_gating() {
+        let mut registry = Registry::new(allowed_resources());
+        let (every_tick, every_tick_runs) = Counting::new(true, Duration::ZERO);
+        let (slow, slow_runs) = Counting::new(true, Duration::from_millis(100));
+        registry.register(every_tick);
+        registry.register(slow);
+
+        for _ in 0..3 {
+            registry.collect().await;
+        }
+        assert_eq!(every_tick_runs.load(Ordering::SeqCst), 3);
+        assert_eq!(slow_runs.load(Ordering::SeqCst), 1);
+
+        tokio::time::sleep(Duration::from_millis(120)).await;
+        registry.collect().await;
+        assert_eq!(slow_runs.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_disabled_collector_is_not_registered() {
+        let mut registry = Registry::new(allowed_resources());
+        let (disabled, runs) = Counting::new(false, Duration::ZERO);
+        registry.register(disabled);
+        registry.collect().await;
+
+        assert!(registry.slots.is_empty());
+        assert_eq!(runs.load(Ordering::SeqCst), 0);
+    }
+
+    #[test]
+    fn test_from_config_skips_hidden_widgets() {
+        let mut config = AppConfig::default();
+        let hidden = Registry::from_config(&config, allowed_resources()).slots.len();
+
+        config.dashboard.processes.push("containers".to_string());
+        config.dashboard.bars.push("units".to_string());
+        let shown = Registry::from_config(&config, allowed_resources()).slots.len();
+        assert_eq!(shown, hidden + 2);
+
+        // `/metrics` exports them whatever the dashboard shows
+        let mut config = AppConfig::default();
+        config.metrics.enabled = true;
+        let exported = Registry::from_config(&config, allowed_resources()).slots.len();
+        assert_eq!(exported, hidden + 2);
+    }
+}
@@ -0,0 +1,174 @@
+use crate::collectors::system::sensor_matches;
+use crate::models::sensor::{Sensor, SensorKind};
+use crate::models::system::SystemComponent;
+use regex::Regex;
+
+/// How the inputs matched by one selector become one reading.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Aggregate {
+    #[default]
+    Max,
+    Min,
+    Avg,
+}
+
+/// Which hwmon inputs a `[RESOURCES.sensors]` key selects.
+///
+/// A key made of `field=value` pairs is structured, e.g. `chip=k10temp label=Tccd* agg=avg` or
+/// `chip=nct6798 input=fan2`:
+///
+/// - `chip`: hwmon `name`, exactly
+/// - `label`: input label, `*` matches anything
+/// - `input`: channel (`temp3`, `fan2`, `in1`, `power1`, `curr1`) or just its index
+/// - `kind`: `temp`, `fan`, `in`, `power` or `curr`; temperatures unless `input` says otherwise
+/// - `device`: part of the resolved device path, e.g. a PCI address `0000:01:00.0`
+/// - `regex`: regular expression over "name label model path"
+/// - `agg`: `max` (default), `min` or `avg` when several inputs match
+///
+/// Any other key is a plain hint, matched as before by [`sensor_matches`].
+#[derive(Debug, Clone, Default)]
+pub struct SensorSelector {
+    hint: Option<String>,
+    chip: Option<String>,
+    label: Option<Regex>,
+    kind: Option<SensorKind>,
+    input: Option<u32>,
+    device: Option<String>,
+    regex: Option<Regex>,
+    pub aggregate: Aggregate,
+}
+
+impl SensorSelector {
+    pub fn parse(key: &str) -> Result<Self, String> {
+        if !key.contains('=') {
+            return Ok(Self {
+                hint: Some(key.to_string()),
+                ..Self::default()
+            });
+        }
+
+        let mut selector = Self::default();
+        for token in key.split_whitespace() {
+            let (field, value) = token
+                .split_once('=')
+                .ok_or_else(|| format!("expected field=value, found '{}'", token))?;
+            match field.to_lowercase().as_str() {
+                "chip" => selector.chip = Some(value.to_lowercase()),
+                "label" => {
+                    let pattern = value.split('*').map(regex::escape).collect::<Vec<_>>().join(".*");
+                    selector.label = Some(compile(&format!("(?i)^{}$", pattern))?);
+                }
+                "input" => {
+                    let kind = SensorKind::ALL
+                        .into_iter()
+                        .filter(|kind| value.starts_with(kind.prefix()))
+                        .max_by_key(|kind| kind.prefix().len());
+                    let index = kind.map_or(value, |kind| &value[kind.prefix().len()..]);
+                    selector.input = Some(index.parse().map_err(|_| format!("unknown input '{}'", value))?);
+                    selector.kind = kind.or(selector.kind);
+                }
+                "kind" => {
+                    let kind = SensorKind::ALL.into_iter().find(|kind| kind.prefix() == value);
+                    selector.kind = Some(kind.ok_or_else(|| format!("unknown kind '{}'", value))?);
+                }
+                "device" => selector.device = Some(value.to_lowercase()),
+                "regex" => selector.regex = Some(compile(&format!("(?i){}", value))?),
+                "agg" => {
+                    selector.aggregate = match value.to_lowercase().as_str() {
+                        "max" => Aggregate::Max,
+                        "min" => Aggregate::Min,
+                        "avg" => Aggregate::Avg,
+                        other => return Err(format!("unknown aggregation '{}'", other)),
+                    }
+                }
+                other => return Err(format!("unknown field '{}'", other)),
+            }
+        }
+        Ok(selector)
+    }
+
+    pub fn matches(&self, sensor: &Sensor) -> bool {
+        if let Some(hint) = &self.hint {
+            return sensor_matches(sensor, hint);
+        }
+        let reference = format!("{} {} {} {}", sensor.name, sensor.label, sensor.model, sensor.path);
+        sensor.kind == self.kind.unwrap_or_default()
+            && self.chip.as_ref().is_none_or(|chip| sensor.name.to_lowercase() == *chip)
+            && self.label.as_ref().is_none_or(|label| label.is_match(&sensor.label))
+            && self.input.is_none_or(|input| sensor.id == input)
+            && self.device.as_ref().is_none_or(|device| sensor.device.to_lowercase().contains(device))
+            && self.regex.as_ref().is_none_or(|regex| regex.is_match(&reference))
+    }
+
+    /// One reading from every sensor this selector matches, or `None` when nothing matches.
+    pub fn select(&self, sensors: &[Sensor], label: &str) -> Option<SystemComponent> {
+        let matched: Vec<&Sensor> = sensors.iter().filter(|sensor| self.matches(sensor)).collect();
+        let first = matched.first()?;
+        let values = matched.iter().map(|sensor| sensor.value);
+        let value = match self.aggregate {
+            Aggregate::Max => values.fold(f32::MIN, f32::max),
+            Aggregate::Min => values.fold(f32::MAX, f32::min),
+            Aggregate::Avg => values.sum::<f32>() / matched.len() as f32,
+        };
+        Some(SystemComponent {
+            label: label.to_string(),
+            kind: first.kind,
+            value,
+        })
+    }
+}
+
+fn compile(pattern: &str) -> Result<Regex, String> {
+    Regex::new(pattern).map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sensor(name: &str, kind: SensorKind, id: u32, label: &str, device: &str, value: f32) -> Sensor {
+        Sensor {
+            id,
+            path: format!("/sys/class/hwmon/hwmon1/{}{}_input", kind.prefix(), id),
+            name: name.to_string(),
+            label: label.to_string(),
+            device: device.to_string(),
+            kind,
+            unit: kind.unit(),
+            value,
+            ..Sensor::default()
+        }
+    }
+
+    #[test]
+    fn test_selectors() {
+        let nvme0 = "/sys/devices/pci0000:00/0000:00:01.1/0000:01:00.0/nvme/nvme0";
+        let nvme1 = "/sys/devices/pci0000:00/0000:00:02.1/0000:04:00.0/nvme/nvme1";
+        let sensors = vec![
+            sensor("k10temp", SensorKind::Temperature, 1, "Tctl", "", 70.0),
+            sensor("k10temp", SensorKind::Temperature, 3, "Tccd1", "", 60.0),
+            sensor("k10temp", SensorKind::Temperature, 4, "Tccd2", "", 64.0),
+            sensor("nvme", SensorKind::Temperature, 1, "Composite", nvme0, 41.0),
+            sensor("nvme", SensorKind::Temperature, 1, "Composite", nvme1, 55.0),
+            sensor("nct6798", SensorKind::Fan, 2, "", "", 1450.0),
+            sensor("nct6798", SensorKind::Voltage, 1, "", "", 12.1),
+        ];
+        let value = |key: &str| SensorSelector::parse(key).unwrap().select(&sensors, "x").map(|c| c.value);
+
+        assert_eq!(value("chip=k10temp label=Tccd* agg=avg"), Some(62.0));
+        assert_eq!(value("chip=k10temp"), Some(70.0));
+        assert_eq!(value("chip=k10temp agg=min"), Some(60.0));
+        assert_eq!(value("chip=nvme device=0000:04:00.0"), Some(55.0));
+        assert_eq!(value("chip=nct6798 input=fan2"), Some(1450.0));
+        assert_eq!(value("chip=nct6798 kind=in input=1"), Some(12.1));
+        assert_eq!(value("regex=^nvme\\s+composite"), Some(55.0));
+        assert_eq!(value("chip=k10temp input=temp9"), None);
+        // Plain hints keep working
+        assert_eq!(value("k10temp tctl"), Some(70.0));
+
+        let pump = SensorSelector::parse("chip=nct6798 input=fan2").unwrap();
+        assert_eq!(pump.select(&sensors, "Pump").unwrap().display(), "1450 RPM Pump");
+        assert!(SensorSelector::parse("chip=k10temp agg=median").is_err());
+        assert!(SensorSelector::parse("colour=red").is_err());
+    }
+}
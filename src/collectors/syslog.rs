@@ -0,0 +1,334 @@
+use crate::collectors::collector::Collector;
+use crate::collectors::journal::{self, JournalEntry, JournalFilter};
+use crate::config::{LogOverflow, LogSource, SyslogConfig};
+use crate::models::system::{LogLine, LogSeverity};
+use crate::models::SystemInfo;
+use crate::utils::tail::Tail;
+use log::{debug, error, info, warn};
+use regex::Regex;
+use std::collections::VecDeque;
+use std::path::Path;
+use std::sync::LazyLock;
+use std::time::Instant;
+
+static ERROR_KEYWORDS: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?i)\b(emerg|emergency|alert|crit|critical|fatal|panic|err|error|fail|failed|failure)\b").unwrap()
+});
+static WARNING_KEYWORDS: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"(?i)\b(warn|warning)\b").unwrap());
+/// RFC 3339 (`2025-05-01T10:00:00.123+01:00`) or traditional (`May  1 10:00:00`) timestamp, then host.
+static SYSLOG_PREFIX: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^(\d{4}-\d{2}-\d{2}T\S+|[A-Z][a-z]{2} [ \d]\d \d{2}:\d{2}:\d{2}) \S+ ").unwrap()
+});
+
+/// Include/exclude regular expressions applied to every line.
+#[derive(Debug, Default)]
+struct LineFilter {
+    include: Vec<Regex>,
+    exclude: Vec<Regex>,
+}
+
+impl LineFilter {
+    fn new(include: &[String], exclude: &[String]) -> Self {
+        let compile = |patterns: &[String]| -> Vec<Regex> {
+            patterns
+                .iter()
+                .filter_map(|pattern| match Regex::new(pattern) {
+                    Ok(regex) => Some(regex),
+                    Err(e) => {
+                        warn!("Ignoring invalid log filter '{}': {}", pattern, e);
+                        None
+                    }
+                })
+                .collect()
+        };
+        Self {
+            include: compile(include),
+            exclude: compile(exclude),
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.include.is_empty() && self.exclude.is_empty()
+    }
+
+    fn matches(&self, line: &str) -> bool {
+        (self.include.is_empty() || self.include.iter().any(|regex| regex.is_match(line)))
+            && !self.exclude.iter().any(|regex| regex.is_match(line))
+    }
+}
+
+/// A followed log file.
+struct Source {
+    /// Shown before each line when several files are followed.
+    label: Option<String>,
+    tail: Tail,
+    /// Whether the last read failed, so that a missing file is reported once.
+    failing: bool,
+}
+
+/// Most recent log lines, from log files or the systemd journal.
+pub(crate) struct SyslogCollector {
+    config: SyslogConfig,
+    /// `config.source` with `auto` resolved.
+    source: LogSource,
+    filter: JournalFilter,
+    line_filter: LineFilter,
+    files: Vec<Source>,
+    /// Latest matching lines of all files, oldest first.
+    recent: VecDeque<LogLine>,
+}
+
+impl SyslogCollector {
+    pub fn new(config: SyslogConfig) -> Self {
+        let source = match config.source {
+            // journald-only distributions have no syslog file
+            LogSource::Auto if config.files.first().is_some_and(|file| Path::new(file).exists()) => LogSource::File,
+            LogSource::Auto => LogSource::Journal,
+            ref source => source.clone(),
+        };
+        info!("Log panel source: {:?}", source);
+
+        let priority = if config.priority.is_empty() {
+            None
+        } else {
+            let priority = journal::parse_priority(&config.priority);
+            if priority.is_none() {
+                warn!("Unknown log priority '{}', showing all", config.priority);
+            }
+            priority
+        };
+        let filter = JournalFilter {
+            priority,
+            units: config.units.clone(),
+            identifiers: config.identifiers.clone(),
+        };
+
+        let files = config
+            .files
+            .iter()
+            .map(|file| {
+                let tail = Tail::new(file);
+                Source {
+                    label: (config.files.len() > 1)
+                        .then(|| tail.path().file_name().map(|name| name.to_string_lossy().to_string()))
+                        .flatten(),
+                    tail,
+                    failing: false,
+                }
+            })
+            .collect();
+
+        Self {
+            line_filter: LineFilter::new(&config.include, &config.exclude),
+            config,
+            source,
+            filter,
+            files,
+            recent: VecDeque::new(),
+        }
+    }
+
+    /// Reads what was appended to each file since the previous tick.
+    fn follow_files(&mut self) -> Vec<LogLine> {
+        let start = Instant::now();
+        let mut any_read = false;
+        for file in &mut self.files {
+            match file.tail.read_lines(self.config.lines) {
+                Ok(lines) => {
+                    any_read = true;
+                    file.failing = false;
+                    for line in lines {
+                        let message = strip_syslog_prefix(&line);
+                        if !self.line_filter.matches(message) {
+                            continue;
+                        }
+                        let text = match &file.label {
+                            Some(label) => format!("{}: {}", label, message),
+                            None => message.to_string(),
+                        };
+                        self.recent.push_back(LogLine {
+                            severity: severity(message),
+                            text,
+                        });
+                    }
+                }
+                Err(e) => {
+                    if !file.failing {
+                        error!("Error reading {}: {}", file.tail.path().display(), e);
+                    }
+                    file.failing = true;
+                }
+            }
+        }
+        while self.recent.len() > self.config.lines {
+            self.recent.pop_front();
+        }
+        debug!("follow_files took: {} ms", start.elapsed().as_millis());
+
+        if !any_read && self.recent.is_empty() {
+            return vec![LogLine {
+                text: "Error reading syslog".to_string(),
+                severity: LogSeverity::Error,
+            }];
+        }
+        self.recent.iter().cloned().collect()
+    }
+
+    async fn journal_lines(&self) -> Vec<LogLine> {
+        // The line filter runs after journalctl, so fetch more entries for it to choose from
+        let wanted = if self.line_filter.is_empty() {
+            self.config.lines
+        } else {
+            self.config.lines * 10
+        };
+        match journal::collect(wanted, &self.filter).await {
+            Ok(entries) => {
+                let mut lines: Vec<LogLine> = entries
+                    .iter()
+                    .map(|entry| LogLine {
+                        text: entry.to_line(),
+                        severity: journal_severity(entry),
+                    })
+                    .filter(|line| self.line_filter.matches(&line.text))
+                    .collect();
+                lines.drain(..lines.len().saturating_sub(self.config.lines));
+                lines
+            }
+            Err(_) => vec![LogLine {
+                text: "Error reading journal".to_string(),
+                severity: LogSeverity::Error,
+            }],
+        }
+    }
+}
+
+impl Collector for SyslogCollector {
+    type Config = SyslogConfig;
+    type Output = Vec<LogLine>;
+
+    fn name(&self) -> &'static str {
+        "syslog"
+    }
+
+    fn config(&self) -> &SyslogConfig {
+        &self.config
+    }
+
+    async fn collect(&mut self) -> Vec<LogLine> {
+        let lines = match self.source {
+            LogSource::Journal => self.journal_lines().await,
+            _ => self.follow_files(),
+        };
+        fit(lines, self.config.lines, self.config.length, &self.config.overflow)
+    }
+
+    fn apply(&self, output: &Vec<LogLine>, info: &mut SystemInfo) {
+        info.syslog_lines = output.clone();
+    }
+}
+
+/// Drops the timestamp and host of a syslog line; other lines are kept whole.
+pub fn strip_syslog_prefix(line: &str) -> &str {
+    match SYSLOG_PREFIX.find(line) {
+        Some(prefix) => line[prefix.end()..].trim(),
+        None => line.trim(),
+    }
+}
+
+/// Highlighting by keywords such as `error` or `warning`.
+pub fn severity(message: &str) -> LogSeverity {
+    if ERROR_KEYWORDS.is_match(message) {
+        LogSeverity::Error
+    } else if WARNING_KEYWORDS.is_match(message) {
+        LogSeverity::Warning
+    } else {
+        LogSeverity::Normal
+    }
+}
+
+fn journal_severity(entry: &JournalEntry) -> LogSeverity {
+    match entry.priority {
+        0..=3 => LogSeverity::Error,
+        4 => LogSeverity::Warning,
+        _ => severity(&entry.message),
+    }
+}
+
+/// Cuts `message` to `length` characters, marking the cut with `...`.
+pub fn shorten(message: &str, length: usize) -> String {
+    if message.chars().count() > length {
+        format!("{}...", message.chars().take(length).collect::<String>())
+    } else {
+        message.to_string()
+    }
+}
+
+/// Splits `message` into lines of at most `length` characters, indenting the continuations.
+pub fn wrap(message: &str, length: usize) -> Vec<String> {
+    let chars: Vec<char> = message.chars().collect();
+    let first = length.max(1).min(chars.len());
+    let mut lines = vec![chars[..first].iter().collect::<String>()];
+    for chunk in chars[first..].chunks(length.saturating_sub(2).max(1)) {
+        lines.push(format!("  {}", chunk.iter().collect::<String>()));
+    }
+    lines
+}
+
+/// Applies the overflow setting and keeps the newest `count` display lines.
+fn fit(lines: Vec<LogLine>, count: usize, length: usize, overflow: &LogOverflow) -> Vec<LogLine> {
+    let mut fitted: Vec<LogLine> = match overflow {
+        LogOverflow::Ellipsize => lines
+            .into_iter()
+            .map(|line| LogLine {
+                text: shorten(&line.text, length),
+                ..line
+            })
+            .collect(),
+        LogOverflow::Wrap => lines
+            .into_iter()
+            .flat_map(|line| {
+                wrap(&line.text, length)
+                    .into_iter()
+                    .map(move |text| LogLine { text, severity: line.severity })
+            })
+            .collect(),
+    };
+    fitted.drain(..fitted.len().saturating_sub(count));
+    fitted
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_filter_highlight_and_fit() {
+        assert_eq!(
+            strip_syslog_prefix("2025-05-01T10:00:00.123456+01:00 host kernel: usb 1-2: new device"),
+            "kernel: usb 1-2: new device"
+        );
+        assert_eq!(strip_syslog_prefix("May  1 10:00:00 host sshd[42]: Accepted"), "sshd[42]: Accepted");
+        // Not a syslog line, e.g. an nginx access log
+        assert_eq!(strip_syslog_prefix("10.0.0.1 - - \"GET / HTTP/1.1\" 200"), "10.0.0.1 - - \"GET / HTTP/1.1\" 200");
+
+        assert_eq!(severity("nginx: [error] connect() failed"), LogSeverity::Error);
+        assert_eq!(severity("kernel: WARNING: CPU: 3 PID: 1"), LogSeverity::Warning);
+        assert_eq!(severity("systemd[1]: Started errand.service"), LogSeverity::Normal);
+
+        let filter = LineFilter::new(&["nginx|sshd".to_string()], &["health".to_string(), "(".to_string()]);
+        assert!(filter.matches("nginx: GET /index.html"));
+        assert!(!filter.matches("nginx: GET /health"));
+        assert!(!filter.matches("kernel: eth0 link up"));
+
+        let lines = vec![
+            LogLine { text: "first".to_string(), severity: LogSeverity::Normal },
+            LogLine { text: "wärning ünicode".to_string(), severity: LogSeverity::Warning },
+        ];
+        let ellipsized = fit(lines.clone(), 5, 6, &LogOverflow::Ellipsize);
+        assert_eq!(ellipsized[1].text, "wärnin...");
+        let wrapped = fit(lines, 3, 6, &LogOverflow::Wrap);
+        let texts: Vec<&str> = wrapped.iter().map(|line| line.text.as_str()).collect();
+        assert_eq!(texts, vec!["  g ün", "  icod", "  e"]);
+        assert!(wrapped.iter().all(|line| line.severity == LogSeverity::Warning));
+    }
+}
@@ -1,16 +1,116 @@
-use crate::models::sensor::Sensor;
-use crate::models::system::{ProcessInfo, SensorInfo, SystemComponent};
+use crate::collectors::collector::Collector;
+use crate::collectors::selector::SensorSelector;
+use crate::config::CollectorConfig;
+use crate::models::sensor::{Sensor, SensorKind};
+use crate::models::system::{SensorInfo, SystemComponent, SystemMetrics};
+use crate::models::SystemInfo;
 use crate::utils;
 use crate::utils::file;
 use indexmap::IndexMap;
-use log::{debug, error};
-use regex::Regex;
+use log::{debug, error, warn};
 use std::fs::read_dir;
 use std::path::Path;
 use std::time::Duration;
 use std::time::Instant;
 use systemstat::{Platform, System};
-use tokio::process::Command;
+
+/// Hostname, uptime and load average.
+pub(crate) struct SystemCollector {
+    config: CollectorConfig,
+    sys_stat: System,
+}
+
+impl SystemCollector {
+    pub fn new(config: CollectorConfig) -> Self {
+        Self {
+            config,
+            sys_stat: System::new(),
+        }
+    }
+}
+
+impl Collector for SystemCollector {
+    type Config = CollectorConfig;
+    type Output = SystemMetrics;
+
+    fn name(&self) -> &'static str {
+        "system"
+    }
+
+    fn config(&self) -> &CollectorConfig {
+        &self.config
+    }
+
+    async fn collect(&mut self) -> SystemMetrics {
+        let (hostname, load, uptime) = tokio::join!(
+            get_hostname(),
+            collect_load(&self.sys_stat),
+            collect_uptime(&self.sys_stat)
+        );
+
+        SystemMetrics {
+            hostname,
+            sensors: SensorInfo::default(),
+            uptime: uptime.0,
+            uptime_display: uptime.2.join(" "),
+            load_avg: (load[0], load[1], load[2]),
+            load_avg_display: format!("{:.1} {:.1} {:.1}", load[0], load[1], load[2]),
+        }
+    }
+
+    fn apply(&self, output: &SystemMetrics, info: &mut SystemInfo) {
+        // Sensors have their own collector
+        let sensors = std::mem::take(&mut info.system.sensors);
+        info.system = SystemMetrics {
+            sensors,
+            ..output.clone()
+        };
+    }
+}
+
+/// hwmon readings matching `[RESOURCES.sensors]`.
+pub(crate) struct SensorCollector {
+    config: CollectorConfig,
+    /// Parsed `[RESOURCES.sensors]` keys with their labels.
+    selectors: Vec<(SensorSelector, String)>,
+}
+
+impl SensorCollector {
+    pub fn new(config: CollectorConfig, allowed: IndexMap<String, String>) -> Self {
+        let selectors = allowed
+            .into_iter()
+            .filter_map(|(key, label)| match SensorSelector::parse(&key) {
+                Ok(selector) => Some((selector, label)),
+                Err(e) => {
+                    warn!("Ignoring sensor '{}': {}", key, e);
+                    None
+                }
+            })
+            .collect();
+        Self { config, selectors }
+    }
+}
+
+impl Collector for SensorCollector {
+    type Config = CollectorConfig;
+    type Output = SensorInfo;
+
+    fn name(&self) -> &'static str {
+        "sensors"
+    }
+
+    fn config(&self) -> &CollectorConfig {
+        &self.config
+    }
+
+    async fn collect(&mut self) -> SensorInfo {
+        collect_sensors(&self.selectors).await
+    }
+
+    fn apply(&self, output: &SensorInfo, info: &mut SystemInfo) {
+        info.system.sensors = output.clone();
+    }
+}
 
 pub async fn collect_load(sys: &System) -> Vec<f32> {
     let start = Instant::now();
@@ -62,7 +162,7 @@ pub async fn collect_uptime(sys: &System) -> (u64, Vec<u64>, Vec<String>) {
     result
 }
 
-pub async fn collect_sensors(allowed: &IndexMap<String, String>) -> SensorInfo {
+pub async fn collect_sensors(selectors: &[(SensorSelector, String)]) -> SensorInfo {
     let start = Instant::now();
     let mut sensors: Vec<Sensor> = Vec::new();
     if let Ok(dir) = read_dir(Path::new("/sys/class/hwmon/")) {
@@ -82,27 +182,13 @@ pub async fn collect_sensors(allowed: &IndexMap<String, String>) -> SensorInfo {
             }
         }
     }
+    // read_dir order is arbitrary
+    sensors.sort_by(|a, b| a.path.cmp(&b.path));
 
     let mut readings = IndexMap::<String, SystemComponent>::new();
-
-    for sensor in &sensors {
-        for (allowed_label_hint, rename_to) in allowed.iter() {
-            let reference = format!(
-                "{} {} {} {}",
-                sensor.name, sensor.label, sensor.model, sensor.path
-            )
-            .replace("  ", " ")
-            .trim()
-            .to_lowercase()
-            .to_string();
-            debug!("sensor: {}", reference);
-            if reference.contains(allowed_label_hint) {
-                let component_info = SystemComponent {
-                    label: rename_to.clone(),
-                    temperature: sensor.temperature,
-                };
-                readings.insert(rename_to.clone(), component_info);
-            }
+    for (selector, rename_to) in selectors {
+        if let Some(component_info) = selector.select(&sensors, rename_to) {
+            readings.insert(rename_to.clone(), component_info);
         }
     }
 
@@ -113,239 +199,26 @@ pub async fn collect_sensors(allowed: &IndexMap<String, String>) -> SensorInfo {
     debug!("collect_sensors took: {} ms", start.elapsed().as_millis());
     result
 }
-/*
-pub async fn collect_processes0(count: usize) -> (Vec<ProcessInfo>, Vec<ProcessInfo>) {
-    let mut sys = SysInfo::new_all();
-    sys.processes();
-    sys.refresh_processes_specifics(
-        ProcessesToUpdate::All,
-        true,
-        ProcessRefreshKind::nothing()
-            .with_memory()
-            .with_cpu()
-            .with_exe(UpdateKind::OnlyIfNotSet),
-    );
-    tokio::time::sleep(sysinfo::MINIMUM_CPU_UPDATE_INTERVAL).await;
-
-    let mut cpu: Vec<ProcessInfo> = Vec::new();
-
-    let total_memory = sys.total_memory();
-    let mut mems: HashMap<String,ProcessInfo> = HashMap::new();
-    for (pid, process) in sys.processes() {
-        let exec = process.exe();
-        if exec.is_none() {
-            continue;
-        }
-        let exec_str = exec.map_or_else(String::new, |p| p.display().to_string());
-        let cpu_percent = process.cpu_usage();
-        let mem_percent = process.memory() as f32 / total_memory as f32 * 100.0;
-        let name = exec_str.split('/').last().unwrap_or(process.name().to_str().unwrap_or("unknown")).to_string();
-        let p = ProcessInfo {
-            pid: pid.as_u32(),
-            name: name.clone(),
-            memory_percent: mem_percent,
-            cpu_percent,
-        };
-
-        // Shorten the list that needs sorting
-        if cpu_percent >= 0.0 {
-            cpu.push(p.clone());
-        }
-
-        // Shorten the list that needs sorting
-        if mem_percent > 0.1 {
-            mems.insert(format!("{}{}",name,mem_percent), p);
-        }
-    }
-
-    let mut memory:Vec<ProcessInfo> = mems.values().cloned().collect();
-    // Sort by memory (descending)
-    memory.sort_by(|a, b| b.memory_percent.total_cmp(&a.memory_percent));
-    memory.truncate(count);
-
-    // Sort by CPU (descending)
-    cpu.sort_by(|a, b| b.cpu_percent.total_cmp(&a.cpu_percent));
-    cpu.truncate(count);
-
-    (cpu, memory)
-}
-pub async fn collect_processes2(count: usize) -> (Vec<ProcessInfo>, Vec<ProcessInfo>) {
-    let mut sys = SysInfo::new_all();
-
-    tokio::time::sleep(sysinfo::MINIMUM_CPU_UPDATE_INTERVAL).await;
-    sys.refresh_processes_specifics(
-        ProcessesToUpdate::All,
-        true,
-        ProcessRefreshKind::nothing()
-            .with_memory()
-            .with_cpu()
-            .with_exe(UpdateKind::OnlyIfNotSet),
-    );
-
-    let total_memory = sys.total_memory();
-
-    let mut memory: Vec<ProcessInfo> = Vec::new();
-    let mut cpu: Vec<ProcessInfo> = Vec::new();
-
-    let mut pids: HashSet<u32> = HashSet::new();
-    for (pid, process) in sys.processes() {
-        let is_memory = process.memory() > 0;
-        let is_cpu = process.cpu_usage() > 0.0;
-        let exec = process.exe();
-        let exec_str = exec.map_or_else(String::new, |p| p.display().to_string());
-
-        if !exec_str.is_empty() && (is_memory || is_cpu) {
-            pids.insert(process.parent().unwrap_or(pid.clone()).as_u32());
-        }
+/// Whether `hint` from `[RESOURCES.sensors]` selects `sensor`.
+///
+/// Temperatures match when the hint is part of "name label model path" (e.g. `k10temp tctl`).
+/// Chips often have many fan, voltage and power inputs, so those must be named: by chip and
+/// channel (`nct6798 fan2`) or by a hint that includes their label (`amdgpu ppt`).
+pub fn sensor_matches(sensor: &Sensor, hint: &str) -> bool {
+    let hint = hint.trim().to_lowercase();
+    let reference = format!("{} {} {} {}", sensor.name, sensor.label, sensor.model, sensor.path)
+        .replace("  ", " ")
+        .trim()
+        .to_lowercase();
+    debug!("sensor: {}", reference);
+    if hint == format!("{} {}", sensor.name, sensor.channel()).to_lowercase() {
+        return true;
     }
-
-    pids.iter().for_each(|pid| {
-        if let Some(process) = sys.process(Pid::from(pid.clone() as usize)) {
-            let exec = process.exe();
-            let exec_str = exec.map_or_else(String::new, |p| p.display().to_string());
-            let p = ProcessInfo {
-                pid: pid.clone(),
-                name: exec_str.split('/').last().unwrap_or("unknown").to_string(),
-                memory_percent: process.memory() as f32 / total_memory as f32 * 100.0,
-                cpu_percent: process.cpu_usage(),
-            };
-
-            if process.memory() > 0 {
-                memory.push(p.clone());
-            }
-
-            if process.cpu_usage() > 0.0 {
-                cpu.push(p);
-            }
-        }
-    });
-
-    // Sort by memory (descending)
-    memory.sort_by(|a, b| b.memory_percent.total_cmp(&a.memory_percent));
-    memory.truncate(count);
-
-    // Sort by CPU (descending)
-    cpu.sort_by(|a, b| b.cpu_percent.total_cmp(&a.cpu_percent));
-    cpu.truncate(count);
-
-    (cpu, memory)
-}
-
-*/
-pub async fn collect_processes(count: usize) -> (Vec<ProcessInfo>, Vec<ProcessInfo>) {
-    let memory = collect_processes_cmd("memory", count).await;
-    let cpu = collect_processes_cmd("cpu", count).await;
-    (cpu, memory)
-}
-pub async fn collect_processes_cmd(sort_by: &str, count: usize) -> Vec<ProcessInfo> {
-    let start = Instant::now();
-    let sort_key = match sort_by {
-        "memory" => "pmem",
-        "cpu" => "pcpu",
-        _ => "pmem", // Default to memory
-    };
-
-    let cmd_start = Instant::now();
-    let ps_command = Command::new("ps")
-        .args(&[
-            "-eo",
-            "pid,comm,%mem,%cpu",
-            &format!("--sort=-{}", sort_key),
-        ])
-        .output()
-        .await;
-    debug!(
-        "ps command execution took: {} ms",
-        cmd_start.elapsed().as_millis()
-    );
-
-    let result = match ps_command {
-        Ok(output) => {
-            if output.status.success() {
-                let parse_start = Instant::now();
-                let output_str = String::from_utf8_lossy(&output.stdout);
-                let lines: Vec<&str> = output_str.lines().collect();
-
-                let result = if lines.len() > 1 {
-                    let data_lines = &lines[1..std::cmp::min(count + 1, lines.len())];
-                    let re = Regex::new(r"^\s*(\d+)\s+(.+?)\s+(\d+\.?\d*)\s+(\d+\.?\d*)$").unwrap();
-
-                    data_lines
-                        .iter()
-                        .filter_map(|line| {
-                            re.captures(line).map(|captures| {
-                                let pid = captures[1].parse::<u32>().unwrap_or(0);
-                                let name = captures[2].trim().to_string();
-                                let memory_percent = captures[3].parse::<f32>().unwrap_or(0.0);
-                                let cpu_percent = captures[4].parse::<f32>().unwrap_or(0.0);
-
-                                ProcessInfo {
-                                    pid,
-                                    name,
-                                    memory_percent,
-                                    cpu_percent,
-                                }
-                            })
-                        })
-                        .collect()
-                } else {
-                    Vec::new()
-                };
-                debug!(
-                    "Process data parsing took: {} ms",
-                    parse_start.elapsed().as_millis()
-                );
-                result
-            } else {
-                Vec::new()
-            }
-        }
-        Err(e) => {
-            error!("Error getting top processes: {}", e);
-            Vec::new()
-        }
-    };
-    debug!(
-        "collect_processes (total) took: {} ms",
-        start.elapsed().as_millis()
-    );
-    result
+    let label = sensor.label.to_lowercase();
+    reference.contains(&hint)
+        && (sensor.kind == SensorKind::Temperature || (!label.is_empty() && hint.contains(&label)))
 }
 
-pub async fn collect_recent_syslog_lines(num_lines: usize, character_length: usize) -> Vec<String> {
-    match file::simple_tail("/var/log/syslog", num_lines) {
-        Ok(lines) => {
-            let parse_start = Instant::now();
-            let result = lines
-                .into_iter()
-                .rev()
-                .map(|line| {
-                    let parts: Vec<&str> = line.splitn(3, ' ').collect();
-                    let message = if parts.len() >= 3 {
-                        parts[2].trim()
-                    } else {
-                        line.as_str()
-                    };
-
-                    if message.len() > character_length {
-                        format!("{}...", &message[..character_length])
-                    } else {
-                        message.to_string()
-                    }
-                })
-                .collect();
-            debug!(
-                "Syslog parsing took: {} ms",
-                parse_start.elapsed().as_millis()
-            );
-            result
-        }
-        Err(e) => {
-            error!("Error reading syslog: {}", e);
-            vec!["Error reading syslog".to_string()]
-        }
-    }
-}
 /*
 pub async fn collect_recent_syslog_lines_tail(num_lines: usize, character_length: usize) -> Vec<String> {
     let start = Instant::now();
@@ -444,3 +317,34 @@ pub async fn get_hostname_cmd() -> String {
 }
 
  */
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sensor_matches() {
+        let sensor = |kind: SensorKind, id: u32, label: &str| Sensor {
+            id,
+            path: format!("/sys/class/hwmon/hwmon3/{}{}_input", kind.prefix(), id),
+            name: "nct6798".to_string(),
+            label: label.to_string(),
+            kind,
+            unit: kind.unit(),
+            ..Sensor::default()
+        };
+        let temp = sensor(SensorKind::Temperature, 1, "SYSTIN");
+        let fan = sensor(SensorKind::Fan, 2, "");
+        let power = sensor(SensorKind::Power, 1, "PPT");
+
+        // A chip-wide hint only selects temperatures
+        assert!(sensor_matches(&temp, "nct6798"));
+        assert!(!sensor_matches(&fan, "nct6798"));
+        assert!(!sensor_matches(&power, "nct6798"));
+
+        assert!(sensor_matches(&fan, "NCT6798 fan2"));
+        assert!(!sensor_matches(&fan, "nct6798 fan1"));
+        assert!(sensor_matches(&power, "nct6798 ppt"));
+        assert!(sensor_matches(&temp, "nct6798 temp1"));
+    }
+}
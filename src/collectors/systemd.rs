@@ -0,0 +1,267 @@
+use crate::collectors::collector::Collector;
+use crate::config::SystemdConfig;
+use crate::models::unit::UnitInfo;
+use crate::models::SystemInfo;
+use log::{debug, warn};
+use serde::Deserialize;
+use serde_json::Value;
+use std::time::Instant;
+use tokio::process::Command;
+
+const SHOW_PROPERTIES: &str =
+    "Id,Description,LoadState,ActiveState,SubState,NRestarts,StateChangeTimestampMonotonic";
+
+/// A unit as listed by systemd, before its details are asked for.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ListedUnit {
+    pub name: String,
+    pub active_state: String,
+}
+
+/// One entry of `systemctl list-units --output=json`.
+#[derive(Debug, Deserialize)]
+struct JsonUnit {
+    unit: String,
+    active: String,
+}
+
+pub(crate) struct SystemdCollector {
+    config: SystemdConfig,
+    /// Whether systemd being unreachable was already reported.
+    warned: bool,
+}
+
+impl SystemdCollector {
+    pub fn new(config: SystemdConfig) -> Self {
+        Self { config, warned: false }
+    }
+}
+
+impl Collector for SystemdCollector {
+    type Config = SystemdConfig;
+    type Output = Option<Vec<UnitInfo>>;
+
+    fn name(&self) -> &'static str {
+        "systemd"
+    }
+
+    fn config(&self) -> &SystemdConfig {
+        &self.config
+    }
+
+    async fn collect(&mut self) -> Option<Vec<UnitInfo>> {
+        let start = Instant::now();
+        let listed = match list_units().await {
+            Ok(listed) => listed,
+            Err(e) => {
+                if !self.warned {
+                    warn!("Error listing systemd units: {}", e);
+                    self.warned = true;
+                }
+                return None;
+            }
+        };
+        let names = select_units(&listed, &self.config.units, self.config.failed);
+        if names.is_empty() {
+            return Some(Vec::new());
+        }
+
+        let units = match run("systemctl", &show_args(&names)).await {
+            Ok(output) => Some(parse_show(&output, monotonic_now())),
+            Err(e) => {
+                warn!("Error reading systemd units: {}", e);
+                None
+            }
+        };
+        debug!("collect_units took: {} ms", start.elapsed().as_millis());
+        units
+    }
+
+    fn apply(&self, output: &Option<Vec<UnitInfo>>, info: &mut SystemInfo) {
+        info.units = output.clone();
+    }
+}
+
+/// Loaded units from systemd's `ListUnits` D-Bus method, or from `systemctl` when `busctl` is
+/// missing or fails.
+async fn list_units() -> Result<Vec<ListedUnit>, String> {
+    let dbus = run(
+        "busctl",
+        &[
+            "--system",
+            "--json=short",
+            "call",
+            "org.freedesktop.systemd1",
+            "/org/freedesktop/systemd1",
+            "org.freedesktop.systemd1.Manager",
+            "ListUnits",
+        ],
+    )
+    .await
+    .and_then(|output| parse_busctl(&output).map_err(|e| e.to_string()));
+    match dbus {
+        Ok(units) => Ok(units),
+        Err(e) => {
+            debug!("D-Bus ListUnits failed ({}), falling back to systemctl", e);
+            let output = run("systemctl", &["list-units", "--output=json", "--no-pager", "--all"]).await?;
+            parse_list_units(&output).map_err(|e| e.to_string())
+        }
+    }
+}
+
+fn show_args(names: &[String]) -> Vec<&str> {
+    let mut args = vec!["show", "--no-pager", "--property", SHOW_PROPERTIES];
+    args.extend(names.iter().map(String::as_str));
+    args
+}
+
+async fn run(program: &str, args: &[&str]) -> Result<String, String> {
+    let cmd_start = Instant::now();
+    let output = Command::new(program).args(args).output().await;
+    debug!("{} command execution took: {} ms", program, cmd_start.elapsed().as_millis());
+    match output {
+        Ok(output) if output.status.success() => Ok(String::from_utf8_lossy(&output.stdout).to_string()),
+        Ok(output) => Err(format!("{} failed: {}", program, String::from_utf8_lossy(&output.stderr).trim())),
+        Err(e) => Err(format!("{}: {}", program, e)),
+    }
+}
+
+/// Seconds on `CLOCK_MONOTONIC`, the clock of systemd's `*TimestampMonotonic` properties. Unlike
+/// `/proc/uptime` it stops while the machine is suspended.
+fn monotonic_now() -> f64 {
+    let mut now = libc::timespec { tv_sec: 0, tv_nsec: 0 };
+    // SAFETY: `now` is a valid timespec to write to
+    if unsafe { libc::clock_gettime(libc::CLOCK_MONOTONIC, &mut now) } != 0 {
+        return 0.0;
+    }
+    now.tv_sec as f64 + now.tv_nsec as f64 / 1_000_000_000.0
+}
+
+/// Parses `busctl --json=short call ... ListUnits`: `{"type":"a(ssssssouso)","data":[[[name,
+/// description, load state, active state, sub state, ...], ...]]}`.
+pub fn parse_busctl(json: &str) -> Result<Vec<ListedUnit>, serde_json::Error> {
+    let value: Value = serde_json::from_str(json)?;
+    let units = value["data"][0].as_array().cloned().unwrap_or_default();
+    Ok(units
+        .iter()
+        .filter_map(|unit| {
+            Some(ListedUnit {
+                name: unit.get(0)?.as_str()?.to_string(),
+                active_state: unit.get(3)?.as_str()?.to_string(),
+            })
+        })
+        .collect())
+}
+
+pub fn parse_list_units(json: &str) -> Result<Vec<ListedUnit>, serde_json::Error> {
+    let units: Vec<JsonUnit> = serde_json::from_str(json)?;
+    Ok(units
+        .into_iter()
+        .map(|unit| ListedUnit {
+            name: unit.unit,
+            active_state: unit.active,
+        })
+        .collect())
+}
+
+/// The watched units in configured order, then any other failed unit.
+pub fn select_units(listed: &[ListedUnit], watched: &[String], failed: bool) -> Vec<String> {
+    let mut names = watched.to_vec();
+    if failed {
+        for unit in listed.iter().filter(|unit| unit.active_state == "failed") {
+            if !names.contains(&unit.name) {
+                names.push(unit.name.clone());
+            }
+        }
+    }
+    names
+}
+
+/// Parses `systemctl show --property ...` for several units: one `key=value` block per unit,
+/// separated by empty lines. `now` (seconds on the monotonic clock) turns the monotonic state
+/// change timestamp into the time spent in the current state.
+pub fn parse_show(text: &str, now: f64) -> Vec<UnitInfo> {
+    text.split("\n\n")
+        .filter(|block| !block.trim().is_empty())
+        .map(|block| {
+            let mut unit = UnitInfo::default();
+            for (key, value) in block.lines().filter_map(|line| line.split_once('=')) {
+                match key {
+                    "Id" => unit.name = value.to_string(),
+                    "Description" => unit.description = value.to_string(),
+                    "LoadState" => unit.load_state = value.to_string(),
+                    "ActiveState" => unit.active_state = value.to_string(),
+                    "SubState" => unit.sub_state = value.to_string(),
+                    "NRestarts" => unit.restarts = value.parse().unwrap_or(0),
+                    "StateChangeTimestampMonotonic" => {
+                        let changed = value.parse::<u64>().unwrap_or(0) as f64 / 1_000_000.0;
+                        // 0 means never changed since boot
+                        if changed > 0.0 && changed <= now {
+                            unit.since = Some((now - changed) as u64);
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            unit.since_display = unit.since.map(duration_display).unwrap_or_default();
+            unit
+        })
+        .collect()
+}
+
+/// The two largest parts of a duration, e.g. `2d 4h`, `3h 12m` or `45s`.
+fn duration_display(seconds: u64) -> String {
+    let parts = [
+        (seconds / 86_400, "d"),
+        (seconds % 86_400 / 3_600, "h"),
+        (seconds % 3_600 / 60, "m"),
+        (seconds % 60, "s"),
+    ];
+    let first = parts.iter().position(|(value, _)| *value > 0).unwrap_or(3);
+    parts[first..]
+        .iter()
+        .take(2)
+        .map(|(value, unit)| format!("{}{}", value, unit))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_units() {
+        let busctl = include_str!("../../tests/fixtures/systemd/busctl-list-units.json");
+        let systemctl = include_str!("../../tests/fixtures/systemd/list-units.json");
+        let (busctl, systemctl) = (parse_busctl(busctl).unwrap(), parse_list_units(systemctl).unwrap());
+        assert_eq!(busctl, systemctl);
+        assert_eq!(busctl.len(), 4);
+    }
+
+    #[test]
+    fn test_select_units() {
+        let units = parse_list_units(include_str!("../../tests/fixtures/systemd/list-units.json")).unwrap();
+        let watched = vec!["postgresql.service".to_string(), "nginx.service".to_string()];
+        assert_eq!(
+            select_units(&units, &watched, true),
+            vec!["postgresql.service", "nginx.service", "mnt-data.mount"]
+        );
+        assert_eq!(select_units(&units, &watched, false), watched);
+    }
+
+    #[test]
+    fn test_parse_show() {
+        let units = parse_show(include_str!("../../tests/fixtures/systemd/show.txt"), 7_200.5);
+        assert_eq!(units.len(), 5);
+        let nginx = &units[0];
+        assert!(nginx.failed() && !nginx.restarting());
+        assert_eq!((nginx.since, nginx.since_display.as_str()), (Some(3_600), "1h 0m"));
+        assert_eq!(units[1].since_display, "1h 59m");
+        let backup = &units[2];
+        assert!(backup.restarting());
+        assert_eq!((backup.restarts, backup.since_display.as_str()), (14, "5s"));
+        assert_eq!(units[3].since, None);
+        assert_eq!((units[4].load_state.as_str(), units[4].active_state.as_str()), ("not-found", "inactive"));
+    }
+}
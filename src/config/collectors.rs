@@ -0,0 +1,478 @@
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+fn default_enabled() -> bool {
+    true
+}
+
+fn default_process_count() -> usize {
+    5
+}
+
+fn default_syslog_lines() -> usize {
+    5
+}
+
+fn default_syslog_length() -> usize {
+    75
+}
+
+fn default_container_sockets() -> Vec<String> {
+    vec!["/var/run/docker.sock".to_string(), "/run/podman/podman.sock".to_string()]
+}
+
+fn default_probe_history() -> usize {
+    30
+}
+
+fn default_probe_interval() -> u64 {
+    30
+}
+
+fn default_probe_timeout() -> u64 {
+    5
+}
+
+fn default_custom_interval() -> u64 {
+    15
+}
+
+fn default_custom_scale() -> f64 {
+    1.0
+}
+
+fn default_syslog_files() -> Vec<String> {
+    vec!["/var/log/syslog".to_string()]
+}
+
+/// Settings every collector understands.
+pub trait CollectorSettings {
+    fn enabled(&self) -> bool;
+    /// Minimum time between two collections. Zero collects on every dashboard tick.
+    fn interval(&self) -> Duration;
+}
+
+/// `[COLLECTORS.<name>]` for collectors without options of their own.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct CollectorConfig {
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    /// Seconds between collections, `0` for every tick.
+    #[serde(default)]
+    pub interval: u64,
+}
+
+impl Default for CollectorConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            interval: 0,
+        }
+    }
+}
+
+/// How the process rankings merge related processes.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum ProcessGrouping {
+    /// One entry per process.
+    #[default]
+    None,
+    /// One entry per executable name, e.g. all `chrome` processes together.
+    Executable,
+    /// One entry per cgroup, e.g. per systemd service or container.
+    Cgroup,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ProcessesConfig {
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    #[serde(default)]
+    pub interval: u64,
+    /// Number of processes listed per ranking.
+    #[serde(default = "default_process_count")]
+    pub count: usize,
+    #[serde(default)]
+    pub group_by: ProcessGrouping,
+}
+
+impl Default for ProcessesConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            interval: 0,
+            count: default_process_count(),
+            group_by: ProcessGrouping::default(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct PressureConfig {
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    #[serde(default)]
+    pub interval: u64,
+    /// cgroups whose pressure is read as well, relative to the cgroup v2 root,
+    /// e.g. `system.slice/docker.service`.
+    #[serde(default, deserialize_with = "super::deserialize_string_or_vec")]
+    pub cgroups: Vec<String>,
+}
+
+impl Default for PressureConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            interval: 0,
+            cgroups: Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ContainersConfig {
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    #[serde(default)]
+    pub interval: u64,
+    /// Number of containers and slices listed.
+    #[serde(default = "default_process_count")]
+    pub count: usize,
+    /// List systemd slices (`system.slice`, `user.slice`, ...) next to containers.
+    #[serde(default = "default_enabled")]
+    pub slices: bool,
+    /// Look up container names through the Docker or Podman API instead of showing short IDs.
+    #[serde(default = "default_enabled")]
+    pub resolve_names: bool,
+    /// Docker-compatible API sockets asked for container names, in order.
+    #[serde(default = "default_container_sockets", deserialize_with = "super::deserialize_string_or_vec")]
+    pub sockets: Vec<String>,
+}
+
+impl Default for ContainersConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            interval: 0,
+            count: default_process_count(),
+            slices: true,
+            resolve_names: true,
+            sockets: default_container_sockets(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct SystemdConfig {
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    #[serde(default)]
+    pub interval: u64,
+    /// Units always shown with their state, e.g. `nginx.service`.
+    #[serde(default, deserialize_with = "super::deserialize_string_or_vec")]
+    pub units: Vec<String>,
+    /// Show every failed unit as well.
+    #[serde(default = "default_enabled")]
+    pub failed: bool,
+}
+
+impl Default for SystemdConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            interval: 0,
+            units: Vec::new(),
+            failed: true,
+        }
+    }
+}
+
+/// `[COLLECTORS.probes]`: settings shared by every `[PROBES.<name>]`.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ProbesConfig {
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    /// Seconds between checking which probes are due; each probe has its own interval.
+    #[serde(default)]
+    pub interval: u64,
+    /// Latency samples kept per probe.
+    #[serde(default = "default_probe_history")]
+    pub history: usize,
+}
+
+impl Default for ProbesConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            interval: 0,
+            history: default_probe_history(),
+        }
+    }
+}
+
+/// How a probe checks its target.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum ProbeKind {
+    /// Connects to `host:port`.
+    #[default]
+    Tcp,
+    /// GETs an `http://` or `https://` URL.
+    Http,
+    /// Resolves a host name.
+    Dns,
+    /// Pings a host with the `ping` command, where unprivileged ICMP is permitted.
+    Icmp,
+}
+
+/// `[PROBES.<name>]`: one reachability check.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ProbeConfig {
+    #[serde(rename = "type", default)]
+    pub kind: ProbeKind,
+    /// `host:port`, URL or host name, depending on `type`.
+    pub target: String,
+    /// Seconds between checks.
+    #[serde(default = "default_probe_interval")]
+    pub interval: u64,
+    /// Seconds before the target counts as down.
+    #[serde(default = "default_probe_timeout")]
+    pub timeout: u64,
+    /// HTTP only: the status code expected, or `0` for any status below 400.
+    #[serde(default)]
+    pub expect: u16,
+}
+
+impl Default for ProbeConfig {
+    fn default() -> Self {
+        Self {
+            kind: ProbeKind::default(),
+            target: String::new(),
+            interval: default_probe_interval(),
+            timeout: default_probe_timeout(),
+            expect: 0,
+        }
+    }
+}
+
+/// Where a custom metric comes from.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum CustomSource {
+    /// A sample of a Prometheus or OpenMetrics text page, e.g. an exporter's `/metrics`.
+    #[default]
+    Scrape,
+    /// An instant PromQL query against a Prometheus server's HTTP API.
+    Promql,
+}
+
+/// `[CUSTOM.<name>]`: a value from another service, shown by `metric:<name>` on the dashboard.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct CustomMetricConfig {
+    #[serde(default)]
+    pub source: CustomSource,
+    /// The page to scrape, or the Prometheus server, e.g. `http://prometheus:9090`.
+    pub url: String,
+    /// Scrape only: the metric name. Samples matching `labels` are added up.
+    #[serde(default)]
+    pub metric: String,
+    /// Scrape only: `label=value` pairs the sample must have.
+    #[serde(default, deserialize_with = "super::deserialize_string_or_vec")]
+    pub labels: Vec<String>,
+    /// PromQL only: the query. Several series in the result are added up.
+    #[serde(default)]
+    pub query: String,
+    /// Shows the per-second rate of a counter instead of its value.
+    #[serde(default)]
+    pub rate: bool,
+    /// Shown before the value; the section name when empty.
+    #[serde(default)]
+    pub label: String,
+    /// Appended to the value, e.g. ` req/s`.
+    #[serde(default)]
+    pub unit: String,
+    /// Multiplies the value, e.g. `100` for a ratio shown in percent.
+    #[serde(default = "default_custom_scale")]
+    pub scale: f64,
+    /// The value drawn as a full bar; without one the metric is a text row.
+    #[serde(default)]
+    pub max: f64,
+    /// Seconds between fetches.
+    #[serde(default = "default_custom_interval")]
+    pub interval: u64,
+    /// Seconds before the fetch fails.
+    #[serde(default = "default_probe_timeout")]
+    pub timeout: u64,
+}
+
+impl Default for CustomMetricConfig {
+    fn default() -> Self {
+        Self {
+            source: CustomSource::default(),
+            url: String::new(),
+            metric: String::new(),
+            labels: Vec::new(),
+            query: String::new(),
+            rate: false,
+            label: String::new(),
+            unit: String::new(),
+            scale: default_custom_scale(),
+            max: 0.0,
+            interval: default_custom_interval(),
+            timeout: default_probe_timeout(),
+        }
+    }
+}
+
+/// Where the log panel reads from.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum LogSource {
+    /// The first file when it exists, otherwise the journal.
+    #[default]
+    Auto,
+    File,
+    Journal,
+}
+
+/// What happens to log lines longer than `length`.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum LogOverflow {
+    /// Cut and end with `...`.
+    #[default]
+    Ellipsize,
+    /// Continue on the next line, which costs older lines their place.
+    Wrap,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct SyslogConfig {
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    #[serde(default)]
+    pub interval: u64,
+    /// Number of log lines shown.
+    #[serde(default = "default_syslog_lines")]
+    pub lines: usize,
+    /// Maximum characters per line.
+    #[serde(default = "default_syslog_length")]
+    pub length: usize,
+    #[serde(default)]
+    pub source: LogSource,
+    /// Log files followed by the `file` source, e.g. the syslog plus nginx and application logs.
+    #[serde(
+        default = "default_syslog_files",
+        alias = "file",
+        deserialize_with = "super::deserialize_string_or_vec"
+    )]
+    pub files: Vec<String>,
+    /// Journal only: least severe priority shown, by name (`err`, `warning`, ...) or 0-7.
+    #[serde(default)]
+    pub priority: String,
+    /// Journal only: systemd units to show, e.g. `nginx.service`.
+    #[serde(default, deserialize_with = "super::deserialize_string_or_vec")]
+    pub units: Vec<String>,
+    /// Journal only: syslog identifiers to show, e.g. `kernel`.
+    #[serde(default, deserialize_with = "super::deserialize_string_or_vec")]
+    pub identifiers: Vec<String>,
+    /// Regular expressions: when any are given, only lines matching one of them are shown.
+    #[serde(default, deserialize_with = "super::deserialize_string_or_vec")]
+    pub include: Vec<String>,
+    /// Regular expressions: lines matching any of them are hidden.
+    #[serde(default, deserialize_with = "super::deserialize_string_or_vec")]
+    pub exclude: Vec<String>,
+    #[serde(default)]
+    pub overflow: LogOverflow,
+}
+
+impl Default for SyslogConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            interval: 0,
+            lines: default_syslog_lines(),
+            length: default_syslog_length(),
+            source: LogSource::default(),
+            files: default_syslog_files(),
+            priority: String::new(),
+            units: Vec::new(),
+            identifiers: Vec::new(),
+            include: Vec::new(),
+            exclude: Vec::new(),
+            overflow: LogOverflow::default(),
+        }
+    }
+}
+
+macro_rules! impl_collector_settings {
+    ($($config:ty),*) => {
+        $(
+            impl CollectorSettings for $config {
+                fn enabled(&self) -> bool {
+                    self.enabled
+                }
+
+                fn interval(&self) -> Duration {
+                    Duration::from_secs(self.interval)
+                }
+            }
+        )*
+    };
+}
+
+impl_collector_settings!(
+    CollectorConfig,
+    ProcessesConfig,
+    PressureConfig,
+    ContainersConfig,
+    ProbesConfig,
+    SystemdConfig,
+    SyslogConfig
+);
+
+/// `[COLLECTORS]`: one sub-section per collector, e.g. `[COLLECTORS.nvidia]` with `enabled = false`
+/// to skip `nvidia-smi` on machines without an NVIDIA GPU.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
+pub struct CollectorsConfig {
+    #[serde(default)]
+    pub cpu: CollectorConfig,
+    #[serde(default)]
+    pub memory: CollectorConfig,
+    /// Pressure Stall Information.
+    #[serde(default)]
+    pub pressure: PressureConfig,
+    #[serde(default)]
+    pub disk: CollectorConfig,
+    #[serde(default)]
+    pub network: CollectorConfig,
+    #[serde(default)]
+    pub system: CollectorConfig,
+    #[serde(default)]
+    pub sensors: CollectorConfig,
+    #[serde(default)]
+    pub nvidia: CollectorConfig,
+    /// AMD and Intel GPUs from sysfs.
+    #[serde(default)]
+    pub gpu: CollectorConfig,
+    /// Batteries, AC adapters and RAPL power.
+    #[serde(default)]
+    pub power: CollectorConfig,
+    #[serde(default)]
+    pub processes: ProcessesConfig,
+    /// Per-container and per-slice usage from cgroup v2.
+    #[serde(default)]
+    pub containers: ContainersConfig,
+    /// Failed and watched systemd units.
+    #[serde(default)]
+    pub systemd: SystemdConfig,
+    /// Reachability checks of `[PROBES.<name>]`.
+    #[serde(default)]
+    pub probes: ProbesConfig,
+    /// Values from other services, `[CUSTOM.<name>]`.
+    #[serde(default)]
+    pub custom: CollectorConfig,
+    #[serde(default)]
+    pub syslog: SyslogConfig,
+}
@@ -0,0 +1,397 @@
+use config::{Map, Value, ValueKind};
+use std::error::Error;
+use std::fmt;
+
+/// A line-preserving INI document.
+///
+/// Unlike the `config` crate's built-in INI format this keeps every line of the original file
+/// (comments, blank lines and key order), so a configuration can be loaded, changed and written
+/// back without losing anything the user typed by hand.
+///
+/// ## Syntax
+///
+/// - `[SECTION]` starts a section, `[SECTION.sub]` a nested table (e.g. `[RESOURCES.sensors]`).
+/// - `key = value` sets a value. Repeating a key within a section builds a list.
+/// - Values and keys may be wrapped in double quotes; `\"` and `\\` are unescaped inside quotes.
+/// - Lines starting with `;` or `#` are comments, as is anything after ` ;` or ` #` on a value line.
+#[derive(Debug, Clone, Default)]
+pub struct IniDocument {
+    lines: Vec<Line>,
+}
+
+#[derive(Debug, Clone)]
+enum Line {
+    /// Blank lines and comments, kept verbatim.
+    Raw(String),
+    Section {
+        name: String,
+        raw: String,
+    },
+    Entry {
+        key: String,
+        value: String,
+        comment: Option<String>,
+        raw: String,
+    },
+}
+
+#[derive(Debug)]
+pub struct IniError {
+    line: usize,
+    message: String,
+}
+
+impl fmt::Display for IniError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl Error for IniError {}
+
+impl IniDocument {
+    pub fn parse(text: &str) -> Result<Self, IniError> {
+        let mut lines = Vec::new();
+
+        for (index, raw) in text.lines().enumerate() {
+            let trimmed = raw.trim();
+            let error = |message: &str| IniError {
+                line: index + 1,
+                message: message.to_string(),
+            };
+
+            if trimmed.is_empty() || trimmed.starts_with(';') || trimmed.starts_with('#') {
+                lines.push(Line::Raw(raw.to_string()));
+            } else if let Some(rest) = trimmed.strip_prefix('[') {
+                let (name, _) = rest
+                    .split_once(']')
+                    .ok_or_else(|| error("unterminated section header"))?;
+                lines.push(Line::Section {
+                    name: name.trim().to_string(),
+                    raw: raw.to_string(),
+                });
+            } else {
+                let (key, rest) = split_key(trimmed).ok_or_else(|| error("expected `key = value`"))?;
+                let (value, comment) = split_value(rest).map_err(|message| error(&message))?;
+                lines.push(Line::Entry {
+                    key,
+                    value,
+                    comment,
+                    raw: raw.to_string(),
+                });
+            }
+        }
+
+        Ok(Self { lines })
+    }
+
+    /// Converts the document into the nested table structure understood by the `config` crate.
+    pub fn to_map(&self, uri: Option<&String>) -> Map<String, Value> {
+        let mut root = Map::new();
+        let mut path: Vec<String> = Vec::new();
+
+        for line in &self.lines {
+            match line {
+                Line::Raw(_) => {}
+                Line::Section { name, .. } => {
+                    path = name.split('.').map(|s| s.trim().to_string()).collect();
+                    table_at(&mut root, &path, uri);
+                }
+                Line::Entry { key, value, .. } => {
+                    let table = table_at(&mut root, &path, uri);
+                    let value = Value::new(uri, ValueKind::String(value.clone()));
+                    match table.get_mut(key) {
+                        Some(Value {
+                            kind: ValueKind::Array(items),
+                            ..
+                        }) => items.push(value),
+                        Some(existing) => {
+                            let first = existing.clone();
+                            *existing = Value::new(uri, ValueKind::Array(vec![first, value]));
+                        }
+                        None => {
+                            table.insert(key.clone(), value);
+                        }
+                    }
+                }
+            }
+        }
+
+        root
+    }
+
+    pub fn has_section(&self, section: &str) -> bool {
+        self.lines
+            .iter()
+            .any(|line| matches!(line, Line::Section { name, .. } if name == section))
+    }
+
+    /// Returns all values of `key` in `section` (`""` for keys before the first section).
+    pub fn get(&self, section: &str, key: &str) -> Vec<String> {
+        self.entries_of(section, key)
+            .into_iter()
+            .filter_map(|index| match &self.lines[index] {
+                Line::Entry { value, .. } => Some(value.clone()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Returns the distinct keys of `section` in file order.
+    pub fn keys(&self, section: &str) -> Vec<String> {
+        let mut current = "";
+        let mut keys: Vec<String> = Vec::new();
+        for line in &self.lines {
+            match line {
+                Line::Section { name, .. } => current = name,
+                Line::Entry { key, .. } if current == section && !keys.contains(key) => keys.push(key.clone()),
+                _ => {}
+            }
+        }
+        keys
+    }
+
+    /// Appends `[section]` unless it already exists.
+    ///
+    /// Nested sections are placed after their parent's block so related settings stay together.
+    pub fn ensure_section(&mut self, section: &str) {
+        if section.is_empty() || self.has_section(section) {
+            return;
+        }
+
+        let mut position = self.lines.len();
+        if let Some((parent, _)) = section.rsplit_once('.') {
+            if let Some(end) = self.block_end(parent, true) {
+                position = end;
+            }
+        }
+
+        let mut new_lines = Vec::new();
+        if position > 0 && !matches!(&self.lines[position - 1], Line::Raw(raw) if raw.trim().is_empty()) {
+            new_lines.push(Line::Raw(String::new()));
+        }
+        new_lines.push(Line::Section {
+            name: section.to_string(),
+            raw: format!("[{}]", section),
+        });
+        if position < self.lines.len() {
+            new_lines.push(Line::Raw(String::new()));
+        }
+        self.lines.splice(position..position, new_lines);
+    }
+
+    /// Replaces every value of `key` in `section` with `values`.
+    ///
+    /// Existing entries are updated in place (keeping their inline comments) and unchanged
+    /// entries are left byte-for-byte identical. New keys are appended to the end of the section.
+    /// An empty `values` removes the key.
+    pub fn set(&mut self, section: &str, key: &str, values: &[String]) {
+        let existing = self.entries_of(section, key);
+
+        if existing.is_empty() && values.is_empty() {
+            return;
+        }
+
+        if existing.is_empty() {
+            self.ensure_section(section);
+            let position = self.block_end(section, false).unwrap_or(self.lines.len());
+            let new_lines: Vec<Line> = values.iter().map(|value| new_entry(key, value, None)).collect();
+            self.lines.splice(position..position, new_lines);
+            return;
+        }
+
+        let mut replacements = Vec::new();
+        for (n, value) in values.iter().enumerate() {
+            match existing.get(n).map(|index| &self.lines[*index]) {
+                Some(line @ Line::Entry { value: old, .. }) if old == value => replacements.push(line.clone()),
+                Some(Line::Entry { comment, .. }) => replacements.push(new_entry(key, value, comment.clone())),
+                _ => replacements.push(new_entry(key, value, None)),
+            }
+        }
+
+        // Extra values go straight after the last existing entry, surplus entries are removed.
+        let anchor = existing[existing.len() - 1];
+        for index in existing.iter().rev() {
+            if *index != anchor {
+                self.lines.remove(*index);
+            }
+        }
+        let anchor = anchor - (existing.len() - 1);
+        self.lines.splice(anchor..anchor + 1, replacements);
+    }
+
+    pub fn to_text(&self) -> String {
+        let mut text = String::new();
+        for line in &self.lines {
+            match line {
+                Line::Raw(raw) | Line::Section { raw, .. } | Line::Entry { raw, .. } => text.push_str(raw),
+            }
+            text.push('\n');
+        }
+        text
+    }
+
+    fn entries_of(&self, section: &str, key: &str) -> Vec<usize> {
+        let mut current = "";
+        let mut indexes = Vec::new();
+        for (index, line) in self.lines.iter().enumerate() {
+            match line {
+                Line::Section { name, .. } => current = name,
+                Line::Entry { key: k, .. } if current == section && k == key => indexes.push(index),
+                _ => {}
+            }
+        }
+        indexes
+    }
+
+    /// Index just after the last entry belonging to `section` (and its sub-sections when
+    /// `include_children` is set), ignoring trailing blank lines and comments.
+    fn block_end(&self, section: &str, include_children: bool) -> Option<usize> {
+        let prefix = format!("{}.", section);
+        let mut current: Option<&str> = if section.is_empty() { Some("") } else { None };
+        let mut end = None;
+        for (index, line) in self.lines.iter().enumerate() {
+            match line {
+                Line::Section { name, .. } => {
+                    current = Some(name);
+                    if name == section || (include_children && name.starts_with(&prefix)) {
+                        end = Some(index + 1);
+                    }
+                }
+                Line::Entry { .. } => {
+                    if let Some(name) = current {
+                        if name == section || (include_children && name.starts_with(&prefix)) {
+                            end = Some(index + 1);
+                        }
+                    }
+                }
+                Line::Raw(_) => {}
+            }
+        }
+        end
+    }
+}
+
+fn new_entry(key: &str, value: &str, comment: Option<String>) -> Line {
+    let mut raw = format!("{} = {}", format_key(key), format_value(value));
+    if let Some(comment) = &comment {
+        raw.push(' ');
+        raw.push_str(comment);
+    }
+    Line::Entry {
+        key: key.to_string(),
+        value: value.to_string(),
+        comment,
+        raw,
+    }
+}
+
+fn table_at<'a>(root: &'a mut Map<String, Value>, path: &[String], uri: Option<&String>) -> &'a mut Map<String, Value> {
+    let mut table = root;
+    for part in path {
+        let entry = table
+            .entry(part.clone())
+            .or_insert_with(|| Value::new(uri, ValueKind::Table(Map::new())));
+        if !matches!(entry.kind, ValueKind::Table(_)) {
+            *entry = Value::new(uri, ValueKind::Table(Map::new()));
+        }
+        let ValueKind::Table(inner) = &mut entry.kind else {
+            unreachable!()
+        };
+        table = inner;
+    }
+    table
+}
+
+fn split_key(line: &str) -> Option<(String, &str)> {
+    if let Some(rest) = line.strip_prefix('"') {
+        let (key, rest) = read_quoted(rest).ok()?;
+        let rest = rest.trim_start().strip_prefix('=')?;
+        return Some((key, rest));
+    }
+    let (key, rest) = line.split_once('=')?;
+    let key = key.trim();
+    if key.is_empty() {
+        return None;
+    }
+    Some((key.to_string(), rest))
+}
+
+fn split_value(rest: &str) -> Result<(String, Option<String>), String> {
+    let rest = rest.trim();
+    if let Some(quoted) = rest.strip_prefix('"') {
+        let (value, tail) = read_quoted(quoted)?;
+        let tail = tail.trim();
+        let comment = (!tail.is_empty()).then(|| tail.to_string());
+        return Ok((value, comment));
+    }
+
+    // An inline comment needs whitespace in front so values such as `#fff` survive.
+    let comment_start = rest
+        .char_indices()
+        .find(|(i, c)| (*c == '#' || *c == ';') && rest[..*i].ends_with(char::is_whitespace))
+        .map(|(i, _)| i);
+    match comment_start {
+        Some(i) => Ok((rest[..i].trim_end().to_string(), Some(rest[i..].to_string()))),
+        None => Ok((rest.to_string(), None)),
+    }
+}
+
+fn read_quoted(text: &str) -> Result<(String, &str), String> {
+    let mut value = String::new();
+    let mut chars = text.char_indices();
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '"' => return Ok((value, &text[i + 1..])),
+            '\\' => match chars.next() {
+                Some((_, escaped)) => value.push(escaped),
+                None => break,
+            },
+            c => value.push(c),
+        }
+    }
+    Err("unterminated quoted string".to_string())
+}
+
+fn needs_quotes(text: &str) -> bool {
+    text.is_empty()
+        || text.trim() != text
+        || text.starts_with('[')
+        || text.contains(['"', '\\', '#', ';', '='])
+}
+
+fn quote(text: &str) -> String {
+    format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+fn format_key(key: &str) -> String {
+    if needs_quotes(key) {
+        quote(key)
+    } else {
+        key.to_string()
+    }
+}
+
+fn format_value(value: &str) -> String {
+    if needs_quotes(value) {
+        quote(value)
+    } else {
+        value.to_string()
+    }
+}
+
+/// `config` crate file format backed by [`IniDocument`].
+#[derive(Debug, Clone, Copy)]
+pub struct IniFormat;
+
+impl config::Format for IniFormat {
+    fn parse(&self, uri: Option<&String>, text: &str) -> Result<Map<String, Value>, Box<dyn Error + Send + Sync>> {
+        Ok(IniDocument::parse(text)?.to_map(uri))
+    }
+}
+
+impl config::FileStoredFormat for IniFormat {
+    fn file_extensions(&self) -> &'static [&'static str] {
+        &["ini"]
+    }
+}
@@ -1,12 +1,21 @@
+mod collectors;
+mod ini;
+mod profile;
+
 use anyhow::{Context, Result};
-use config::{Config, File};
+use config::{Config, Map, Source, Value};
 use indexmap::IndexMap;
 use log::{debug, info, LevelFilter};
-use serde::{Deserialize, Deserializer};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt;
 use std::fs;
-use std::path::{Path};
+use std::path::Path;
 use std::str::FromStr;
 
+pub use collectors::*;
+pub use ini::{IniDocument, IniFormat};
+pub use profile::HostIdentity;
+
 fn default_vid() -> u16 {
     0x1908
 }
@@ -15,6 +24,14 @@ fn default_pid() -> u16 {
     0x0102
 }
 
+fn default_bars() -> Vec<String> {
+    ["cpu", "memory", "disk", "gpu"].iter().map(|s| s.to_string()).collect()
+}
+
+fn default_processes() -> Vec<String> {
+    ["cpu", "memory"].iter().map(|s| s.to_string()).collect()
+}
+
 fn deserialize_hex_or_int<'de, D>(deserializer: D) -> Result<u16, D::Error>
 where
     D: Deserializer<'de>,
@@ -30,27 +47,73 @@ where
     }
 }
 
-#[derive(Debug, Deserialize, Clone)]
+fn serialize_hex<S>(value: &u16, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    serializer.serialize_str(&format!("{:#06x}", value))
+}
+
+/// Accepts either a single value (`disks = "sda"`) or a list built from repeated keys.
+fn deserialize_string_or_vec<'de, D>(deserializer: D) -> Result<Vec<String>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    struct StringOrVec;
+
+    impl<'de> serde::de::Visitor<'de> for StringOrVec {
+        type Value = Vec<String>;
+
+        fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+            formatter.write_str("a string or a list of strings")
+        }
+
+        fn visit_str<E: serde::de::Error>(self, value: &str) -> Result<Self::Value, E> {
+            Ok(vec![value.to_string()])
+        }
+
+        fn visit_seq<A: serde::de::SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+            let mut values = Vec::new();
+            while let Some(value) = seq.next_element::<String>()? {
+                values.push(value);
+            }
+            Ok(values)
+        }
+    }
+
+    deserializer.deserialize_any(StringOrVec)
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 pub struct LcdConfig {
     pub backlight: u8,
     pub width: u16,
     pub height: u16,
     pub file: String,
     pub polling: u64,
-    #[serde(default = "default_vid", deserialize_with = "deserialize_hex_or_int")]
+    #[serde(default = "default_vid", deserialize_with = "deserialize_hex_or_int", serialize_with = "serialize_hex")]
     pub vid: u16,
-    #[serde(default = "default_pid", deserialize_with = "deserialize_hex_or_int")]
+    #[serde(default = "default_pid", deserialize_with = "deserialize_hex_or_int", serialize_with = "serialize_hex")]
     pub pid: u16,
 }
 
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 pub struct DashboardConfig {
     pub file: String,
     pub enabled: bool,
     pub save_to_file: bool,
+    /// Rows of the left column, top to bottom: `cpu`, `cores`, `memory`, `pressure`, `disk`,
+    /// `mounts`, `devices`, `raid`, `interfaces`, `tcp`, `gpu`, `battery`, `units`, `probes`, and
+    /// `metric:<name>` for a `[CUSTOM.<name>]` value.
+    #[serde(default = "default_bars", deserialize_with = "deserialize_string_or_vec")]
+    pub bars: Vec<String>,
+    /// Process rankings of the right column, top to bottom: `cpu`, `memory`, `io`, `connections`,
+    /// `containers`, or `metric:<name>`.
+    #[serde(default = "default_processes", deserialize_with = "deserialize_string_or_vec")]
+    pub processes: Vec<String>,
 }
 
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 pub struct LoggingConfig {
     #[serde(default = "default_log_level")]
     pub level: String,
@@ -68,37 +131,44 @@ fn default_log_level() -> String {
     "info".to_string()
 }
 
-#[derive(Debug, Deserialize, Clone)]
-pub struct ResourcesConfig {
-    #[serde(default)]
-    pub disks: Vec<String>,
-    #[serde(default)]
-    pub networks: Vec<String>,
-    #[serde(default)]
-    pub mount_points: Vec<String>,
+fn default_metrics_listen() -> String {
+    "127.0.0.1:9206".to_string()
+}
+
+/// Prometheus `/metrics` endpoint publishing everything collected.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct MetricsConfig {
     #[serde(default)]
-    pub sensors: IndexMap<String, String>,
+    pub enabled: bool,
+    /// Address and port to listen on, e.g. `0.0.0.0:9206` to be scraped from other hosts.
+    #[serde(default = "default_metrics_listen")]
+    pub listen: String,
 }
 
-impl Default for ResourcesConfig {
+impl Default for MetricsConfig {
     fn default() -> Self {
-        let mut sensors = IndexMap::new();
-        sensors.insert("k10temp".to_string(), "CPU".to_string());
-        sensors.insert("amdgpu".to_string(), "GPU0".to_string());
-        sensors.insert("NVIDIA RTX A2000".to_string(), "GPU1".to_string());
-        sensors.insert("r8169".to_string(), "Eth0".to_string());
-        sensors.insert("nvme composite".to_string(), "NVMe0".to_string());
-
         Self {
-            disks: vec!["nvme0n1".to_string(), "sda1".to_string()],
-            networks: vec!["enp13s0".to_string(), "enx0024278838ca".to_string()],
-            mount_points: vec!["/".to_string()],
-            sensors,
+            enabled: false,
+            listen: default_metrics_listen(),
         }
     }
 }
 
-#[derive(Debug, Deserialize, Clone)]
+/// Devices shown on the dashboard. An empty list (or an empty `[RESOURCES.sensors]`) means
+/// "auto": whatever `ax206lcd discover` finds on this machine is used.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
+pub struct ResourcesConfig {
+    #[serde(default, deserialize_with = "deserialize_string_or_vec")]
+    pub disks: Vec<String>,
+    #[serde(default, deserialize_with = "deserialize_string_or_vec")]
+    pub networks: Vec<String>,
+    #[serde(default, deserialize_with = "deserialize_string_or_vec")]
+    pub mount_points: Vec<String>,
+    #[serde(default)]
+    pub sensors: IndexMap<String, String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
 pub struct AppConfig {
     #[serde(rename = "LCD")]
     pub lcd: LcdConfig,
@@ -108,6 +178,33 @@ pub struct AppConfig {
     pub resources: ResourcesConfig,
     #[serde(rename = "LOGGING", default)]
     pub logging: LoggingConfig,
+    #[serde(rename = "COLLECTORS", default)]
+    pub collectors: CollectorsConfig,
+    #[serde(rename = "METRICS", default)]
+    pub metrics: MetricsConfig,
+    /// Reachability checks by name, from `[PROBES.<name>]` sections.
+    #[serde(rename = "PROBES", default, skip_serializing_if = "IndexMap::is_empty")]
+    pub probes: IndexMap<String, ProbeConfig>,
+    /// Values scraped from other services by name, from `[CUSTOM.<name>]` sections.
+    #[serde(rename = "CUSTOM", default, skip_serializing_if = "IndexMap::is_empty")]
+    pub custom: IndexMap<String, CustomMetricConfig>,
+    /// Name of the `[PROFILE.<name>]` overlay applied when loading, if any.
+    #[serde(skip)]
+    pub profile: Option<String>,
+}
+
+/// Already merged configuration values handed to the `config` crate.
+#[derive(Debug, Clone)]
+struct MapSource(Map<String, Value>);
+
+impl Source for MapSource {
+    fn clone_into_box(&self) -> Box<dyn Source + Send + Sync> {
+        Box::new(self.clone())
+    }
+
+    fn collect(&self) -> std::result::Result<Map<String, Value>, config::ConfigError> {
+        Ok(self.0.clone())
+    }
 }
 
 impl Default for LcdConfig {
@@ -130,17 +227,8 @@ impl Default for DashboardConfig {
             file: "dashboard.png".to_string(),
             enabled: false,
             save_to_file: false,
-        }
-    }
-}
-
-impl Default for AppConfig {
-    fn default() -> Self {
-        Self {
-            lcd: LcdConfig::default(),
-            dashboard: DashboardConfig::default(),
-            resources: ResourcesConfig::default(),
-            logging: LoggingConfig::default(),
+            bars: default_bars(),
+            processes: default_processes(),
         }
     }
 }
@@ -163,93 +251,185 @@ impl AppConfig {
     }
 
     pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self> {
+        Self::load(path, None)
+    }
+
+    /// Loads `path` with its `include`s and applies a profile: `profile` when given, otherwise
+    /// the first `[PROFILE.<name>]` matching this machine's hostname or DMI product name.
+    pub fn load<P: AsRef<Path>>(path: P, profile: Option<&str>) -> Result<Self> {
+        Self::load_for_host(path, profile, &HostIdentity::detect())
+    }
+
+    pub fn load_for_host<P: AsRef<Path>>(path: P, profile: Option<&str>, host: &HostIdentity) -> Result<Self> {
         let config_path = path.as_ref();
         debug!("Loading configuration from {}", config_path.display());
 
+        let layers = profile::Layers::read(config_path)?;
+        let mut merged = layers.base();
+
+        let selected = profile::select(&merged, profile, host)?;
+        if let Some(name) = &selected {
+            info!("Applying configuration profile '{}'", name);
+            let overlay = profile::overlay(&merged, name);
+            profile::merge(&mut merged, &overlay);
+        }
+        merged.shift_remove(profile::PROFILE_SECTION);
+        merged.shift_remove(profile::INCLUDE_KEY);
+
         let config = Config::builder()
-            .add_source(File::with_name(config_path.to_str().unwrap_or("")).format(config::FileFormat::Ini))
+            .add_source(MapSource(merged))
             .build()
             .context(format!("Failed to load config from {}", config_path.display()))?;
 
-        let app_config: AppConfig = config.try_deserialize()
+        let mut app_config: AppConfig = config.try_deserialize()
             .context("Failed to deserialize config")?;
+        app_config.profile = selected;
 
         Ok(app_config)
     }
 
+    /// Writes the configuration to `path`.
+    ///
+    /// Values are serialised through serde, so everything `from_file` reads is written back.
+    /// When the file already exists only the changed lines are touched: comments, blank lines
+    /// and key order are preserved. The file is replaced atomically so a crash mid-write never
+    /// leaves a truncated config behind.
+    ///
+    /// Values are written to the layer they were loaded from: with an active profile, changes go
+    /// into `[PROFILE.<name>.*]`, and values that merely repeat an included file or a default are
+    /// not copied into an existing file. A new file gets every setting.
     pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<()> {
         let config_path = path.as_ref();
 
-        // Build the config string
-        let mut config_str = String::new();
-
-        // LCD section
-        config_str.push_str(&format!(
-            "[LCD]\nbacklight = {}\nwidth = {}\nheight = {}\nfile = {}\npolling = {}\nvid = {:#06x}\npid = {:#06x}\n\n",
-            self.lcd.backlight,
-            self.lcd.width,
-            self.lcd.height,
-            self.lcd.file,
-            self.lcd.polling,
-            self.lcd.vid,
-            self.lcd.pid
-        ));
-
-        // DASHBOARD section
-        config_str.push_str(&format!(
-            "[DASHBOARD]\nfile = {}\nenabled = {}\nsave_to_file = {}\n\n",
-            self.dashboard.file,
-            self.dashboard.enabled,
-            self.dashboard.save_to_file
-        ));
-
-        // LOGGING section
-        config_str.push_str(&format!(
-            "[LOGGING]\nlevel = {}\n\n",
-            self.logging.level
-        ));
-
-        // RESOURCES section
-        config_str.push_str("[RESOURCES]\n");
-
-        // Disks
-        if !self.resources.disks.is_empty() {
-            for disk in &self.resources.disks {
-                config_str.push_str(&format!("disks = \"{}\"\n", disk));
+        let existing = if config_path.exists() {
+            fs::read_to_string(config_path).context(format!("Failed to read config from {}", config_path.display()))?
+        } else {
+            String::new()
+        };
+
+        let mut writer = if !existing.trim().is_empty() {
+            let document = IniDocument::parse(&existing)
+                .context(format!("Failed to parse config from {}", config_path.display()))?;
+            let layers = profile::Layers::read(config_path)?;
+            let defaults = serde_json::to_value(AppConfig::default()).context("Failed to serialise config")?;
+            match &self.profile {
+                Some(name) => {
+                    let mut lower = layers.base();
+                    lower.shift_remove(profile::PROFILE_SECTION);
+                    SectionWriter {
+                        document,
+                        lower,
+                        defaults,
+                        prefix: format!("{}.{}.", profile::PROFILE_SECTION, name),
+                    }
+                }
+                None => SectionWriter {
+                    document,
+                    lower: layers.included,
+                    defaults,
+                    prefix: String::new(),
+                },
             }
-        }
+        } else {
+            // A new or empty file spells out every setting
+            SectionWriter {
+                document: IniDocument::default(),
+                lower: Map::new(),
+                defaults: serde_json::Value::Null,
+                prefix: String::new(),
+            }
+        };
 
-        // Networks
-        if !self.resources.networks.is_empty() {
-            for network in &self.resources.networks {
-                config_str.push_str(&format!("networks = \"{}\"\n", network));
+        let value = serde_json::to_value(self).context("Failed to serialise config")?;
+        if let serde_json::Value::Object(sections) = &value {
+            for (section, table) in sections {
+                let defaults = writer.defaults.get(section).cloned();
+                writer.write_section(section, table, defaults.as_ref());
             }
         }
+        let document = writer.document;
+
+        let mut temp_path = config_path.as_os_str().to_owned();
+        temp_path.push(".tmp");
+        fs::write(&temp_path, document.to_text())
+            .context(format!("Failed to save config to {}", config_path.display()))?;
+        fs::rename(&temp_path, config_path)
+            .context(format!("Failed to save config to {}", config_path.display()))?;
+
+        info!("Configuration saved to {}", config_path.display());
+        Ok(())
+    }
+}
+
+/// Writes serialised tables into one layer of the configuration.
+struct SectionWriter {
+    document: IniDocument,
+    /// Merged values of the layers underneath the one being written.
+    lower: Map<String, Value>,
+    /// Serialised `AppConfig::default()`, what a key left out of every layer reads as.
+    defaults: serde_json::Value,
+    /// Section prefix of the layer being written, e.g. `PROFILE.office.`.
+    prefix: String,
+}
 
-        // Mount points
-        if !self.resources.mount_points.is_empty() {
-            for mount_point in &self.resources.mount_points {
-                config_str.push_str(&format!("mount_points = \"{}\"\n", mount_point));
+impl SectionWriter {
+    /// Writes one serialised table into `section`; nested tables become `[section.key]`.
+    ///
+    /// Keys that are no longer part of the table (e.g. a removed sensor) are dropped from the file.
+    /// Keys the layer does not define yet are only added when they differ from the layers below,
+    /// or from `defaults` where no layer sets them.
+    fn write_section(&mut self, section: &str, value: &serde_json::Value, defaults: Option<&serde_json::Value>) {
+        let serde_json::Value::Object(table) = value else {
+            return;
+        };
+
+        let target = format!("{}{}", self.prefix, section);
+        if !profile::has_table(&self.lower, section) && defaults != Some(value) {
+            self.document.ensure_section(&target);
+        }
+        for key in self.document.keys(&target) {
+            if !table.contains_key(&key) {
+                self.document.set(&target, &key, &[]);
             }
         }
 
-        // Sensors
-        if !self.resources.sensors.is_empty() {
-            config_str.push_str("\n[RESOURCES.sensors]\n");
-            for (key, value) in &self.resources.sensors {
-                config_str.push_str(&format!("{} = \"{}\"\n", key, value));
+        for (key, item) in table {
+            let default = defaults.and_then(|defaults| defaults.get(key));
+            if item.is_object() {
+                self.write_section(&format!("{}.{}", section, key), item, default);
+                continue;
+            }
+
+            let values = scalars(item);
+            let defined = !self.document.get(&target, key).is_empty();
+            let inherited = profile::lookup(&self.lower, section, key)
+                .or_else(|| default.map(scalars))
+                .unwrap_or_default();
+            if defined || inherited != values {
+                self.document.set(&target, key, &values);
             }
         }
+    }
+}
 
-        fs::write(config_path, config_str)
-            .context(format!("Failed to save config to {}", config_path.display()))?;
+/// The INI values of a serialised key: one per list item, none for an unset option.
+fn scalars(value: &serde_json::Value) -> Vec<String> {
+    match value {
+        serde_json::Value::Array(items) => items.iter().map(scalar_to_string).collect(),
+        serde_json::Value::Null => Vec::new(),
+        _ => vec![scalar_to_string(value)],
+    }
+}
 
-        info!("Configuration saved to {}", config_path.display());
-        Ok(())
+fn scalar_to_string(value: &serde_json::Value) -> String {
+    match value {
+        serde_json::Value::String(s) => s.clone(),
+        other => other.to_string(),
     }
 }
 
 #[cfg(test)]
+#[allow(clippy::bool_assert_comparison)]
 mod tests {
     use super::*;
     use std::io::Write;
@@ -291,11 +471,6 @@ mod tests {
     #[test]
     fn test_save_config() {
         let mut config = AppConfig::default();
-        // Clear arrays to avoid serialization issues in tests
-        config.resources.disks.clear();
-        config.resources.networks.clear();
-        config.resources.mount_points.clear();
-        config.resources.sensors.clear();
 
         config.lcd.backlight = 7;
         config.lcd.width = 1024;
@@ -321,6 +496,80 @@ mod tests {
         assert_eq!(loaded_config.dashboard.file, "saved-dashboard.png");
         assert_eq!(loaded_config.dashboard.enabled, true);
         assert_eq!(loaded_config.dashboard.save_to_file, true);
+        assert_eq!(loaded_config, config);
+    }
+
+    #[test]
+    fn test_save_round_trips_lists_and_sensors() {
+        let mut config = AppConfig::default();
+        config.resources.disks = vec!["nvme0n1".to_string()];
+        config.resources.networks = vec!["eth0".to_string(), "wlan0".to_string()];
+        config.resources.mount_points.clear();
+        config.resources.sensors.clear();
+        config.resources.sensors.insert("NVIDIA RTX A2000".to_string(), "GPU1".to_string());
+        config.resources.sensors.insert("nvme composite".to_string(), "NVMe # 0".to_string());
+        config.resources.sensors.insert("chip=nvme device=0000:04:00.0 agg=max".to_string(), "NVMe1".to_string());
+
+        let temp_file = NamedTempFile::new().unwrap();
+        config.save(temp_file.path()).unwrap();
+
+        let loaded_config = AppConfig::from_file(temp_file.path()).unwrap();
+        assert_eq!(loaded_config, config);
+        assert!(loaded_config.resources.sensors.keys().eq(config.resources.sensors.keys()));
+    }
+
+    #[test]
+    fn test_save_preserves_comments_and_order() {
+        let mut temp_file = NamedTempFile::new().unwrap();
+        let config_content = "; Frame on the desk\n[LCD]\nfile = \"test.png\"\nbacklight = 5   # dim at night\nwidth = 800\nheight = 600\npolling = 10\n\n[DASHBOARD]\nfile = test-dashboard.png\nenabled = true\nsave_to_file = false\n\n[RESOURCES]\n# Disks to monitor\ndisks = \"sda\"\ndisks = \"sdb\"\n\n[RESOURCES.sensors]\nk10temp = \"CPU\"\namdgpu = \"GPU0\"\n";
+        temp_file.write_all(config_content.as_bytes()).unwrap();
+
+        let mut config = AppConfig::from_file(temp_file.path()).unwrap();
+        assert_eq!(config.resources.disks, vec!["sda", "sdb"]);
+        assert_eq!(config.resources.sensors.get("amdgpu").map(String::as_str), Some("GPU0"));
+
+        config.lcd.backlight = 3;
+        config.resources.disks = vec!["sda".to_string()];
+        config.resources.sensors.shift_remove("amdgpu");
+        config.save(temp_file.path()).unwrap();
+
+        let saved = fs::read_to_string(temp_file.path()).unwrap();
+        assert!(saved.starts_with("; Frame on the desk\n[LCD]\nfile = \"test.png\"\nbacklight = 3 # dim at night\nwidth = 800\n"));
+        assert!(saved.contains("# Disks to monitor\ndisks = \"sda\"\n"));
+        assert!(!saved.contains("sdb"));
+        assert!(!saved.contains("amdgpu"));
+
+        let loaded_config = AppConfig::from_file(temp_file.path()).unwrap();
+        assert_eq!(loaded_config, config);
+    }
+
+    #[test]
+    fn test_save_leaves_defaults_out() {
+        let mut temp_file = NamedTempFile::new().unwrap();
+        let config_content = "# Desk frame
+[LCD]
+backlight = 5
+width = 480
+height = 320
+file = current.png
+polling = 3
+
+[DASHBOARD]
+file = dashboard.png
+enabled = true
+save_to_file = false
+";
+        temp_file.write_all(config_content.as_bytes()).unwrap();
+
+        let mut config = AppConfig::from_file(temp_file.path()).unwrap();
+        config.save(temp_file.path()).unwrap();
+        assert_eq!(fs::read_to_string(temp_file.path()).unwrap(), config_content);
+
+        // As `discover --write` does: only the new values are added
+        config.resources.disks = vec!["sda".to_string()];
+        config.save(temp_file.path()).unwrap();
+        let expected = format!("{}\n[RESOURCES]\ndisks = sda\n", config_content);
+        assert_eq!(fs::read_to_string(temp_file.path()).unwrap(), expected);
     }
 
     #[test]
@@ -337,4 +586,67 @@ mod tests {
         assert_eq!(config.lcd.vid, 0x1234);
         assert_eq!(config.lcd.pid, 0x0102);
     }
+
+    fn write_config(dir: &Path, name: &str, content: &str) -> std::path::PathBuf {
+        let path = dir.join(name);
+        fs::write(&path, content).unwrap();
+        path
+    }
+
+    const PROFILED_CONFIG: &str = "include = \"layout.ini\"\n\n[LCD]\nbacklight = 5\nwidth = 800\nheight = 600\nfile = test.png\npolling = 10\n\n[RESOURCES]\ndisks = sda\n\n[RESOURCES.sensors]\nk10temp = CPU\n\n[PROFILE.office]\nhostname = office-*\n\n[PROFILE.office.RESOURCES]\ndisks = nvme0n1\nnetworks = eno1\n\n[PROFILE.lab]\nproduct = \"ThinkStation P620\"\n\n[PROFILE.lab.LCD]\nbacklight = 1\n";
+
+    #[test]
+    fn test_include_and_profiles() {
+        let dir = tempfile::tempdir().unwrap();
+        write_config(dir.path(), "layout.ini", "[DASHBOARD]\nfile = shared.png\nenabled = true\nsave_to_file = false\n\n[LCD]\nbacklight = 7\n");
+        let path = write_config(dir.path(), "config.ini", PROFILED_CONFIG);
+
+        let office = HostIdentity { hostname: "office-42".to_string(), product: String::new() };
+        let config = AppConfig::load_for_host(&path, None, &office).unwrap();
+        assert_eq!(config.profile.as_deref(), Some("office"));
+        assert_eq!(config.dashboard.file, "shared.png");
+        assert_eq!(config.lcd.backlight, 5);
+        assert_eq!(config.resources.disks, vec!["nvme0n1"]);
+        assert_eq!(config.resources.networks, vec!["eno1"]);
+        assert_eq!(config.resources.sensors.get("k10temp").map(String::as_str), Some("CPU"));
+
+        let lab = HostIdentity { hostname: "unknown".to_string(), product: "ThinkStation P620".to_string() };
+        let config = AppConfig::load_for_host(&path, None, &lab).unwrap();
+        assert_eq!(config.profile.as_deref(), Some("lab"));
+        assert_eq!(config.lcd.backlight, 1);
+        assert_eq!(config.resources.disks, vec!["sda"]);
+
+        let config = AppConfig::load_for_host(&path, Some("lab"), &office).unwrap();
+        assert_eq!(config.profile.as_deref(), Some("lab"));
+
+        let none = HostIdentity::default();
+        let config = AppConfig::load_for_host(&path, None, &none).unwrap();
+        assert_eq!(config.profile, None);
+
+        assert!(AppConfig::load_for_host(&path, Some("missing"), &none).is_err());
+    }
+
+    #[test]
+    fn test_save_writes_to_active_profile() {
+        let dir = tempfile::tempdir().unwrap();
+        write_config(dir.path(), "layout.ini", "[DASHBOARD]\nfile = shared.png\nenabled = true\nsave_to_file = false\n");
+        let path = write_config(dir.path(), "config.ini", PROFILED_CONFIG);
+
+        let office = HostIdentity { hostname: "office-42".to_string(), product: String::new() };
+        let mut config = AppConfig::load_for_host(&path, None, &office).unwrap();
+        config.resources.disks.push("sdb".to_string());
+        config.lcd.polling = 2;
+        config.save(&path).unwrap();
+
+        let saved = fs::read_to_string(&path).unwrap();
+        assert!(saved.contains("[RESOURCES]\ndisks = sda\n"));
+        assert!(saved.contains("[PROFILE.office.RESOURCES]\ndisks = nvme0n1\ndisks = sdb\n"));
+        assert!(saved.contains("[PROFILE.office.LCD]\npolling = 2\n"));
+        assert!(!saved.contains("shared.png"));
+
+        assert_eq!(AppConfig::load_for_host(&path, None, &office).unwrap(), config);
+        let base = AppConfig::load_for_host(&path, None, &HostIdentity::default()).unwrap();
+        assert_eq!(base.resources.disks, vec!["sda"]);
+        assert_eq!(base.lcd.polling, 10);
+    }
 }
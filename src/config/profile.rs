@@ -0,0 +1,211 @@
+use crate::config::ini::IniDocument;
+use anyhow::{bail, Context, Result};
+use config::{Map, Value, ValueKind};
+use log::debug;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Top-level directive pulling other INI files in underneath the current one.
+pub const INCLUDE_KEY: &str = "include";
+/// Section holding the per-host overlays, e.g. `[PROFILE.office]` and `[PROFILE.office.RESOURCES]`.
+pub const PROFILE_SECTION: &str = "PROFILE";
+
+/// What a profile can be matched against.
+#[derive(Debug, Clone, Default)]
+pub struct HostIdentity {
+    pub hostname: String,
+    pub product: String,
+}
+
+impl HostIdentity {
+    pub fn detect() -> Self {
+        let read = |path: &str| fs::read_to_string(path).map(|s| s.trim().to_string()).unwrap_or_default();
+        let mut hostname = read("/etc/hostname");
+        if hostname.is_empty() {
+            hostname = std::env::var("HOSTNAME").unwrap_or_default();
+        }
+        Self {
+            hostname,
+            product: read("/sys/class/dmi/id/product_name"),
+        }
+    }
+}
+
+/// A configuration file with its `include`s resolved.
+pub struct Layers {
+    /// Everything pulled in through `include`, merged in order.
+    pub included: Map<String, Value>,
+    /// The file itself.
+    pub main: Map<String, Value>,
+}
+
+impl Layers {
+    pub fn read(path: &Path) -> Result<Self> {
+        let mut stack = Vec::new();
+        let main = read_map(path)?;
+        let included = read_includes(path, &main, &mut stack)?;
+        Ok(Self { included, main })
+    }
+
+    /// Includes with the main file merged on top, before any profile is applied.
+    pub fn base(&self) -> Map<String, Value> {
+        let mut base = self.included.clone();
+        merge(&mut base, &self.main);
+        base
+    }
+}
+
+/// Picks the profile to apply: the explicitly requested one, otherwise the first profile whose
+/// `hostname` or `product` (DMI product name) matches this machine.
+///
+/// Both keys may be repeated and accept `*` wildcards, e.g. `hostname = build-*`.
+pub fn select(base: &Map<String, Value>, requested: Option<&str>, host: &HostIdentity) -> Result<Option<String>> {
+    let profiles = match base.get(PROFILE_SECTION).map(|v| &v.kind) {
+        Some(ValueKind::Table(profiles)) => profiles.clone(),
+        _ => Map::new(),
+    };
+
+    if let Some(name) = requested {
+        if !profiles.contains_key(name) {
+            bail!("Profile '{}' is not defined", name);
+        }
+        return Ok(Some(name.to_string()));
+    }
+
+    for (name, profile) in &profiles {
+        let ValueKind::Table(table) = &profile.kind else {
+            continue;
+        };
+        let matches = |key: &str, actual: &str| {
+            !actual.is_empty() && strings(table.get(key)).iter().any(|pattern| wildcard_match(pattern, actual))
+        };
+        if matches("hostname", &host.hostname) || matches("product", &host.product) {
+            debug!("Profile '{}' matches host {} ({})", name, host.hostname, host.product);
+            return Ok(Some(name.clone()));
+        }
+    }
+
+    Ok(None)
+}
+
+/// The overlay tables of `profile`, i.e. everything except its match keys.
+pub fn overlay(base: &Map<String, Value>, profile: &str) -> Map<String, Value> {
+    let Some(ValueKind::Table(profiles)) = base.get(PROFILE_SECTION).map(|v| &v.kind) else {
+        return Map::new();
+    };
+    let Some(ValueKind::Table(table)) = profiles.get(profile).map(|v| &v.kind) else {
+        return Map::new();
+    };
+    table
+        .iter()
+        .filter(|(_, value)| matches!(value.kind, ValueKind::Table(_)))
+        .map(|(key, value)| (key.clone(), value.clone()))
+        .collect()
+}
+
+/// Deep-merges `overlay` into `base`. Tables are merged key by key, everything else (including
+/// lists) is replaced.
+pub fn merge(base: &mut Map<String, Value>, overlay: &Map<String, Value>) {
+    for (key, value) in overlay {
+        match (base.get_mut(key), &value.kind) {
+            (
+                Some(Value {
+                    kind: ValueKind::Table(existing),
+                    ..
+                }),
+                ValueKind::Table(incoming),
+            ) => merge(existing, incoming),
+            _ => {
+                base.insert(key.clone(), value.clone());
+            }
+        }
+    }
+}
+
+/// Looks up `section` (dotted) and `key` and returns its values as strings.
+pub fn lookup(map: &Map<String, Value>, section: &str, key: &str) -> Option<Vec<String>> {
+    let mut table = map;
+    for part in section.split('.').filter(|p| !p.is_empty()) {
+        match table.get(part).map(|v| &v.kind) {
+            Some(ValueKind::Table(inner)) => table = inner,
+            _ => return None,
+        }
+    }
+    table.get(key).map(|value| strings(Some(value)))
+}
+
+pub fn has_table(map: &Map<String, Value>, section: &str) -> bool {
+    let mut table = map;
+    for part in section.split('.').filter(|p| !p.is_empty()) {
+        match table.get(part).map(|v| &v.kind) {
+            Some(ValueKind::Table(inner)) => table = inner,
+            _ => return false,
+        }
+    }
+    true
+}
+
+fn strings(value: Option<&Value>) -> Vec<String> {
+    match value.map(|v| &v.kind) {
+        Some(ValueKind::Array(items)) => items.iter().map(|item| item.to_string()).collect(),
+        Some(ValueKind::Table(_)) | None => Vec::new(),
+        Some(_) => vec![value.map(|v| v.to_string()).unwrap_or_default()],
+    }
+}
+
+fn read_map(path: &Path) -> Result<Map<String, Value>> {
+    let text = fs::read_to_string(path).context(format!("Failed to read config from {}", path.display()))?;
+    let document = IniDocument::parse(&text).context(format!("Failed to parse config from {}", path.display()))?;
+    let uri = path.display().to_string();
+    Ok(document.to_map(Some(&uri)))
+}
+
+fn read_includes(path: &Path, map: &Map<String, Value>, stack: &mut Vec<PathBuf>) -> Result<Map<String, Value>> {
+    let canonical = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+    if stack.contains(&canonical) {
+        bail!("Config include cycle at {}", path.display());
+    }
+    stack.push(canonical);
+
+    let directory = path.parent().unwrap_or(Path::new("."));
+    let mut merged = Map::new();
+    for include in strings(map.get(INCLUDE_KEY)) {
+        let include_path = directory.join(&include);
+        debug!("Including configuration from {}", include_path.display());
+        let included = read_map(&include_path)?;
+        let mut layer = read_includes(&include_path, &included, stack)?;
+        merge(&mut layer, &included);
+        merge(&mut merged, &layer);
+    }
+
+    stack.pop();
+    merged.shift_remove(INCLUDE_KEY);
+    Ok(merged)
+}
+
+fn wildcard_match(pattern: &str, text: &str) -> bool {
+    let pattern = pattern.to_lowercase();
+    let text = text.to_lowercase();
+    let parts: Vec<&str> = pattern.split('*').collect();
+    if parts.len() == 1 {
+        return pattern == text;
+    }
+
+    let mut rest = text.as_str();
+    for (i, part) in parts.iter().enumerate() {
+        if i == 0 {
+            let Some(stripped) = rest.strip_prefix(part) else {
+                return false;
+            };
+            rest = stripped;
+        } else if i == parts.len() - 1 {
+            return rest.ends_with(part);
+        } else {
+            match rest.find(part) {
+                Some(index) => rest = &rest[index + part.len()..],
+                None => return false,
+            }
+        }
+    }
+    true
+}
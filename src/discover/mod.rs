@@ -0,0 +1,418 @@
+use crate::collectors::nvidia;
+use crate::config::ResourcesConfig;
+use crate::models::sensor::{Sensor, SensorKind};
+use crate::models::AllowedResources;
+use crate::utils;
+use indexmap::IndexMap;
+use log::debug;
+use std::collections::HashMap;
+use std::fmt;
+use std::fs::{canonicalize, read_dir};
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+/// Block device prefixes that never make sense on the dashboard.
+const IGNORED_DISKS: [&str; 5] = ["loop", "ram", "zram", "sr", "fd"];
+/// File systems that are not backed by a real disk.
+const IGNORED_FILE_SYSTEMS: [&str; 6] = ["squashfs", "overlay", "tmpfs", "devtmpfs", "efivarfs", "ramfs"];
+/// Mount point prefixes hidden from the dashboard (snap images, EFI partitions).
+const IGNORED_MOUNT_PREFIXES: [&str; 3] = ["/snap", "/boot", "/var/snap"];
+
+/// Resources found on this machine.
+#[derive(Debug, Clone, Default)]
+pub struct Discovery {
+    pub disks: Vec<String>,
+    pub mount_points: Vec<String>,
+    pub networks: Vec<String>,
+    /// Every hwmon input (temperatures, fans, voltages, power and current), for reference.
+    pub hwmon: Vec<Sensor>,
+    pub gpus: Vec<String>,
+    /// Suggested `[RESOURCES.sensors]` entries: match hint -> display label.
+    pub sensors: IndexMap<String, String>,
+}
+
+impl Discovery {
+    pub fn to_resources(&self) -> ResourcesConfig {
+        ResourcesConfig {
+            disks: self.disks.clone(),
+            networks: self.networks.clone(),
+            mount_points: self.mount_points.clone(),
+            sensors: self.sensors.clone(),
+        }
+    }
+}
+
+impl fmt::Display for Discovery {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Block devices: {}", self.disks.join(", "))?;
+        writeln!(f, "Mount points:  {}", self.mount_points.join(", "))?;
+        writeln!(f, "Networks:      {}", self.networks.join(", "))?;
+        writeln!(f, "GPUs:          {}", self.gpus.join(", "))?;
+        writeln!(f, "Sensors:")?;
+        for sensor in &self.hwmon {
+            writeln!(
+                f,
+                "  {:<12} {:<7} {:<16} {:<24} {:>7.1} {}",
+                sensor.name,
+                sensor.channel(),
+                sensor.label,
+                sensor.model,
+                sensor.value,
+                sensor.unit
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Enumerates disks, mount points, network interfaces, hwmon sensors and GPUs.
+pub async fn discover() -> Discovery {
+    let start = Instant::now();
+    let mut discovery = discover_in(Path::new("/sys"));
+    discovery.mount_points = mount_points();
+
+    for gpu in nvidia::collect().await {
+        if !gpu.name.is_empty() {
+            discovery.sensors.insert(gpu.name.clone(), gpu_label(&discovery.sensors));
+            discovery.gpus.push(gpu.name);
+        }
+    }
+    for hint in drm_gpu_hints(Path::new("/sys")) {
+        let label = gpu_label(&discovery.sensors);
+        discovery.sensors.insert(hint, label);
+    }
+
+    debug!("discover took: {} ms", start.elapsed().as_millis());
+    discovery
+}
+
+/// Sysfs part of [`discover`], rooted at `sys` so it can run against a fake tree.
+pub fn discover_in(sys: &Path) -> Discovery {
+    let mut discovery = Discovery {
+        disks: block_devices(&sys.join("block")),
+        networks: network_interfaces(&sys.join("class/net")),
+        ..Discovery::default()
+    };
+
+    for entry in sorted_entries(&sys.join("class/hwmon")) {
+        utils::hwmon::from_hwmon(&mut discovery.hwmon, &sys.join("class/hwmon").join(&entry));
+    }
+    // A GPU's own hwmon chip is left to its `<driver> <card>` hint, which also brings load and VRAM
+    let gpu_devices: Vec<PathBuf> = gpu_cards(sys).into_iter().map(|(_, _, device)| device).collect();
+    let chips: Vec<Sensor> = discovery
+        .hwmon
+        .iter()
+        .filter(|sensor| chip_device(sensor).is_none_or(|device| !gpu_devices.contains(&device)))
+        .cloned()
+        .collect();
+    discovery.sensors = suggest_sensors(&chips);
+
+    for card in sorted_entries(&sys.join("class/drm")) {
+        if !card.starts_with("card") || card.contains('-') {
+            continue;
+        }
+        if let Some(driver) = card_driver(&sys.join("class/drm").join(&card)) {
+            discovery.gpus.push(format!("{} ({})", card, driver));
+        }
+    }
+
+    discovery
+}
+
+/// Sensor hints for AMD and Intel GPUs, named like the GPU collector does (e.g. `amdgpu card0`).
+pub fn drm_gpu_hints(sys: &Path) -> Vec<String> {
+    gpu_cards(sys)
+        .into_iter()
+        .map(|(card, driver, _)| format!("{} {}", driver, card))
+        .collect()
+}
+
+/// DRM cards the GPU collector reads: name, driver and resolved PCI device.
+fn gpu_cards(sys: &Path) -> Vec<(String, String, PathBuf)> {
+    let drm = sys.join("class/drm");
+    let mut cards = Vec::new();
+    for card in sorted_entries(&drm) {
+        if !card.starts_with("card") || card.contains('-') {
+            continue;
+        }
+        let driver = card_driver(&drm.join(&card));
+        let device = canonicalize(drm.join(&card).join("device"));
+        if let (Some(driver), Ok(device)) = (driver, device) {
+            if matches!(driver.as_str(), "amdgpu" | "i915" | "xe") {
+                cards.push((card, driver, device));
+            }
+        }
+    }
+    cards
+}
+
+/// Fills every empty list of `resources` with what was discovered on this machine.
+pub(crate) async fn resolve(resources: &ResourcesConfig) -> AllowedResources {
+    let auto = resources.disks.is_empty()
+        || resources.networks.is_empty()
+        || resources.mount_points.is_empty()
+        || resources.sensors.is_empty();
+    let discovery = if auto { discover().await } else { Discovery::default() };
+
+    let pick = |configured: &Vec<String>, discovered: &Vec<String>| {
+        if configured.is_empty() {
+            discovered.clone()
+        } else {
+            configured.clone()
+        }
+    };
+
+    AllowedResources {
+        disks: pick(&resources.disks, &discovery.disks),
+        networks: pick(&resources.networks, &discovery.networks),
+        mount_points: pick(&resources.mount_points, &discovery.mount_points),
+        sensors: if resources.sensors.is_empty() {
+            discovery.sensors
+        } else {
+            resources.sensors.clone()
+        },
+    }
+}
+
+/// Suggests one sensor per hwmon chip together with a display label.
+///
+/// The hint is what `collect_sensors` matches against ("name label model path", lower case).
+/// Where the short hint would match several chips (e.g. two NVMe drives) the model is added.
+pub fn suggest_sensors(sensors: &[Sensor]) -> IndexMap<String, String> {
+    let mut primary: Vec<&Sensor> = Vec::new();
+    for sensor in sensors.iter().filter(|sensor| sensor.kind == SensorKind::Temperature) {
+        match primary.iter().position(|s| s.name == sensor.name && chip_of(s) == chip_of(sensor)) {
+            Some(index) if preference(sensor) < preference(primary[index]) => primary[index] = sensor,
+            Some(_) => {}
+            None => primary.push(sensor),
+        }
+    }
+
+    let mut counters: HashMap<&'static str, usize> = HashMap::new();
+    let mut suggestions = IndexMap::new();
+    for sensor in &primary {
+        let short = normalise(&format!("{} {}", sensor.name, sensor.label));
+        let ambiguous = primary.iter().filter(|s| normalise(&format!("{} {}", s.name, s.label)) == short).count() > 1;
+        let hint = if ambiguous {
+            normalise(&format!("{} {} {}", sensor.name, sensor.label, sensor.model))
+        } else {
+            short
+        };
+
+        let prefix = label_prefix(&sensor.name);
+        let counter = counters.entry(prefix).or_insert(0);
+        let label = match prefix {
+            "CPU" if *counter == 0 => "CPU".to_string(),
+            _ => format!("{}{}", prefix, counter),
+        };
+        *counter += 1;
+
+        if !suggestions.contains_key(&hint) {
+            suggestions.insert(hint, label);
+        }
+    }
+    suggestions
+}
+
+/// Kernel driver of a DRM card, e.g. `amdgpu`.
+fn card_driver(card_dir: &Path) -> Option<String> {
+    let driver = card_dir.join("device/driver").read_link().ok()?;
+    Some(driver.file_name()?.to_string_lossy().to_string())
+}
+
+fn gpu_label(sensors: &IndexMap<String, String>) -> String {
+    let count = sensors.values().filter(|label| label.starts_with("GPU")).count();
+    format!("GPU{}", count)
+}
+
+fn label_prefix(chip: &str) -> &'static str {
+    match chip {
+        "k10temp" | "coretemp" | "zenpower" | "cpu_thermal" | "acpitz" => "CPU",
+        "amdgpu" | "nouveau" | "radeon" | "i915" | "xe" => "GPU",
+        "nvme" => "NVMe",
+        "drivetemp" => "Disk",
+        "iwlwifi" | "iwlwifi_1" | "mt7921_phy0" | "ath11k_hwmon" => "WiFi",
+        chip if chip.starts_with("r8") || chip.contains("igc") || chip.contains("e1000") || chip.contains("ixgbe") => "Eth",
+        _ => "Temp",
+    }
+}
+
+/// Lower is better: prefer the label that best represents the chip.
+fn preference(sensor: &Sensor) -> usize {
+    const PREFERRED: [&str; 5] = ["tctl", "package id 0", "composite", "edge", "tdie"];
+    let label = sensor.label.to_lowercase();
+    PREFERRED.iter().position(|p| *p == label).unwrap_or(PREFERRED.len() + sensor.id as usize)
+}
+
+/// The hwmon directory a sensor input belongs to.
+fn chip_of(sensor: &Sensor) -> &str {
+    Path::new(&sensor.path)
+        .parent()
+        .and_then(|p| p.to_str())
+        .unwrap_or("")
+}
+
+/// Where the chip's `device` link points, e.g. the PCI device of a GPU.
+fn chip_device(sensor: &Sensor) -> Option<PathBuf> {
+    canonicalize(Path::new(chip_of(sensor)).join("device")).ok()
+}
+
+fn normalise(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ").to_lowercase()
+}
+
+fn block_devices(block: &Path) -> Vec<String> {
+    sorted_entries(block)
+        .into_iter()
+        .filter(|name| !IGNORED_DISKS.iter().any(|prefix| name.starts_with(prefix)))
+        .filter(|name| {
+            utils::hwmon::get_file_line(&block.join(name).join("size"), 16)
+                .and_then(|size| size.parse::<u64>().ok())
+                .is_some_and(|size| size > 0)
+        })
+        .collect()
+}
+
+fn network_interfaces(net: &Path) -> Vec<String> {
+    sorted_entries(net)
+        .into_iter()
+        .filter(|name| name != "lo" && net.join(name).join("device").exists())
+        .collect()
+}
+
+fn mount_points() -> Vec<String> {
+    let disks = sysinfo::Disks::new_with_refreshed_list();
+    let mut mount_points: Vec<String> = Vec::new();
+    for disk in &disks {
+        let file_system = disk.file_system().to_string_lossy();
+        let Some(mount_point) = disk.mount_point().to_str() else {
+            continue;
+        };
+        if IGNORED_FILE_SYSTEMS.contains(&file_system.as_ref())
+            || IGNORED_MOUNT_PREFIXES.iter().any(|prefix| mount_point.starts_with(prefix))
+            || mount_points.iter().any(|m| m == mount_point)
+        {
+            continue;
+        }
+        mount_points.push(mount_point.to_string());
+    }
+    mount_points
+}
+
+fn sorted_entries(dir: &Path) -> Vec<String> {
+    let mut names: Vec<String> = read_dir(dir)
+        .map(|entries| {
+            entries
+                .flatten()
+                .map(|entry| entry.file_name().to_string_lossy().to_string())
+                .collect()
+        })
+        .unwrap_or_default();
+    names.sort();
+    names
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::testing::write;
+    use std::fs;
+    use std::os::unix::fs::symlink;
+
+    fn sensor(id: u32, hwmon: &str, name: &str, label: &str, model: &str) -> Sensor {
+        Sensor {
+            id,
+            path: format!("/sys/class/hwmon/{}/temp{}_input", hwmon, id),
+            name: name.to_string(),
+            label: label.to_string(),
+            model: model.to_string(),
+            value: 40.0,
+            ..Sensor::default()
+        }
+    }
+
+    #[test]
+    fn test_suggest_sensors() {
+        let sensors = vec![
+            sensor(3, "hwmon1", "k10temp", "Tccd1", ""),
+            sensor(1, "hwmon1", "k10temp", "Tctl", ""),
+            sensor(1, "hwmon2", "nvme", "Composite", "Samsung SSD 980 PRO 1TB"),
+            sensor(2, "hwmon2", "nvme", "Sensor 1", "Samsung SSD 980 PRO 1TB"),
+            sensor(1, "hwmon3", "nvme", "Composite", "WD Blue SN570"),
+            sensor(1, "hwmon4", "amdgpu", "edge", ""),
+            sensor(1, "hwmon5", "r8169_0_d00:00", "", ""),
+        ];
+
+        let suggestions = suggest_sensors(&sensors);
+        let expected: Vec<(&str, &str)> = vec![
+            ("k10temp tctl", "CPU"),
+            ("nvme composite samsung ssd 980 pro 1tb", "NVMe0"),
+            ("nvme composite wd blue sn570", "NVMe1"),
+            ("amdgpu edge", "GPU0"),
+            ("r8169_0_d00:00", "Eth0"),
+        ];
+        let actual: Vec<(&str, &str)> = suggestions.iter().map(|(k, v)| (k.as_str(), v.as_str())).collect();
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_discover_in() {
+        let root = tempfile::tempdir().unwrap();
+        let sys = root.path();
+        write(sys, "block/sda/size", "1000215216");
+        write(sys, "block/sdb/size", "0");
+        write(sys, "block/md0/size", "2000000");
+        write(sys, "block/loop0/size", "1024");
+        write(sys, "class/net/lo/mtu", "65536");
+        write(sys, "class/net/eth0/device/vendor", "0x10ec");
+        write(sys, "class/net/docker0/mtu", "1500");
+        write(sys, "class/hwmon/hwmon0/name", "k10temp");
+        write(sys, "class/hwmon/hwmon0/temp1_input", "45000");
+        write(sys, "class/hwmon/hwmon0/temp1_label", "Tctl");
+        write(sys, "class/hwmon/hwmon1/name", "nvme");
+        write(sys, "class/hwmon/hwmon1/temp1_input", "38000");
+        write(sys, "class/hwmon/hwmon1/temp1_label", "Composite");
+        write(sys, "class/hwmon/hwmon1/device/model", "WD Blue SN570");
+        write(sys, "bus/pci/drivers/amdgpu/bind", "");
+        write(sys, "class/drm/card0-DP-1/status", "connected");
+        fs::create_dir_all(sys.join("class/drm/card0/device")).unwrap();
+        symlink(sys.join("bus/pci/drivers/amdgpu"), sys.join("class/drm/card0/device/driver")).unwrap();
+
+        let discovery = discover_in(sys);
+        assert_eq!(discovery.disks, vec!["md0", "sda"]);
+        assert_eq!(discovery.networks, vec!["eth0"]);
+        assert_eq!(discovery.hwmon.len(), 2);
+        let sensors: Vec<(&str, &str)> = discovery.sensors.iter().map(|(k, v)| (k.as_str(), v.as_str())).collect();
+        assert_eq!(sensors, vec![("k10temp tctl", "CPU"), ("nvme composite", "NVMe0")]);
+        assert_eq!(discovery.gpus, vec!["card0 (amdgpu)"]);
+    }
+
+    #[test]
+    fn test_drm_gpu_hints() {
+        let root = tempfile::tempdir().unwrap();
+        let sys = root.path();
+        // card0 and hwmon0 are the same AMD card; card1 is an Intel GPU without an hwmon chip
+        let cards = [("card0", "0000:03:00.0", "amdgpu"), ("card1", "0000:00:02.0", "i915")];
+        for (card, pci, driver) in cards {
+            let device = sys.join("devices/pci0000:00").join(pci);
+            write(sys, &format!("bus/pci/drivers/{}/bind", driver), "");
+            fs::create_dir_all(&device).unwrap();
+            symlink(sys.join("bus/pci/drivers").join(driver), device.join("driver")).unwrap();
+            fs::create_dir_all(sys.join("class/drm").join(card)).unwrap();
+            symlink(&device, sys.join("class/drm").join(card).join("device")).unwrap();
+        }
+        write(sys, "class/hwmon/hwmon0/name", "amdgpu");
+        write(sys, "class/hwmon/hwmon0/temp1_input", "51000");
+        write(sys, "class/hwmon/hwmon0/temp1_label", "edge");
+        symlink(sys.join("devices/pci0000:00/0000:03:00.0"), sys.join("class/hwmon/hwmon0/device")).unwrap();
+
+        write(sys, "class/hwmon/hwmon1/name", "k10temp");
+        write(sys, "class/hwmon/hwmon1/temp1_input", "45000");
+        write(sys, "class/hwmon/hwmon1/temp1_label", "Tctl");
+
+        // The AMD card is suggested as a GPU, with its load and VRAM, rather than as a bare chip
+        let discovery = discover_in(sys);
+        assert_eq!(discovery.sensors.keys().collect::<Vec<_>>(), vec!["k10temp tctl"]);
+        assert_eq!(drm_gpu_hints(sys), vec!["amdgpu card0", "i915 card1"]);
+    }
+}
@@ -0,0 +1,465 @@
+use crate::exporter::DeviceStatus;
+use crate::models::container::ContainerInfo;
+use crate::models::disk::MountInfo;
+use crate::models::gpu::GpuInfo;
+use crate::models::sensor::SensorKind;
+use crate::models::system::ProcessInfo;
+use crate::models::SystemInfo;
+use std::fmt::Write;
+use std::time::Duration;
+
+const PREFIX: &str = "ax206lcd_";
+
+/// Name, help and value of a gauge family with one sample per mount, GPU, ...
+type Family<T> = (&'static str, &'static str, fn(&T) -> f64);
+
+/// Builds a page in the Prometheus text exposition format (version 0.0.4).
+#[derive(Debug, Default)]
+pub struct Exposition {
+    text: String,
+}
+
+impl Exposition {
+    /// Starts a metric family; its samples follow with [`Exposition::sample`].
+    pub fn family(&mut self, name: &str, kind: &str, help: &str) {
+        let _ = writeln!(self.text, "# HELP {}{} {}", PREFIX, name, help);
+        let _ = writeln!(self.text, "# TYPE {}{} {}", PREFIX, name, kind);
+    }
+
+    pub fn sample(&mut self, name: &str, labels: &[(&str, &str)], value: f64) {
+        let _ = write!(self.text, "{}{}", PREFIX, name);
+        if !labels.is_empty() {
+            let labels: Vec<String> =
+                labels.iter().map(|(label, value)| format!("{}=\"{}\"", label, escape(value))).collect();
+            let _ = write!(self.text, "{{{}}}", labels.join(","));
+        }
+        let _ = writeln!(self.text, " {}", number(value));
+    }
+
+    /// A family with one unlabelled gauge.
+    pub fn gauge(&mut self, name: &str, help: &str, value: f64) {
+        self.family(name, "gauge", help);
+        self.sample(name, &[], value);
+    }
+
+    pub fn into_text(self) -> String {
+        self.text
+    }
+}
+
+/// Label values escape backslashes, double quotes and line feeds.
+fn escape(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
+
+fn number(value: f64) -> String {
+    if value.is_nan() {
+        "NaN".to_string()
+    } else if value.is_infinite() {
+        if value > 0.0 { "+Inf" } else { "-Inf" }.to_string()
+    } else {
+        value.to_string()
+    }
+}
+
+fn bool_value(value: bool) -> f64 {
+    if value {
+        1.0
+    } else {
+        0.0
+    }
+}
+
+/// Every collected value, then collector timings and the device status.
+pub fn render(
+    info: Option<&SystemInfo>,
+    timings: &[(&'static str, Duration)],
+    collect: Option<Duration>,
+    device: &DeviceStatus,
+) -> String {
+    let mut page = Exposition::default();
+    if let Some(info) = info {
+        system(&mut page, info);
+        memory(&mut page, info);
+        disks(&mut page, info);
+        network(&mut page, info);
+        sensors(&mut page, info);
+        gpus(&mut page, info);
+        power(&mut page, info);
+        services(&mut page, info);
+        containers(&mut page, info);
+        processes(&mut page, info);
+        custom(&mut page, info);
+    }
+
+    page.family("collector_duration_seconds", "gauge", "Time the collector's last run took.");
+    for (name, duration) in timings {
+        page.sample("collector_duration_seconds", &[("collector", name)], duration.as_secs_f64());
+    }
+    if let Some(collect) = collect {
+        page.gauge(
+            "collect_duration_seconds",
+            "Time the last collection of all collectors took.",
+            collect.as_secs_f64(),
+        );
+    }
+
+    page.gauge("device_connected", "Whether the LCD is connected and accepting images.", bool_value(device.connected));
+    if let Some(upload) = device.upload {
+        page.gauge("device_upload_seconds", "Time the last image upload to the LCD took.", upload.as_secs_f64());
+    }
+    page.family("device_upload_failures_total", "counter", "Image uploads to the LCD that failed.");
+    page.sample("device_upload_failures_total", &[], device.upload_failures as f64);
+    page.into_text()
+}
+
+fn system(page: &mut Exposition, info: &SystemInfo) {
+    let cpu = &info.cpu;
+    page.gauge("cpu_usage_ratio", "Share of CPU time busy, 0-1.", cpu.percent as f64);
+    page.family("cpu_mode_ratio", "gauge", "Share of CPU time per mode, 0-1.");
+    for (mode, value) in [("user", cpu.user), ("system", cpu.system), ("iowait", cpu.iowait), ("steal", cpu.steal)] {
+        page.sample("cpu_mode_ratio", &[("mode", mode)], value as f64);
+    }
+    page.family("cpu_core_usage_ratio", "gauge", "Share of time each logical core was busy, 0-1.");
+    for core in &cpu.cores {
+        page.sample("cpu_core_usage_ratio", &[("core", &core.id.to_string())], core.percent as f64);
+    }
+    page.gauge("cpu_frequency_megahertz", "Average current CPU frequency.", cpu.freq as f64);
+    page.gauge("cpu_temperature_celsius", "CPU package temperature.", cpu.cpu_temp as f64);
+
+    let system = &info.system;
+    page.gauge("uptime_seconds", "Time since boot.", system.uptime as f64);
+    page.family("load_average", "gauge", "Run queue length averaged over 1, 5 and 15 minutes.");
+    let (load1, load5, load15) = system.load_avg;
+    for (window, value) in [("1m", load1), ("5m", load5), ("15m", load15)] {
+        page.sample("load_average", &[("window", window)], value as f64);
+    }
+
+    let pressure = &info.pressure;
+    page.family("pressure_some_ratio", "gauge", "Share of the last 10 s some tasks stalled on a resource, 0-1.");
+    let mut resources = vec![("", &pressure.system)];
+    resources.extend(pressure.cgroups.iter().map(|cgroup| (cgroup.path.as_str(), &cgroup.pressure)));
+    for (cgroup, resource) in resources {
+        for (name, value) in [("cpu", &resource.cpu), ("memory", &resource.memory), ("io", &resource.io)] {
+            if let Some(value) = value {
+                let labels = [("resource", name), ("cgroup", cgroup)];
+                page.sample("pressure_some_ratio", &labels, value.some.avg10 as f64 / 100.0);
+            }
+        }
+    }
+}
+
+fn memory(page: &mut Exposition, info: &SystemInfo) {
+    let memory = &info.memory;
+    page.family("memory_bytes", "gauge", "Physical memory by state.");
+    for (state, value) in [
+        ("total", memory.total),
+        ("used", memory.used),
+        ("available", memory.available),
+        ("free", memory.free),
+        ("cache", memory.cache),
+        ("buffers", memory.buffers),
+        ("shared", memory.shared),
+        ("slab", memory.slab),
+        ("dirty", memory.dirty),
+        ("writeback", memory.writeback),
+    ] {
+        page.sample("memory_bytes", &[("state", state)], value as f64);
+    }
+    page.family("swap_bytes", "gauge", "Swap space by state.");
+    page.sample("swap_bytes", &[("state", "total")], info.swap_memory.total as f64);
+    page.sample("swap_bytes", &[("state", "used")], info.swap_memory.used as f64);
+}
+
+fn disks(page: &mut Exposition, info: &SystemInfo) {
+    let families: [Family<MountInfo>; 5] = [
+        ("filesystem_size_bytes", "Filesystem size.", |mount| mount.total as f64),
+        ("filesystem_used_bytes", "Filesystem space used.", |mount| mount.used as f64),
+        ("filesystem_inodes", "Filesystem inodes, 0 when not fixed.", |mount| mount.inodes_total as f64),
+        ("filesystem_inodes_used", "Filesystem inodes in use.", |mount| mount.inodes_used as f64),
+        ("filesystem_readonly", "Whether the filesystem is mounted read-only.", |mount| bool_value(mount.read_only)),
+    ];
+    for (name, help, value) in families {
+        page.family(name, "gauge", help);
+        for mount in &info.disk.mounts {
+            let labels = [("mountpoint", &mount.mount_point), ("device", &mount.device), ("fstype", &mount.fs_type)];
+            page.sample(name, &labels.map(|(label, value)| (label, value.as_str())), value(mount));
+        }
+    }
+
+    let devices = &info.disk_io.devices;
+    page.family("disk_bytes_per_second", "gauge", "Block device throughput.");
+    for device in devices {
+        page.sample("disk_bytes_per_second", &[("device", &device.name), ("direction", "read")], device.read as f64);
+        page.sample("disk_bytes_per_second", &[("device", &device.name), ("direction", "write")], device.write as f64);
+    }
+    page.family("disk_operations_per_second", "gauge", "Block device operations completed.");
+    for device in devices {
+        let (name, read, write) = (&device.name, device.read_ops as f64, device.write_ops as f64);
+        page.sample("disk_operations_per_second", &[("device", name), ("direction", "read")], read);
+        page.sample("disk_operations_per_second", &[("device", name), ("direction", "write")], write);
+    }
+    page.family("disk_busy_ratio", "gauge", "Share of time the block device had I/O in flight, 0-1.");
+    for device in devices {
+        page.sample("disk_busy_ratio", &[("device", &device.name)], device.busy as f64);
+    }
+
+    page.family("raid_degraded", "gauge", "Whether the md RAID array is inactive, missing or failing members.");
+    for array in &info.disk.raid {
+        page.sample("raid_degraded", &[("array", &array.name), ("level", &array.level)], bool_value(array.degraded()));
+    }
+    page.family("raid_sync_ratio", "gauge", "Progress of a running recovery, resync, check or reshape, 0-1.");
+    for array in &info.disk.raid {
+        if let (Some(action), Some(progress)) = (&array.action, array.progress) {
+            page.sample("raid_sync_ratio", &[("array", &array.name), ("action", action)], progress as f64);
+        }
+    }
+    if let Some(zfs) = &info.disk.zfs {
+        page.family("zfs_pool_online", "gauge", "Whether the ZFS pool's health is ONLINE.");
+        for pool in &zfs.pools {
+            page.sample(
+                "zfs_pool_online",
+                &[("pool", &pool.name), ("health", &pool.health)],
+                bool_value(pool.online()),
+            );
+        }
+        page.gauge("zfs_arc_size_bytes", "Bytes cached in the ZFS ARC.", zfs.arc_size as f64);
+        page.gauge("zfs_arc_max_bytes", "Target maximum size of the ZFS ARC.", zfs.arc_max as f64);
+        page.gauge(
+            "zfs_arc_hit_ratio",
+            "Share of ARC lookups that hit since the module loaded.",
+            zfs.arc_hit_ratio as f64,
+        );
+    }
+}
+
+fn network(page: &mut Exposition, info: &SystemInfo) {
+    let interfaces = &info.network.interfaces;
+    page.family("network_bytes_per_second", "gauge", "Network interface throughput.");
+    for interface in interfaces {
+        let name = interface.name.as_str();
+        page.sample(
+            "network_bytes_per_second",
+            &[("interface", name), ("direction", "receive")],
+            interface.recv as f64,
+        );
+        page.sample(
+            "network_bytes_per_second",
+            &[("interface", name), ("direction", "transmit")],
+            interface.sent as f64,
+        );
+    }
+    page.family("network_packets_per_second", "gauge", "Network interface packets.");
+    for interface in interfaces {
+        let (name, recv, sent) = (&interface.name, interface.recv_packets as f64, interface.sent_packets as f64);
+        page.sample("network_packets_per_second", &[("interface", name), ("direction", "receive")], recv);
+        page.sample("network_packets_per_second", &[("interface", name), ("direction", "transmit")], sent);
+    }
+    page.family("network_errors_total", "counter", "Network interface errors and drops since the interface came up.");
+    for interface in interfaces {
+        let name = interface.name.as_str();
+        for (direction, kind, value) in [
+            ("receive", "error", interface.recv_errors),
+            ("transmit", "error", interface.sent_errors),
+            ("receive", "drop", interface.recv_drops),
+            ("transmit", "drop", interface.sent_drops),
+        ] {
+            page.sample(
+                "network_errors_total",
+                &[("interface", name), ("direction", direction), ("kind", kind)],
+                value as f64,
+            );
+        }
+    }
+    page.family("network_up", "gauge", "Whether the interface's operational state is up.");
+    for interface in interfaces {
+        page.sample("network_up", &[("interface", &interface.name)], bool_value(interface.operstate == "up"));
+    }
+    page.family("network_speed_megabits", "gauge", "Negotiated link speed.");
+    for interface in interfaces {
+        if let Some(speed) = interface.speed {
+            page.sample("network_speed_megabits", &[("interface", &interface.name)], speed as f64);
+        }
+    }
+
+    let tcp = &info.network.tcp;
+    page.family("tcp_connections", "gauge", "TCP sockets by state.");
+    for (state, value) in [
+        ("established", tcp.established),
+        ("syn_sent", tcp.syn_sent),
+        ("syn_recv", tcp.syn_recv),
+        ("fin_wait1", tcp.fin_wait1),
+        ("fin_wait2", tcp.fin_wait2),
+        ("time_wait", tcp.time_wait),
+        ("close", tcp.close),
+        ("close_wait", tcp.close_wait),
+        ("last_ack", tcp.last_ack),
+        ("listen", tcp.listen),
+        ("closing", tcp.closing),
+    ] {
+        page.sample("tcp_connections", &[("state", state)], value as f64);
+    }
+}
+
+fn sensors(page: &mut Exposition, info: &SystemInfo) {
+    let readings = &info.system.sensors.readings;
+    for (kind, name, help) in [
+        (SensorKind::Temperature, "sensor_celsius", "hwmon temperature."),
+        (SensorKind::Fan, "sensor_rpm", "hwmon fan speed."),
+        (SensorKind::Voltage, "sensor_volts", "hwmon voltage."),
+        (SensorKind::Power, "sensor_watts", "hwmon power."),
+        (SensorKind::Current, "sensor_amperes", "hwmon current."),
+    ] {
+        page.family(name, "gauge", help);
+        for (hint, reading) in readings.iter().filter(|(_, reading)| reading.kind == kind) {
+            page.sample(name, &[("sensor", hint), ("label", &reading.label)], reading.value as f64);
+        }
+    }
+}
+
+fn gpus(page: &mut Exposition, info: &SystemInfo) {
+    let families: [Family<GpuInfo>; 8] = [
+        ("gpu_usage_ratio", "Share of time the GPU was busy, 0-1.", |gpu| gpu.load as f64),
+        ("gpu_temperature_celsius", "GPU temperature.", |gpu| gpu.temperature as f64),
+        ("gpu_memory_used_bytes", "Dedicated GPU memory in use.", |gpu| gpu.memory_used as f64),
+        ("gpu_memory_total_bytes", "Dedicated GPU memory.", |gpu| gpu.memory_total as f64),
+        ("gpu_power_watts", "GPU power draw.", |gpu| gpu.power as f64),
+        ("gpu_fan_ratio", "GPU fan speed, 0-1.", |gpu| gpu.fan as f64),
+        ("gpu_clock_megahertz", "GPU core clock.", |gpu| gpu.clock as f64),
+        ("gpu_memory_clock_megahertz", "GPU memory clock.", |gpu| gpu.memory_clock as f64),
+    ];
+    for (name, help, value) in families {
+        page.family(name, "gauge", help);
+        for gpu in &info.gpus {
+            page.sample(name, &[("gpu", &gpu.name)], value(gpu));
+        }
+    }
+}
+
+fn power(page: &mut Exposition, info: &SystemInfo) {
+    let power = &info.power;
+    page.family("battery_capacity_ratio", "gauge", "Battery charge level, 0-1.");
+    for battery in &power.batteries {
+        let labels = [("battery", battery.name.as_str()), ("status", battery.status.as_str())];
+        page.sample("battery_capacity_ratio", &labels, battery.capacity as f64);
+    }
+    page.family("battery_power_watts", "gauge", "Power flowing into or out of the battery.");
+    for battery in &power.batteries {
+        page.sample("battery_power_watts", &[("battery", &battery.name)], battery.power as f64);
+    }
+    page.family(
+        "battery_time_remaining_seconds",
+        "gauge",
+        "Time until empty while discharging, or full while charging.",
+    );
+    for battery in &power.batteries {
+        if let Some(seconds) = battery.time_remaining {
+            page.sample("battery_time_remaining_seconds", &[("battery", &battery.name)], seconds as f64);
+        }
+    }
+    if let Some(online) = power.ac_online {
+        page.gauge("ac_online", "Whether a mains adapter is plugged in.", bool_value(online));
+    }
+    page.family("rapl_power_watts", "gauge", "RAPL package and DRAM power.");
+    for domain in &power.rapl {
+        page.sample("rapl_power_watts", &[("domain", &domain.name)], domain.power as f64);
+    }
+}
+
+fn services(page: &mut Exposition, info: &SystemInfo) {
+    page.family("probe_up", "gauge", "Whether the probe's last check succeeded.");
+    // Probes that have not finished their first check yet are left out
+    for probe in info.probes.iter().filter(|probe| !probe.history.is_empty()) {
+        page.sample("probe_up", &[("probe", &probe.name), ("target", &probe.target)], bool_value(probe.up));
+    }
+    page.family("probe_latency_seconds", "gauge", "Latency of the probe's last successful check.");
+    for probe in &info.probes {
+        if let Some(latency) = probe.latency {
+            let labels = [("probe", probe.name.as_str()), ("target", probe.target.as_str())];
+            page.sample("probe_latency_seconds", &labels, latency as f64 / 1000.0);
+        }
+    }
+
+    if let Some(units) = &info.units {
+        page.family("systemd_unit_failed", "gauge", "Whether the failed or watched systemd unit has failed.");
+        for unit in units {
+            let labels = [("unit", unit.name.as_str()), ("state", unit.sub_state.as_str())];
+            page.sample("systemd_unit_failed", &labels, bool_value(unit.failed()));
+        }
+        page.family("systemd_unit_restarts", "gauge", "Automatic restarts of the systemd unit.");
+        for unit in units {
+            page.sample("systemd_unit_restarts", &[("unit", &unit.name)], unit.restarts as f64);
+        }
+    }
+}
+
+/// Containers and slices are told apart by their cgroup path, as nested slices can share a name.
+fn container_labels(container: &ContainerInfo) -> [(&'static str, &str); 3] {
+    [("path", &container.path), ("name", &container.name), ("kind", container.kind.as_str())]
+}
+
+fn containers(page: &mut Exposition, info: &SystemInfo) {
+    let families: [Family<ContainerInfo>; 3] = [
+        ("container_cpu_cores", "CPU used by the container or slice, in cores.", |c| c.cpu_percent as f64 / 100.0),
+        ("container_memory_bytes", "Memory charged to the container or slice.", |c| c.memory as f64),
+        ("container_pids", "Tasks in the container or slice.", |c| c.pids as f64),
+    ];
+    for (name, help, value) in families {
+        page.family(name, "gauge", help);
+        for container in &info.containers {
+            page.sample(name, &container_labels(container), value(container));
+        }
+    }
+    page.family("container_io_bytes_per_second", "gauge", "Storage I/O of the container or slice.");
+    for container in &info.containers {
+        for (direction, value) in [("read", container.read_rate), ("write", container.write_rate)] {
+            let [path, name, kind] = container_labels(container);
+            page.sample("container_io_bytes_per_second", &[path, name, kind, ("direction", direction)], value as f64);
+        }
+    }
+}
+
+/// The processes shown in any ranking, each once.
+fn processes(page: &mut Exposition, info: &SystemInfo) {
+    let mut processes: Vec<&ProcessInfo> = Vec::new();
+    let rankings = [
+        &info.top_cpu_processes,
+        &info.top_memory_processes,
+        &info.top_io_processes,
+        &info.top_connection_processes,
+    ];
+    for process in rankings.into_iter().flatten() {
+        if !processes.iter().any(|p| p.pid == process.pid && p.name == process.name) {
+            processes.push(process);
+        }
+    }
+
+    let families: [Family<ProcessInfo>; 3] = [
+        ("process_cpu_cores", "CPU used by the process, in cores.", |p| p.cpu_percent as f64 / 100.0),
+        ("process_resident_memory_bytes", "Resident memory of the process.", |p| p.rss as f64),
+        ("process_connections", "Open TCP connections of the process.", |p| p.connections as f64),
+    ];
+    for (name, help, value) in families {
+        page.family(name, "gauge", help);
+        for process in &processes {
+            page.sample(name, &[("pid", &process.pid.to_string()), ("name", &process.name)], value(process));
+        }
+    }
+    page.family("process_io_bytes_per_second", "gauge", "Storage I/O of the process.");
+    for process in &processes {
+        let pid = process.pid.to_string();
+        for (direction, value) in [("read", process.read_rate), ("write", process.write_rate)] {
+            let labels = [("pid", pid.as_str()), ("name", process.name.as_str()), ("direction", direction)];
+            page.sample("process_io_bytes_per_second", &labels, value as f64);
+        }
+    }
+}
+
+fn custom(page: &mut Exposition, info: &SystemInfo) {
+    page.family("custom_metric", "gauge", "Latest scaled value of a [CUSTOM.<name>] metric.");
+    for (name, metric) in &info.custom {
+        if let Some(value) = metric.value {
+            page.sample("custom_metric", &[("metric", name), ("label", &metric.label)], value);
+        }
+    }
+}
@@ -0,0 +1,217 @@
+mod exposition;
+
+use crate::models::SystemInfo;
+use log::{debug, warn};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::time::timeout;
+
+/// How long a client may take to send its request.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// The LCD as seen by the main loop.
+#[derive(Debug, Clone, Default)]
+pub struct DeviceStatus {
+    pub connected: bool,
+    /// Time the last backlight and image upload took.
+    pub upload: Option<Duration>,
+    pub upload_failures: u64,
+}
+
+#[derive(Debug, Default)]
+struct Snapshot {
+    info: Option<SystemInfo>,
+    timings: Vec<(&'static str, Duration)>,
+    collect: Option<Duration>,
+    device: DeviceStatus,
+}
+
+/// Latest values, shared between the main loop and the `/metrics` endpoint.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Metrics {
+    snapshot: Arc<Mutex<Snapshot>>,
+}
+
+impl Metrics {
+    /// Keeps a collection and how long each collector took, until the next one.
+    pub fn update(&self, info: &SystemInfo, timings: Vec<(&'static str, Duration)>, collect: Duration) {
+        let mut snapshot = self.snapshot.lock().unwrap();
+        snapshot.info = Some(info.clone());
+        snapshot.timings = timings;
+        snapshot.collect = Some(collect);
+    }
+
+    pub fn uploaded(&self, duration: Duration) {
+        let mut snapshot = self.snapshot.lock().unwrap();
+        snapshot.device.connected = true;
+        snapshot.device.upload = Some(duration);
+    }
+
+    pub fn upload_failed(&self) {
+        let mut snapshot = self.snapshot.lock().unwrap();
+        snapshot.device.connected = false;
+        snapshot.device.upload_failures += 1;
+    }
+
+    pub fn disconnected(&self) {
+        self.snapshot.lock().unwrap().device.connected = false;
+    }
+
+    /// The page served on `/metrics`.
+    pub fn render(&self) -> String {
+        let snapshot = self.snapshot.lock().unwrap();
+        exposition::render(snapshot.info.as_ref(), &snapshot.timings, snapshot.collect, &snapshot.device)
+    }
+}
+
+/// Answers `GET /metrics` on `listener` until the process exits.
+pub async fn serve(listener: TcpListener, metrics: Metrics) {
+    loop {
+        let stream = match listener.accept().await {
+            Ok((stream, _)) => stream,
+            Err(e) => {
+                warn!("Error accepting metrics connection: {}", e);
+                continue;
+            }
+        };
+        let metrics = metrics.clone();
+        tokio::spawn(async move {
+            if let Err(e) = respond(stream, &metrics).await {
+                debug!("Error answering metrics request: {}", e);
+            }
+        });
+    }
+}
+
+async fn respond(mut stream: TcpStream, metrics: &Metrics) -> std::io::Result<()> {
+    // Only the request line matters; the headers are read and ignored
+    let mut request = Vec::new();
+    let mut buffer = [0; 1024];
+    while !request.windows(4).any(|window| window == b"\r\n\r\n") && request.len() < 8192 {
+        let read = timeout(REQUEST_TIMEOUT, stream.read(&mut buffer)).await??;
+        if read == 0 {
+            break;
+        }
+        request.extend_from_slice(&buffer[..read]);
+    }
+
+    let request = String::from_utf8_lossy(&request);
+    let mut request_line = request.split_whitespace();
+    let (method, target) = (request_line.next(), request_line.next().unwrap_or(""));
+    let path = target.split('?').next().unwrap_or("");
+    let (status, content_type, body) = match (method, path) {
+        (Some("GET"), "/metrics") => ("200 OK", "text/plain; version=0.0.4; charset=utf-8", metrics.render()),
+        (Some("GET"), "/") => ("200 OK", "text/plain", "Metrics are at /metrics\n".to_string()),
+        (Some("GET"), _) => ("404 Not Found", "text/plain", "Not found\n".to_string()),
+        _ => ("405 Method Not Allowed", "text/plain", "Only GET is supported\n".to_string()),
+    };
+
+    let response = format!(
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        content_type,
+        body.len(),
+        body
+    );
+    stream.write_all(response.as_bytes()).await?;
+    stream.shutdown().await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::container::ContainerInfo;
+    use crate::models::custom::CustomMetric;
+    use crate::models::gpu::GpuInfo;
+    use crate::models::network::InterfaceInfo;
+    use crate::models::sensor::SensorKind;
+    use crate::models::system::{ProcessInfo, SystemComponent};
+
+    async fn get(address: std::net::SocketAddr, path: &str) -> String {
+        let mut stream = TcpStream::connect(address).await.unwrap();
+        let request = format!("GET {} HTTP/1.1\r\nHost: localhost\r\n\r\n", path);
+        stream.write_all(request.as_bytes()).await.unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await.unwrap();
+        response
+    }
+
+    #[test]
+    fn test_render() {
+        let mut info = SystemInfo::default();
+        info.cpu.percent = 0.25;
+        info.memory.total = 8_000_000_000;
+        info.network.interfaces.push(InterfaceInfo {
+            name: "eth0".to_string(),
+            recv: 1500,
+            recv_drops: 3,
+            operstate: "up".to_string(),
+            ..InterfaceInfo::default()
+        });
+        info.system.sensors.readings.insert(
+            "nvme".to_string(),
+            SystemComponent { label: "SSD \"hot\"".to_string(), kind: SensorKind::Temperature, value: 41.5 },
+        );
+        info.gpus.push(GpuInfo { name: "amdgpu card0".to_string(), load: 0.5, ..GpuInfo::default() });
+        // Nested slices with the same name
+        for path in ["user.slice", "user.slice/user-1000.slice/user@1000.service/user.slice"] {
+            info.containers.push(ContainerInfo {
+                path: path.to_string(),
+                name: "user.slice".to_string(),
+                pids: 4,
+                ..ContainerInfo::default()
+            });
+        }
+        let process = ProcessInfo { pid: 42, name: "postgres".to_string(), rss: 1024, ..ProcessInfo::default() };
+        info.top_cpu_processes.push(process.clone());
+        info.top_memory_processes.push(process);
+        let queue = CustomMetric { label: "Queue".to_string(), value: Some(42.0), ..CustomMetric::default() };
+        info.custom.insert("orders".to_string(), queue);
+
+        let metrics = Metrics::default();
+        metrics.update(&info, vec![("cpu", Duration::from_millis(12))], Duration::from_millis(40));
+        metrics.uploaded(Duration::from_millis(180));
+        metrics.upload_failed();
+
+        let page = metrics.render();
+        for line in [
+            "# TYPE ax206lcd_cpu_usage_ratio gauge",
+            "ax206lcd_cpu_usage_ratio 0.25",
+            "ax206lcd_memory_bytes{state=\"total\"} 8000000000",
+            "ax206lcd_network_bytes_per_second{interface=\"eth0\",direction=\"receive\"} 1500",
+            "# TYPE ax206lcd_network_errors_total counter",
+            "ax206lcd_network_errors_total{interface=\"eth0\",direction=\"receive\",kind=\"drop\"} 3",
+            "ax206lcd_network_up{interface=\"eth0\"} 1",
+            "ax206lcd_sensor_celsius{sensor=\"nvme\",label=\"SSD \\\"hot\\\"\"} 41.5",
+            "ax206lcd_gpu_usage_ratio{gpu=\"amdgpu card0\"} 0.5",
+            "ax206lcd_container_pids{path=\"user.slice\",name=\"user.slice\",kind=\"slice\"} 4",
+            "ax206lcd_container_pids{path=\"user.slice/user-1000.slice/user@1000.service/user.slice\",name=\"user.slice\",kind=\"slice\"} 4",
+            "ax206lcd_process_resident_memory_bytes{pid=\"42\",name=\"postgres\"} 1024",
+            "ax206lcd_custom_metric{metric=\"orders\",label=\"Queue\"} 42",
+            "ax206lcd_collector_duration_seconds{collector=\"cpu\"} 0.012",
+            "ax206lcd_collect_duration_seconds 0.04",
+            "ax206lcd_device_connected 0",
+            "ax206lcd_device_upload_seconds 0.18",
+            "ax206lcd_device_upload_failures_total 1",
+        ] {
+            assert!(page.lines().any(|l| l == line), "missing {}", line);
+        }
+        // A process in several rankings is exported once
+        assert_eq!(page.matches("process_resident_memory_bytes{").count(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_metrics_endpoint() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        tokio::spawn(serve(listener, Metrics::default()));
+
+        let response = get(address, "/metrics").await;
+        assert!(response.starts_with("HTTP/1.1 200 OK\r\n"));
+        assert!(response.contains("Content-Type: text/plain; version=0.0.4"));
+        assert!(response.contains("ax206lcd_device_connected 0"));
+        assert!(get(address, "/other").await.starts_with("HTTP/1.1 404 Not Found"));
+    }
+}
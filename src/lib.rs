@@ -1,15 +1,18 @@
 pub mod config;
 pub mod dashboard;
+pub mod discover;
 
 use crate::client::ax206lcd::AX206LCD;
 use crate::config::AppConfig;
-use crate::collectors::collector;
-use crate::models::AllowedResources;
+use crate::collectors::registry::Registry;
+use crate::exporter::Metrics;
 use anyhow::Context;
 use log::{debug, error, info};
-use std::time::Duration;
+use std::time::{Duration, Instant};
+use tokio::net::TcpListener;
 
 mod collectors;
+mod exporter;
 mod models;
 mod renderer;
 
@@ -17,10 +20,10 @@ mod client;
 
 pub mod utils;
 
-pub async fn run() -> anyhow::Result<()> {
+pub async fn run(config: AppConfig) -> anyhow::Result<()> {
     info!("Starting application");
 
-    match main_loop().await {
+    match main_loop(config).await {
         Ok(_) => info!("Application completed successfully"),
         Err(e) => {
             error!("Application error: {e:#}");
@@ -37,49 +40,66 @@ pub async fn run() -> anyhow::Result<()> {
     Ok(())
 }
 
-async fn main_loop() -> anyhow::Result<()> {
-    debug!("Loading configuration");
-    let config = AppConfig::new().context("Failed to load configuration")?;
+async fn main_loop(config: AppConfig) -> anyhow::Result<()> {
     let mut interval = tokio::time::interval(Duration::from_secs(config.lcd.polling));
     let mut lcd: Option<AX206LCD> = None;
-    loop {
-        interval.tick().await; // Wait for the next tick
 
-        // Declare img variable to be used later
-        let img;
+    // Empty resource lists mean "auto", resolve them once against this machine
+    let allowed_resources = discover::resolve(&config.resources).await;
+    debug!("Resources: {:?}", allowed_resources);
+    let mut registry = Registry::from_config(&config, allowed_resources);
+
+    let metrics = Metrics::default();
+    if config.metrics.enabled {
+        let listener = TcpListener::bind(&config.metrics.listen)
+            .await
+            .context(format!("Failed to listen for metrics on {}", config.metrics.listen))?;
+        info!("Serving metrics on http://{}/metrics", config.metrics.listen);
+        tokio::spawn(exporter::serve(listener, metrics.clone()));
+    }
 
-        if config.dashboard.enabled {
-            // Dashboard is enabled, collect system info and create a dashboard image
-            let allowed_resources = AllowedResources {
-                disks: config.resources.disks.clone(),
-                networks: config.resources.networks.clone(),
-                mount_points: config.resources.mount_points.clone(),
-                sensors: config.resources.sensors.clone(),
-            };
+    loop {
+        interval.tick().await; // Wait for the next tick
 
+        // Collect when the dashboard or the metrics endpoint uses the values
+        let info = if config.dashboard.enabled || config.metrics.enabled {
             debug!("Collecting system info");
-            let info = collector::collect_system_info(allowed_resources).await;
+            let start = Instant::now();
+            let info = registry.collect().await;
+            if config.metrics.enabled {
+                metrics.update(&info, registry.timings(), start.elapsed());
+            }
+            Some(info)
+        } else {
+            None
+        };
 
-            // Generate image from metrics
-            img = dashboard::create_image(&config, &info);
+        let img = match info.filter(|_| config.dashboard.enabled) {
+            Some(info) => {
+                // Dashboard is enabled, generate image from metrics
+                let img = dashboard::create_image(&config, &info);
 
-            // Save image to file if configured to do so
-            if config.dashboard.save_to_file {
-                dashboard::save_image(&config, &img);
+                // Save image to file if configured to do so
+                if config.dashboard.save_to_file {
+                    dashboard::save_image(&config, &img);
+                }
+                img
             }
-        } else {
-            // Dashboard is disabled, load image from file
-            debug!("Loading image from file: {}", config.lcd.file);
-            img = image::open(&config.lcd.file)
-                .context(format!("Failed to load image from {}", config.lcd.file))?;
-        }
+            None => {
+                // Dashboard is disabled, load image from file
+                debug!("Loading image from file: {}", config.lcd.file);
+                image::open(&config.lcd.file)
+                    .context(format!("Failed to load image from {}", config.lcd.file))?
+            }
+        };
 
         // Upload image to the device
         if lcd.is_none() {
-            match AX206LCD::new(false) {
+            match AX206LCD::new(config.lcd.vid, config.lcd.pid, false) {
                 Ok(device) => lcd = Some(device),
                 Err(e) => {
                     error!("Failed to initialize LCD device: {}", e);
+                    metrics.disconnected();
                     tokio::time::sleep(Duration::from_secs(10)).await; // Longer backoff for hardware errors
                     continue;
                 }
@@ -88,8 +108,10 @@ async fn main_loop() -> anyhow::Result<()> {
 
         // Set device backlight
         if let Some(ref mut device) = lcd {
+            let upload_start = Instant::now();
             if let Err(e) = device.set_backlight(config.lcd.backlight) {
                 error!("Failed to set backlight: {}", e);
+                metrics.upload_failed();
                 lcd = None;
                 continue;
             }
@@ -97,9 +119,11 @@ async fn main_loop() -> anyhow::Result<()> {
             // Draw the image on the device
             if let Err(e) = device.draw(&img) {
                 error!("Failed to draw image: {}", e);
+                metrics.upload_failed();
                 lcd = None;
                 continue;
             }
+            metrics.uploaded(upload_start.elapsed());
         }
     }
 }
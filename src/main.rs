@@ -1,16 +1,119 @@
 use env_logger::{Builder, WriteStyle};
 use log::{error};
-use ax206lcd::config::AppConfig;
+use ax206lcd::config::{AppConfig, IniDocument};
+use ax206lcd::discover;
+use std::path::Path;
+
+const USAGE: &str = "Usage: ax206lcd [--config <file>] [--profile <name>] [discover [--write]]
+
+Commands:
+  discover               List disks, mounts, networks, sensors and GPUs found on this machine
+                         and print a suggested [RESOURCES] section (--write saves it to the config)
+
+Options:
+  -c, --config <file>    Configuration file to load (default: config.ini)
+  -p, --profile <name>   Apply [PROFILE.<name>] instead of matching this host
+  -h, --help             Print this help";
+
+const DEFAULT_CONFIG: &str = "config.ini";
+
+struct Args {
+    /// `None` unless `--config` was given.
+    config: Option<String>,
+    profile: Option<String>,
+    discover: bool,
+    write: bool,
+}
+
+fn parse_args() -> Result<Args, String> {
+    let mut args = Args {
+        config: None,
+        profile: None,
+        discover: false,
+        write: false,
+    };
+
+    let mut iter = std::env::args().skip(1);
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "-c" | "--config" => args.config = Some(iter.next().ok_or("--config requires a file")?),
+            "-p" | "--profile" => args.profile = Some(iter.next().ok_or("--profile requires a name")?),
+            "discover" => args.discover = true,
+            "--write" => args.write = true,
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                std::process::exit(0);
+            }
+            other => return Err(format!("Unknown argument: {}", other)),
+        }
+    }
+    if args.write && !args.discover {
+        return Err("--write is only valid with discover".to_string());
+    }
+
+    Ok(args)
+}
+
+impl Args {
+    fn config_path(&self) -> &str {
+        self.config.as_deref().unwrap_or(DEFAULT_CONFIG)
+    }
+}
+
+/// Prints what was found and, with `--write`, stores it as the `[RESOURCES]` section.
+async fn discover(args: &Args) -> anyhow::Result<()> {
+    let discovery = discover::discover().await;
+    println!("{}", discovery);
+
+    let path = args.config_path();
+    let mut config = if Path::new(path).exists() {
+        AppConfig::load(path, args.profile.as_deref())?
+    } else {
+        AppConfig::default()
+    };
+    config.resources = discovery.to_resources();
+
+    if args.write {
+        config.save(path)?;
+        println!("Saved [RESOURCES] to {}", path);
+    } else {
+        let mut suggestion = IniDocument::default();
+        suggestion.set("RESOURCES", "disks", &config.resources.disks);
+        suggestion.set("RESOURCES", "networks", &config.resources.networks);
+        suggestion.set("RESOURCES", "mount_points", &config.resources.mount_points);
+        suggestion.ensure_section("RESOURCES.sensors");
+        for (hint, label) in &config.resources.sensors {
+            suggestion.set("RESOURCES.sensors", hint, std::slice::from_ref(label));
+        }
+        println!("Suggested configuration (run with --write to save it):\n");
+        print!("{}", suggestion.to_text());
+    }
+    Ok(())
+}
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
-    // Load configuration first (without logging)
-    let config = AppConfig::new().unwrap_or_else(|e| {
-        eprintln!("Failed to load configuration: {}", e);
-        // Fall back to default configuration
-        AppConfig::default()
+    let args = parse_args().unwrap_or_else(|e| {
+        eprintln!("{}\n\n{}", e, USAGE);
+        std::process::exit(2);
     });
 
+    if args.discover {
+        return discover(&args).await;
+    }
+
+    // Load configuration first (without logging). Only a missing config.ini falls back to the
+    // defaults: a file that exists, or a file or profile asked for by name, must load.
+    let config = if args.config.is_none() && args.profile.is_none() && !Path::new(DEFAULT_CONFIG).exists() {
+        eprintln!("{} not found, using the default configuration", DEFAULT_CONFIG);
+        AppConfig::default()
+    } else {
+        AppConfig::load(args.config_path(), args.profile.as_deref()).unwrap_or_else(|e| {
+            eprintln!("Failed to load configuration: {:#}", e);
+            std::process::exit(1);
+        })
+    };
+
     // Initialise logger with a configured log level
     Builder::new()
         .filter_level(config.get_log_level())
@@ -18,9 +121,9 @@ async fn main() -> anyhow::Result<()> {
         .format_timestamp_secs()
         .init();
 
-    if let Err(e) = ax206lcd::run().await {
+    if let Err(e) = ax206lcd::run(config).await {
         error!("Application error: {}", e);
-        return Err(e.into());
+        return Err(e);
     }
     Ok(())
 }
@@ -0,0 +1,66 @@
+/// What a cgroup listed by the containers collector is.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum CgroupKind {
+    /// A systemd slice such as `system.slice` or `user-1000.slice`.
+    #[default]
+    Slice,
+    Docker,
+    Podman,
+    /// CRI-O or containerd, as run by Kubernetes.
+    Kubernetes,
+}
+
+impl CgroupKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CgroupKind::Slice => "slice",
+            CgroupKind::Docker => "docker",
+            CgroupKind::Podman => "podman",
+            CgroupKind::Kubernetes => "kubernetes",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ContainerInfo {
+    /// Path below the cgroup root, e.g. `system.slice/docker-1a2b….scope`.
+    pub path: String,
+    /// Container name from the runtime's API, the short container ID, or the slice name.
+    pub name: String,
+    /// Full container ID, empty for slices.
+    pub id: String,
+    pub kind: CgroupKind,
+    /// Share of one CPU, so busy containers exceed 100%.
+    pub cpu_percent: f32,
+    pub cpu_display: String,
+    /// `memory.current` in bytes.
+    pub memory: u64,
+    /// `memory.max` in bytes, `None` when unlimited.
+    pub memory_limit: Option<u64>,
+    pub memory_display: String,
+    /// Storage I/O in bytes per second.
+    pub read_rate: u64,
+    pub write_rate: u64,
+    pub io_display: String,
+    pub pids: u64,
+}
+
+impl Default for ContainerInfo {
+    fn default() -> Self {
+        Self {
+            path: String::new(),
+            name: String::new(),
+            id: String::new(),
+            kind: CgroupKind::default(),
+            cpu_percent: 0.0,
+            cpu_display: String::from("0.0%"),
+            memory: 0,
+            memory_limit: None,
+            memory_display: String::from("0 B"),
+            read_rate: 0,
+            write_rate: 0,
+            io_display: String::from("0 B/s"),
+            pids: 0,
+        }
+    }
+}
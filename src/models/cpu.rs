@@ -2,12 +2,35 @@
 pub struct CpuInfo {
     pub percent: f32,
     pub percent_display: String,
+    /// Share of time (0-1) in user, system (including interrupts), iowait and steal.
+    pub user: f32,
+    pub system: f32,
+    pub iowait: f32,
+    pub steal: f32,
     pub freq: f32,
     pub freq_display: String,
+    /// Lowest and highest frequency the cores support, in MHz.
+    pub min_freq: f32,
+    pub max_freq: f32,
+    pub freq_range_display: String,
     pub count: u64,
     pub count_display: String,
     pub cpu_temp: f32,
     pub cpu_temp_display: String,
+    pub cores: Vec<CoreInfo>,
+}
+
+/// Usage and frequency of one logical core.
+#[derive(Debug, Clone, Default)]
+pub struct CoreInfo {
+    pub id: usize,
+    pub percent: f32,
+    pub user: f32,
+    pub system: f32,
+    pub iowait: f32,
+    pub steal: f32,
+    pub freq: f32,
+    pub freq_display: String,
 }
 
 impl Default for CpuInfo {
@@ -15,12 +38,20 @@ impl Default for CpuInfo {
         Self {
             percent: 0.0,
             percent_display: "0.0%".to_string(),
+            user: 0.0,
+            system: 0.0,
+            iowait: 0.0,
+            steal: 0.0,
             freq: 0.0,
             freq_display: "0.0 MHz".to_string(),
+            min_freq: 0.0,
+            max_freq: 0.0,
+            freq_range_display: "0.0-0.0 GHz".to_string(),
             count: 0,
             count_display: "0 cores".to_string(),
             cpu_temp: 0.0,
             cpu_temp_display: "0.0%".to_string(),
+            cores: Vec::new(),
         }
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,12 @@
+/// Latest value of one `[CUSTOM.<name>]` metric.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CustomMetric {
+    /// The configured label, or the section name.
+    pub label: String,
+    /// Scaled value; `None` until fetched, or when the last fetch failed.
+    pub value: Option<f64>,
+    /// e.g. `1.25k req/s`, or why there is no value.
+    pub display: String,
+    /// Value relative to the configured `max`, 0-1; `None` without one.
+    pub percent: Option<f32>,
+}
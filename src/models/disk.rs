@@ -6,14 +6,116 @@ pub struct DiskInfo {
     pub used_display: String,
     pub total: u64,
     pub total_display: String,
+    /// One entry per configured mount point, in configuration order.
+    pub mounts: Vec<MountInfo>,
+    /// md RAID arrays from `/proc/mdstat`.
+    pub raid: Vec<RaidInfo>,
+    /// `None` without the ZFS module loaded.
+    pub zfs: Option<ZfsInfo>,
 }
 
+/// Space and inodes used on one mount point.
+#[derive(Debug, Clone, Default)]
+pub struct MountInfo {
+    pub mount_point: String,
+    /// Mounted device, e.g. `/dev/nvme0n1p2` or `tank/home`.
+    pub device: String,
+    pub fs_type: String,
+    pub read_only: bool,
+    pub percent: f32,
+    pub percent_display: String,
+    pub used: u64,
+    pub used_display: String,
+    pub total: u64,
+    pub total_display: String,
+    /// 0 for filesystems without a fixed inode count, such as btrfs.
+    pub inodes_used: u64,
+    pub inodes_total: u64,
+    pub inodes_percent: f32,
+    pub inodes_display: String,
+}
+
+/// One md RAID array.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RaidInfo {
+    /// e.g. `md0`.
+    pub name: String,
+    /// `raid1`, `raid5`, ...; empty for inactive arrays.
+    pub level: String,
+    pub active: bool,
+    /// Member devices the array should have, and those working, from `[2/1]`.
+    pub devices: u32,
+    pub working: u32,
+    /// Members marked `(F)`.
+    pub failed: u32,
+    /// `recovery`, `resync`, `check` or `reshape` while one runs.
+    pub action: Option<String>,
+    /// Progress of `action`, 0-1.
+    pub progress: Option<f32>,
+    /// e.g. `[U_] recovery 8.5%` or `[UU]`.
+    pub display: String,
+}
+
+impl RaidInfo {
+    pub fn degraded(&self) -> bool {
+        !self.active || self.working < self.devices || self.failed > 0
+    }
+}
+
+/// One imported ZFS pool.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ZfsPool {
+    pub name: String,
+    /// `ONLINE`, `DEGRADED`, `FAULTED`, `OFFLINE`, `UNAVAIL`, `REMOVED` or `SUSPENDED`.
+    pub health: String,
+}
+
+impl ZfsPool {
+    pub fn online(&self) -> bool {
+        self.health == "ONLINE"
+    }
+}
+
+/// ZFS pools and ARC statistics from `/proc/spl/kstat/zfs`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ZfsInfo {
+    pub pools: Vec<ZfsPool>,
+    /// Bytes cached in the ARC, and its target maximum.
+    pub arc_size: u64,
+    pub arc_max: u64,
+    /// Share of ARC lookups that hit since the module was loaded, 0-1.
+    pub arc_hit_ratio: f32,
+    /// e.g. `ARC 3.2 GB/8.0 GB | 97% hit`.
+    pub arc_display: String,
+}
+
+/// Rates are per second since the previous sample.
 #[derive(Debug, Clone)]
 pub struct DiskIoInfo {
     pub read: u64,
     pub read_display: String,
     pub write: u64,
     pub write_display: String,
+    pub read_ops: u64,
+    pub write_ops: u64,
+    /// Busiest device's share of time with I/O in flight (0-1).
+    pub busy: f32,
+    /// One entry per configured block device, in configuration order.
+    pub devices: Vec<DeviceIoInfo>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct DeviceIoInfo {
+    pub name: String,
+    pub read: u64,
+    pub read_display: String,
+    pub write: u64,
+    pub write_display: String,
+    pub read_ops: u64,
+    pub write_ops: u64,
+    /// Share of time with I/O in flight (0-1), from `io_ticks`.
+    pub busy: f32,
+    pub busy_display: String,
 }
 
 impl Default for DiskInfo {
@@ -25,6 +127,9 @@ impl Default for DiskInfo {
             used_display: String::from("0 B"),
             total: 0,
             total_display: String::from("0 B"),
+            mounts: Vec::new(),
+            raid: Vec::new(),
+            zfs: None,
         }
     }
 }
@@ -33,9 +138,13 @@ impl Default for DiskIoInfo {
     fn default() -> Self {
         Self {
             read: 0,
-            read_display: String::from("0 B"),
+            read_display: String::from("0 B/s"),
             write: 0,
-            write_display: String::from("0 B"),
+            write_display: String::from("0 B/s"),
+            read_ops: 0,
+            write_ops: 0,
+            busy: 0.0,
+            devices: Vec::new(),
         }
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,93 @@
+/// Which driver a GPU was read from.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum GpuVendor {
+    #[default]
+    Nvidia,
+    Amd,
+    Intel,
+}
+
+/// A process holding GPU memory.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct GpuProcess {
+    pub pid: u32,
+    pub name: String,
+    pub memory_used: u64,
+}
+
+#[derive(Debug, Clone)]
+pub struct GpuInfo {
+    pub name: String,
+    pub vendor: GpuVendor,
+    /// 0 when the GPU has no temperature sensor (most integrated Intel GPUs).
+    pub temperature: f32,
+    pub temperature_display: String,
+    pub load: f32,
+    pub load_display: String,
+    /// Dedicated memory; 0 for GPUs sharing system memory.
+    pub memory_used: u64,
+    pub memory_used_display: String,
+    pub memory_total: u64,
+    pub memory_total_display: String,
+    pub memory_percent: f32,
+    pub memory_percent_display: String,
+    /// Watts, 0 when not reported.
+    pub power: f32,
+    pub power_display: String,
+    pub power_limit: f32,
+    /// 0-1, 0 for passively cooled cards.
+    pub fan: f32,
+    pub fan_display: String,
+    /// Core clock in MHz.
+    pub clock: f32,
+    pub clock_display: String,
+    /// Memory clock in MHz.
+    pub memory_clock: f32,
+    /// Intel only: share of time spent in the RC6 power-saving state.
+    pub rc6: f32,
+    /// Video encoder and decoder usage, 0-1.
+    pub encoder: f32,
+    pub decoder: f32,
+    /// PCIe throughput in bytes per second.
+    pub pcie_rx: u64,
+    pub pcie_tx: u64,
+    pub pcie_display: String,
+    pub processes: Vec<GpuProcess>,
+    /// Why clocks are held down, e.g. `sw_power_cap` or `hw_thermal_slowdown`.
+    pub throttle_reasons: Vec<String>,
+}
+
+impl Default for GpuInfo {
+    fn default() -> Self {
+        Self {
+            name: String::from(""),
+            vendor: GpuVendor::default(),
+            temperature: 0.0,
+            temperature_display: String::from("0.0°C"),
+            load: 0.0,
+            load_display: String::from("0%"),
+            memory_used: 0,
+            memory_used_display: String::from("0 MB"),
+            memory_total: 0,
+            memory_total_display: String::from("0 MB"),
+            memory_percent: 0.0,
+            memory_percent_display: String::from("0%"),
+            power: 0.0,
+            power_display: String::from("0 W"),
+            power_limit: 0.0,
+            fan: 0.0,
+            fan_display: String::from("N/A"),
+            clock: 0.0,
+            clock_display: String::from("0 MHz"),
+            memory_clock: 0.0,
+            rc6: 0.0,
+            encoder: 0.0,
+            decoder: 0.0,
+            pcie_rx: 0,
+            pcie_tx: 0,
+            pcie_display: String::new(),
+            processes: Vec::new(),
+            throttle_reasons: Vec::new(),
+        }
+    }
+}
@@ -1,11 +1,29 @@
 #[derive(Debug, Clone)]
 pub struct MemoryInfo {
+    /// Share of memory in use, not counting what the kernel can reclaim (mostly page cache).
     pub percent: f32,
     pub percent_display: String,
     pub used: u64,
     pub used_display: String,
     pub total: u64,
     pub total_display: String,
+    pub available: u64,
+    pub available_display: String,
+    pub free: u64,
+    /// Reclaimable memory (page cache, buffers, reclaimable slab): `available - free`.
+    pub cache: u64,
+    pub cache_percent: f32,
+    pub cache_display: String,
+    pub buffers: u64,
+    pub cached: u64,
+    /// `Shmem`: tmpfs and shared memory segments.
+    pub shared: u64,
+    pub slab: u64,
+    pub dirty: u64,
+    pub writeback: u64,
+    /// Hugepage pool size and unused part, in bytes.
+    pub hugepages_total: u64,
+    pub hugepages_free: u64,
 }
 
 #[derive(Debug, Clone)]
@@ -27,6 +45,20 @@ impl Default for MemoryInfo {
             used_display: String::from("0 B"),
             total: 0,
             total_display: String::from("0 B"),
+            available: 0,
+            available_display: String::from("0 B"),
+            free: 0,
+            cache: 0,
+            cache_percent: 0.0,
+            cache_display: String::from("0 B"),
+            buffers: 0,
+            cached: 0,
+            shared: 0,
+            slab: 0,
+            dirty: 0,
+            writeback: 0,
+            hugepages_total: 0,
+            hugepages_free: 0,
         }
     }
 }
@@ -41,4 +73,4 @@ impl Default for SwapMemoryInfo {
             total_display: String::from("0 B"),
         }
     }
-}
\ No newline at end of file
+}
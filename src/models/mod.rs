@@ -1,12 +1,18 @@
 use indexmap::IndexMap;
 
+pub(crate) mod container;
 pub(crate) mod cpu;
+pub(crate) mod custom;
 pub(crate) mod disk;
 pub(crate) mod memory;
+pub(crate) mod pressure;
+pub(crate) mod power;
+pub(crate) mod probe;
 pub(crate) mod network;
-pub(crate) mod nvidia;
+pub(crate) mod gpu;
 pub(crate) mod system;
 pub(crate) mod sensor;
+pub(crate) mod unit;
 
 #[derive(Debug, Clone)]
 pub struct AllowedResources {
@@ -18,18 +24,28 @@ pub struct AllowedResources {
 
 
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub struct SystemInfo {
     pub cpu: cpu::CpuInfo,
     pub memory: memory::MemoryInfo,
     pub swap_memory: memory::SwapMemoryInfo,
+    pub pressure: pressure::PressureInfo,
     pub disk: disk::DiskInfo,
     pub disk_io: disk::DiskIoInfo,
     pub network: network::NetworkInfo,
-    pub nvidia: Vec<nvidia::NvidiaInfo>,
+    pub gpus: Vec<gpu::GpuInfo>,
+    pub power: power::PowerInfo,
     pub system: system::SystemMetrics,
     pub time: system::TimeInfo,
-    pub syslog_lines: Vec<String>,
+    pub syslog_lines: Vec<system::LogLine>,
     pub top_cpu_processes: Vec<system::ProcessInfo>,
     pub top_memory_processes: Vec<system::ProcessInfo>,
+    pub top_io_processes: Vec<system::ProcessInfo>,
+    pub top_connection_processes: Vec<system::ProcessInfo>,
+    pub containers: Vec<container::ContainerInfo>,
+    /// Failed and watched systemd units; `None` when systemd could not be asked.
+    pub units: Option<Vec<unit::UnitInfo>>,
+    pub probes: Vec<probe::ProbeInfo>,
+    /// `[CUSTOM.<name>]` values by name, for `metric:<name>` widgets.
+    pub custom: IndexMap<String, custom::CustomMetric>,
 }
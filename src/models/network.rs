@@ -1,18 +1,74 @@
-#[derive(Debug, Clone)]
+use std::net::IpAddr;
+
+/// Rates are per second since the previous sample.
+#[derive(Debug, Clone, Default)]
 pub struct NetworkInfo {
     pub sent: u64,
     pub sent_display: String,
     pub recv: u64,
     pub recv_display: String,
+    /// One entry per configured interface, in configuration order.
+    pub interfaces: Vec<InterfaceInfo>,
+    /// IPv4 and IPv6 sockets of the whole host.
+    pub tcp: TcpStates,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct InterfaceInfo {
+    pub name: String,
+    pub recv: u64,
+    pub recv_display: String,
+    pub sent: u64,
+    pub sent_display: String,
+    pub recv_packets: u64,
+    pub sent_packets: u64,
+    /// Counters since the interface came up.
+    pub recv_errors: u64,
+    pub sent_errors: u64,
+    pub recv_drops: u64,
+    pub sent_drops: u64,
+    /// IPv4 addresses first, then IPv6.
+    pub addresses: Vec<IpAddr>,
+    /// The address worth showing: the first IPv4, otherwise the first IPv6 that isn't link-local.
+    pub address_display: String,
+    /// `up`, `down`, `dormant`, or `unknown` for virtual interfaces.
+    pub operstate: String,
+    /// Link speed in Mb/s, `None` when down or not reported (Wi-Fi, virtual interfaces).
+    pub speed: Option<u32>,
+    pub speed_display: String,
+    /// `full`, `half`, or empty when not reported.
+    pub duplex: String,
+    pub wireless: Option<WirelessInfo>,
 }
 
-impl Default for NetworkInfo {
-    fn default() -> Self {
-        Self {
-            sent: 0,
-            sent_display: String::new(),
-            recv: 0,
-            recv_display: String::new(),
-        }
-    }
-}
\ No newline at end of file
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct WirelessInfo {
+    /// Empty when not associated, or when `iw` is not installed.
+    pub ssid: String,
+    /// Signal level in dBm.
+    pub signal: Option<i32>,
+    /// Link quality from 0 to 1.
+    pub quality: Option<f32>,
+    /// Transmit bitrate in Mb/s.
+    pub bitrate: Option<f32>,
+    /// e.g. `HomeNet -56 dBm 390 Mb/s`
+    pub display: String,
+}
+
+/// Number of TCP sockets in each state.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TcpStates {
+    pub established: usize,
+    pub syn_sent: usize,
+    pub syn_recv: usize,
+    pub fin_wait1: usize,
+    pub fin_wait2: usize,
+    pub time_wait: usize,
+    pub close: usize,
+    pub close_wait: usize,
+    pub last_ack: usize,
+    pub listen: usize,
+    pub closing: usize,
+    /// e.g. `TCP 12 est 5 listen 3 tw 1 cw`
+    pub display: String,
+}
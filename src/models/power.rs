@@ -0,0 +1,48 @@
+/// A battery from `/sys/class/power_supply`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct BatteryInfo {
+    /// Supply name, e.g. `BAT0`.
+    pub name: String,
+    /// Charge level, 0-1.
+    pub capacity: f32,
+    pub capacity_display: String,
+    /// `Charging`, `Discharging`, `Full`, `Not charging` or `Unknown`.
+    pub status: String,
+    /// Watts flowing in or out, 0 when not reported.
+    pub power: f32,
+    pub power_display: String,
+    /// Watt-hours.
+    pub energy: f32,
+    pub energy_full: f32,
+    /// Seconds until empty while discharging, or until full while charging.
+    pub time_remaining: Option<u64>,
+    /// e.g. `2h 10m left` or `45m to full`.
+    pub time_display: String,
+}
+
+impl BatteryInfo {
+    pub fn charging(&self) -> bool {
+        self.status == "Charging"
+    }
+}
+
+/// A RAPL power domain from `/sys/class/powercap`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RaplDomain {
+    /// `package-0`, `dram`, ...
+    pub name: String,
+    /// Watts averaged since the previous collection.
+    pub power: f32,
+    pub power_display: String,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PowerInfo {
+    pub batteries: Vec<BatteryInfo>,
+    /// Whether a mains adapter is plugged in; `None` without one (desktops report none either way).
+    pub ac_online: Option<bool>,
+    /// Empty when RAPL is missing or its energy counters are only readable by root.
+    pub rapl: Vec<RaplDomain>,
+    /// e.g. `AC | package-0 12.3 W dram 1.1 W` or `On battery`.
+    pub display: String,
+}
@@ -0,0 +1,54 @@
+/// One line of a PSI file: the share of wall time tasks were stalled, as a percentage averaged over
+/// 10, 60 and 300 seconds, and the total stall time in microseconds.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct PressureAverages {
+    pub avg10: f32,
+    pub avg60: f32,
+    pub avg300: f32,
+    pub total: u64,
+}
+
+/// `some`: at least one task stalled; `full`: all non-idle tasks stalled at once.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Pressure {
+    pub some: PressureAverages,
+    pub full: PressureAverages,
+}
+
+/// CPU, memory and I/O pressure of the system or one cgroup; `None` where the file is missing,
+/// e.g. on kernels built without PSI.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ResourcePressure {
+    pub cpu: Option<Pressure>,
+    pub memory: Option<Pressure>,
+    pub io: Option<Pressure>,
+    /// e.g. `cpu 5.4% mem 0.8% io 0.0%` (`some` over 10 seconds)
+    pub display: String,
+}
+
+impl ResourcePressure {
+    /// The highest `some` avg10 of the three, from 0 to 1.
+    pub fn highest(&self) -> f32 {
+        [self.cpu, self.memory, self.io]
+            .iter()
+            .flatten()
+            .map(|pressure| pressure.some.avg10 / 100.0)
+            .fold(0.0, f32::max)
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CgroupPressure {
+    /// Path below the cgroup root, e.g. `system.slice/docker.service`.
+    pub path: String,
+    /// Last component of `path`.
+    pub name: String,
+    pub pressure: ResourcePressure,
+}
+
+/// Pressure Stall Information from `/proc/pressure` and the configured cgroups.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PressureInfo {
+    pub system: ResourcePressure,
+    pub cgroups: Vec<CgroupPressure>,
+}
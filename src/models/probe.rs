@@ -0,0 +1,13 @@
+/// Latest result and latency history of one `[PROBES.<name>]` check.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ProbeInfo {
+    pub name: String,
+    pub target: String,
+    pub up: bool,
+    /// Milliseconds the last successful check took.
+    pub latency: Option<f32>,
+    /// e.g. `34 ms`, or why the target is down
+    pub latency_display: String,
+    /// Oldest first; `None` for failed checks.
+    pub history: Vec<Option<f32>>,
+}
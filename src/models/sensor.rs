@@ -1,3 +1,54 @@
+/// What an hwmon input measures.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SensorKind {
+    #[default]
+    Temperature,
+    Fan,
+    Voltage,
+    Power,
+    Current,
+}
+
+impl SensorKind {
+    pub const ALL: [SensorKind; 5] = [
+        SensorKind::Temperature,
+        SensorKind::Fan,
+        SensorKind::Voltage,
+        SensorKind::Power,
+        SensorKind::Current,
+    ];
+
+    /// File name prefix in sysfs, as in `temp1_input`, `fan1_input` or `in0_input`.
+    pub fn prefix(&self) -> &'static str {
+        match self {
+            SensorKind::Temperature => "temp",
+            SensorKind::Fan => "fan",
+            SensorKind::Voltage => "in",
+            SensorKind::Power => "power",
+            SensorKind::Current => "curr",
+        }
+    }
+
+    pub fn unit(&self) -> &'static str {
+        match self {
+            SensorKind::Temperature => "°C",
+            SensorKind::Fan => "RPM",
+            SensorKind::Voltage => "V",
+            SensorKind::Power => "W",
+            SensorKind::Current => "A",
+        }
+    }
+
+    /// Divisor from the sysfs value (millidegrees, millivolts, microwatts, ...) to `unit`.
+    pub fn scale(&self) -> f64 {
+        match self {
+            SensorKind::Fan => 1.0,
+            SensorKind::Power => 1_000_000.0,
+            _ => 1_000.0,
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct Sensor {
     pub id: u32,
@@ -5,7 +56,21 @@ pub struct Sensor {
     pub name: String,
     pub label: String,
     pub model: String,
-    pub temperature: f32,
+    /// Resolved `device` link of the chip, e.g. `/sys/devices/pci0000:00/0000:00:01.1/0000:01:00.0/nvme/nvme0`.
+    pub device: String,
+    pub kind: SensorKind,
+    pub unit: &'static str,
+    /// Current reading in `unit`.
+    pub value: f32,
+    pub max: Option<f32>,
+    pub crit: Option<f32>,
+}
+
+impl Sensor {
+    /// The input's name within its chip, e.g. `fan2` or `in1`.
+    pub fn channel(&self) -> String {
+        format!("{}{}", self.kind.prefix(), self.id)
+    }
 }
 
 impl Default for Sensor {
@@ -16,7 +81,12 @@ impl Default for Sensor {
             name: String::new(),
             label: String::new(),
             model: String::new(),
-            temperature: 0.0,
+            device: String::new(),
+            kind: SensorKind::default(),
+            unit: SensorKind::default().unit(),
+            value: 0.0,
+            max: None,
+            crit: None,
         }
     }
-}
\ No newline at end of file
+}
@@ -1,3 +1,4 @@
+use crate::models::sensor::SensorKind;
 use indexmap::IndexMap;
 
 #[derive(Debug, Clone)]
@@ -22,18 +23,25 @@ impl Default for SystemMetrics {
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub struct TimeInfo {
     pub time: u64,
     pub time_display: String,
 }
-impl Default for TimeInfo {
-    fn default() -> Self {
-        Self {
-            time: 0,
-            time_display: String::new(),
-        }
-    }
+
+/// How a log line is highlighted.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum LogSeverity {
+    #[default]
+    Normal,
+    Warning,
+    Error,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct LogLine {
+    pub text: String,
+    pub severity: LogSeverity,
 }
 
 #[derive(Debug, Clone)]
@@ -41,7 +49,24 @@ pub struct ProcessInfo {
     pub pid: u32,
     pub name: String,
     pub memory_percent: f32,
+    /// Share of one CPU, so busy multi-threaded processes exceed 100%.
     pub cpu_percent: f32,
+    pub user: String,
+    pub threads: u64,
+    /// Resident set size in bytes.
+    pub rss: u64,
+    pub rss_display: String,
+    /// `R` running, `S` sleeping, `D` waiting on I/O, `Z` zombie, ...
+    pub state: char,
+    /// Processes merged into this entry when grouping, otherwise 1.
+    pub count: usize,
+    pub cgroup: String,
+    /// Storage I/O in bytes per second.
+    pub read_rate: u64,
+    pub write_rate: u64,
+    pub io_display: String,
+    /// Open TCP connections, listening sockets excluded.
+    pub connections: usize,
 }
 impl Default for ProcessInfo {
     fn default() -> Self {
@@ -50,6 +75,17 @@ impl Default for ProcessInfo {
             name: String::new(),
             memory_percent: 0.0,
             cpu_percent: 0.0,
+            user: String::new(),
+            threads: 0,
+            rss: 0,
+            rss_display: String::from("0 B"),
+            state: '?',
+            count: 1,
+            cgroup: String::new(),
+            read_rate: 0,
+            write_rate: 0,
+            io_display: String::from("0 B/s"),
+            connections: 0,
         }
     }
 }
@@ -57,7 +93,22 @@ impl Default for ProcessInfo {
 #[derive(Debug, Clone)]
 pub struct SystemComponent {
     pub label: String,
-    pub temperature: f32,
+    pub kind: SensorKind,
+    /// Reading in the kind's unit (°C, RPM, V, W or A).
+    pub value: f32,
+}
+
+impl SystemComponent {
+    /// `45 CPU`, `1450 RPM Pump` or `12.10 V 12V`; temperatures go without a unit, which is
+    /// shown once in front of all readings.
+    pub fn display(&self) -> String {
+        match self.kind {
+            SensorKind::Temperature => format!("{:.0} {}", self.value, self.label),
+            SensorKind::Voltage => format!("{:.2} V {}", self.value, self.label),
+            SensorKind::Current => format!("{:.1} A {}", self.value, self.label),
+            kind => format!("{:.0} {} {}", self.value, kind.unit(), self.label),
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
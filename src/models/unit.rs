@@ -0,0 +1,30 @@
+/// State of one systemd unit, as `systemctl status` shows it.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct UnitInfo {
+    pub name: String,
+    pub description: String,
+    /// `loaded`, `not-found`, `masked`, ...
+    pub load_state: String,
+    /// `active`, `inactive`, `failed`, `activating`, ...
+    pub active_state: String,
+    /// Unit type specific, e.g. `running`, `exited` or `auto-restart`.
+    pub sub_state: String,
+    /// Automatic restarts of a service since it was started by hand (`NRestarts`).
+    pub restarts: u32,
+    /// Seconds since the unit entered its current state.
+    pub since: Option<u64>,
+    /// e.g. `3h 12m`
+    pub since_display: String,
+}
+
+impl UnitInfo {
+    pub fn failed(&self) -> bool {
+        self.active_state == "failed"
+    }
+
+    /// Starting, stopping, or waiting to be restarted after a crash.
+    pub fn restarting(&self) -> bool {
+        self.sub_state == "auto-restart"
+            || matches!(self.active_state.as_str(), "activating" | "deactivating")
+    }
+}
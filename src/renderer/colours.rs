@@ -5,12 +5,15 @@ pub(crate) struct Colours {
     pub text: Rgba<u8>,
     pub cpu: Rgba<u8>,
     pub mem: Rgba<u8>,
+    pub mem_cache: Rgba<u8>,
     pub disk: Rgba<u8>,
     pub gpu: Rgba<u8>,
     pub sensor: Rgba<u8>,
     pub io: Rgba<u8>,
     pub process: Rgba<u8>,
     pub log: Rgba<u8>,
+    pub log_warning: Rgba<u8>,
+    pub log_error: Rgba<u8>,
 }
 
 impl Default for Colours {
@@ -20,12 +23,28 @@ impl Default for Colours {
             text: Rgba([238, 238, 236, 255]),     // Off-white - for general text
             cpu: Rgba([87, 174, 36, 255]),        // Vibrant green - for CPU metrics
             mem: Rgba([52, 101, 164, 255]),       // Royal blue - for memory usage
+            mem_cache: Rgba([32, 62, 100, 255]),  // Dark blue - for reclaimable cache
             disk: Rgba([204, 0, 0, 255]),         // Crimson - for disk storage
             gpu: Rgba([173, 127, 168, 255]),      // Lavender - for GPU metrics
             sensor: Rgba([245, 121, 0, 255]),     // Burnt orange - for sensor readings
             io: Rgba([0, 188, 212, 255]),         // Cyan - for IO readings
             process: Rgba([237, 212, 0, 255]),    // Golden yellow - for processes
             log: Rgba([186, 189, 182, 255]),      // Silver gray - for logs
+            log_warning: Rgba([252, 175, 62, 255]), // Light orange - for log warnings
+            log_error: Rgba([239, 41, 41, 255]),  // Bright red - for log errors
         }
     }
-}
\ No newline at end of file
+}
+
+/// Green for 0, through yellow, to red for 1.
+pub fn heat(value: f32) -> Rgba<u8> {
+    let Colours { cpu: cool, process: warm, disk: hot, .. } = Colours::default();
+    let value = value.clamp(0.0, 1.0);
+    let (from, to, t) = if value < 0.5 {
+        (cool, warm, value * 2.0)
+    } else {
+        (warm, hot, (value - 0.5) * 2.0)
+    };
+    let mix = |a: u8, b: u8| (a as f32 + (b as f32 - a as f32) * t).round() as u8;
+    Rgba([mix(from[0], to[0]), mix(from[1], to[1]), mix(from[2], to[2]), 255])
+}
@@ -38,7 +38,7 @@ pub fn text(
         y,
         font_config.scale,
         &font_config.font,
-        &header_text,
+        header_text,
     );
 }
 pub fn progress_bar(
@@ -49,6 +49,18 @@ pub fn progress_bar(
     height: u32,
     percentage: f32,
     color: Rgba<u8>,
+) {
+    stacked_bar(image, x, y, width, height, &[(percentage, color)]);
+}
+
+/// Progress bar of consecutive segments, e.g. used and cache memory, each a fraction of the width.
+pub fn stacked_bar(
+    image: &mut RgbaImage,
+    x: i32,
+    y: i32,
+    width: u32,
+    height: u32,
+    segments: &[(f32, Rgba<u8>)],
 ) {
     let bg_colour = Rgba([30, 30, 30, 255]);
 
@@ -60,20 +72,83 @@ pub fn progress_bar(
     );
 
     // Progress
-    let bar_width = (percentage * width as f32) as u32;
+    let mut bar_x = x;
+    for (percentage, colour) in segments {
+        let remaining = (x + width as i32 - bar_x).max(0) as u32;
+        let bar_width = ((percentage.max(0.0) * width as f32) as u32).min(remaining);
 
-    if bar_width > 0 {
-        draw_filled_rect_mut(
-            image,
-            Rect::at(x as i32, y as i32).of_size(bar_width, height),
-            color,
-        );
+        if bar_width > 0 {
+            draw_filled_rect_mut(
+                image,
+                Rect::at(bar_x, y).of_size(bar_width, height),
+                *colour,
+            );
+        }
+        bar_x += bar_width as i32;
     }
 
     // Border
     draw_hollow_rect_mut(
         image,
-        Rect::at(x as i32, y as i32).of_size(width, height),
+        Rect::at(x, y).of_size(width, height),
         Rgba([100, 100, 100, 255]),
     );
 }
+
+/// Filled rectangle with a dark border, for grids of small tiles.
+pub fn cell(image: &mut RgbaImage, x: i32, y: i32, width: u32, height: u32, colour: Rgba<u8>) {
+    draw_filled_rect_mut(image, Rect::at(x, y).of_size(width, height), colour);
+    draw_hollow_rect_mut(image, Rect::at(x, y).of_size(width, height), Rgba([0, 0, 0, 255]));
+}
+
+/// One column per value out of `capacity`, newest on the right, scaled to the largest; `None` values,
+/// e.g. failed checks, are drawn full height in red.
+#[allow(clippy::too_many_arguments)]
+pub fn sparkline(
+    image: &mut RgbaImage,
+    x: i32,
+    y: i32,
+    width: u32,
+    height: u32,
+    values: &[Option<f32>],
+    capacity: usize,
+    colour: Rgba<u8>,
+) {
+    draw_filled_rect_mut(image, Rect::at(x, y).of_size(width, height), Rgba([30, 30, 30, 255]));
+
+    if !values.is_empty() {
+        let max = values.iter().flatten().fold(0.0_f32, |max, value| max.max(*value));
+        let column_width = (width / capacity.max(values.len()) as u32).max(1);
+        let columns = values.iter().rev().take((width / column_width) as usize);
+        for (i, value) in columns.enumerate() {
+            let (column_height, column_colour) = match value {
+                Some(value) if max > 0.0 => (((value / max) * height as f32).max(1.0) as u32, colour),
+                Some(_) => (1, colour),
+                None => (height, Rgba([239, 41, 41, 255])),
+            };
+            let column_x = x + width as i32 - (i as u32 + 1) as i32 * column_width as i32;
+            let column_y = y + (height - column_height.min(height)) as i32;
+            draw_filled_rect_mut(
+                image,
+                Rect::at(column_x, column_y).of_size(column_width, column_height.min(height)),
+                column_colour,
+            );
+        }
+    }
+
+    draw_hollow_rect_mut(image, Rect::at(x, y).of_size(width, height), Rgba([100, 100, 100, 255]));
+}
+
+/// Battery outline with a terminal on the right, filled to `fraction`.
+pub fn battery_gauge(image: &mut RgbaImage, x: i32, y: i32, width: u32, height: u32, fraction: f32, colour: Rgba<u8>) {
+    let border = Rgba([100, 100, 100, 255]);
+    let terminal_width = 6;
+    let body_width = width - terminal_width;
+
+    stacked_bar(image, x, y, body_width, height, &[(fraction, colour)]);
+    draw_filled_rect_mut(
+        image,
+        Rect::at(x + body_width as i32, y + height as i32 / 4).of_size(terminal_width, height / 2),
+        border,
+    );
+}
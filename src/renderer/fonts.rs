@@ -6,18 +6,15 @@ pub(crate) struct FontConfig {
 }
 
 pub fn title_font_config() -> FontConfig {
-    let config = get_font_config(24.0);
-    config
+    get_font_config(24.0)
 }
 
 pub fn regular_font_config() -> FontConfig {
-    let config = get_font_config(24.0);
-    config
+    get_font_config(24.0)
 }
 
 pub fn small_font_config() -> FontConfig {
-    let config = get_font_config(20.0);
-    config
+    get_font_config(20.0)
 }
 
 fn get_font_config(scale: f32) -> FontConfig {
@@ -1,12 +1,29 @@
 use crate::config::AppConfig;
+use crate::models::system::{LogSeverity, ProcessInfo};
 use crate::models::SystemInfo;
-use crate::renderer::colours::Colours;
+use crate::renderer::colours::{self, Colours};
 use crate::renderer::{drawing, fonts};
 use chrono::Local;
-use image::RgbaImage;
+use image::{Rgba, RgbaImage};
+use log::warn;
+use systemstat::ByteSize;
+
+const PRE_BAR_SPACING: u32 = 28;
+const POST_BAR_SPACING: u32 = 13;
+const BAR_HEIGHT: u32 = 20;
+const ROW_SPACING: u32 = 26;
+const CORE_CELL_MIN_WIDTH: u32 = 24;
+const CORE_CELL_HEIGHT: u32 = 16;
+/// Widths of the pid and value columns of a process ranking. The I/O rates and connection counts
+/// take two characters from the pid; percentages keep the original layout.
+const PERCENT_COLUMNS: (usize, usize) = (9, 7);
+const FIGURE_COLUMNS: (usize, usize) = (7, 9);
+
+/// Title, processes, ranked figure and column widths of a process ranking.
+type Ranking<'a> = (&'a str, &'a [ProcessInfo], fn(&ProcessInfo) -> String, (usize, usize));
 
 pub struct RenderContext<'a> {
-    pub config: &'a AppConfig, // Placeholder for future customisations of the widgets
+    pub config: &'a AppConfig,
     pub info: &'a SystemInfo,
     pub image: &'a mut RgbaImage,
     pub x: u32,
@@ -17,206 +34,552 @@ pub struct RenderContext<'a> {
 pub fn render_resource_bars(ctx: &mut RenderContext) {
     let mut y_pos = ctx.y;
 
-    let colours = Colours::default();
+    for bar in &ctx.config.dashboard.bars {
+        match bar.as_str() {
+            "cpu" => render_cpu_bar(ctx, &mut y_pos),
+            "cores" => render_core_grid(ctx, &mut y_pos),
+            "memory" => render_memory_bar(ctx, &mut y_pos),
+            "pressure" => render_pressure_bars(ctx, &mut y_pos),
+            "disk" => render_disk_bar(ctx, &mut y_pos),
+            "mounts" => render_mount_bars(ctx, &mut y_pos),
+            "devices" => render_device_bars(ctx, &mut y_pos),
+            "raid" => render_raid_rows(ctx, &mut y_pos),
+            "interfaces" => render_interface_rows(ctx, &mut y_pos),
+            "tcp" => render_tcp_row(ctx, &mut y_pos),
+            "gpu" => render_gpu_bars(ctx, &mut y_pos),
+            "battery" => render_battery_gauges(ctx, &mut y_pos),
+            "units" => render_unit_rows(ctx, &mut y_pos),
+            "probes" => render_probe_rows(ctx, &mut y_pos),
+            other => match other.strip_prefix("metric:") {
+                Some(name) => render_custom_metric(ctx, &mut y_pos, name),
+                None => warn!("Unknown dashboard bar: {}", other),
+            },
+        }
+    }
+}
 
+/// Draws `text` with a progress bar underneath and moves `y_pos` past both.
+fn labelled_bar(ctx: &mut RenderContext, y_pos: &mut u32, text: &str, percent: f32, colour: Rgba<u8>) {
+    let colours = Colours::default();
     let fc_regular = fonts::regular_font_config();
 
-    let pre_bar_spacing = 28;
-    let post_bar_spacing = 13;
-    let bar_height = 20;
-
-    // Add CPU text
-    let cpu_text = format!(
-        "CPU {} | {} | x{}",
-        ctx.info.cpu.percent_display, ctx.info.cpu.freq_display, ctx.info.cpu.count_display
-    );
-
     drawing::text(
         ctx.image,
         colours.text,
         (ctx.x + 5) as i32,
-        y_pos as i32, // Position text above the progress bar
+        *y_pos as i32, // Position text above the progress bar
         &fc_regular,
-        &cpu_text,
+        text,
     );
-    y_pos += pre_bar_spacing;
+    *y_pos += PRE_BAR_SPACING;
 
     drawing::progress_bar(
         ctx.image,
         (ctx.x + 5) as i32,
-        y_pos as i32,
+        *y_pos as i32,
         ctx.width - 10,
-        bar_height,
-        ctx.info.cpu.percent,
-        colours.cpu,
+        BAR_HEIGHT,
+        percent,
+        colour,
     );
-    y_pos += bar_height + post_bar_spacing;
+    *y_pos += BAR_HEIGHT + POST_BAR_SPACING;
+}
 
-    // Draw Memory usage
-    // Add Memory text
-    let mem_text = format!(
-        "MEM {} | {}/{}",
-        ctx.info.memory.percent_display, ctx.info.memory.used_display, ctx.info.memory.total_display
+fn render_cpu_bar(ctx: &mut RenderContext, y_pos: &mut u32) {
+    let cpu_text = format!(
+        "CPU {} | {} | x{}",
+        ctx.info.cpu.percent_display, ctx.info.cpu.freq_display, ctx.info.cpu.count_display
     );
+    labelled_bar(ctx, y_pos, &cpu_text, ctx.info.cpu.percent, Colours::default().cpu);
+}
+
+/// One cell per logical core, coloured from green (idle) to red (busy).
+fn render_core_grid(ctx: &mut RenderContext, y_pos: &mut u32) {
+    let colours = Colours::default();
+    let fc_regular = fonts::regular_font_config();
+    let cores = &ctx.info.cpu.cores;
+    if cores.is_empty() {
+        return;
+    }
 
+    let busiest = cores.iter().map(|core| core.percent).fold(0.0, f32::max);
+    let cores_text = format!(
+        "CORES {} | max {:.0}%",
+        ctx.info.cpu.freq_range_display,
+        busiest * 100.0
+    );
     drawing::text(
         ctx.image,
         colours.text,
         (ctx.x + 5) as i32,
-        y_pos as i32, // Position text above the progress bar
+        *y_pos as i32,
         &fc_regular,
-        &mem_text,
+        &cores_text,
     );
-    y_pos += pre_bar_spacing;
+    *y_pos += PRE_BAR_SPACING;
+
+    // As many columns as fit, then balance the rows so the last one isn't nearly empty
+    let width = ctx.width - 10;
+    let max_columns = (width / CORE_CELL_MIN_WIDTH).max(1) as usize;
+    let rows = cores.len().div_ceil(max_columns);
+    let columns = cores.len().div_ceil(rows);
+    let cell_width = width / columns as u32;
+
+    for (i, core) in cores.iter().enumerate() {
+        let x = ctx.x + 5 + (i % columns) as u32 * cell_width;
+        let y = *y_pos + (i / columns) as u32 * CORE_CELL_HEIGHT;
+        drawing::cell(
+            ctx.image,
+            x as i32,
+            y as i32,
+            cell_width,
+            CORE_CELL_HEIGHT,
+            colours::heat(core.percent),
+        );
+    }
+    *y_pos += rows as u32 * CORE_CELL_HEIGHT + POST_BAR_SPACING;
+}
 
-    drawing::progress_bar(
-        ctx.image,
-        (ctx.x + 5) as i32,
-        y_pos as i32,
-        ctx.width - 10,
-        bar_height,
-        ctx.info.memory.percent,
-        colours.mem,
-    );
-    y_pos += bar_height + post_bar_spacing;
+fn render_memory_bar(ctx: &mut RenderContext, y_pos: &mut u32) {
+    let colours = Colours::default();
+    let memory = &ctx.info.memory;
+    let mem_text = format!("MEM {} | {}/{}", memory.percent_display, memory.used_display, memory.total_display);
+    let segments = [(memory.percent, colours.mem), (memory.cache_percent, colours.mem_cache)];
+
+    let fc_regular = fonts::regular_font_config();
+    drawing::text(ctx.image, colours.text, (ctx.x + 5) as i32, *y_pos as i32, &fc_regular, &mem_text);
+    *y_pos += PRE_BAR_SPACING;
+
+    // Used, then reclaimable cache, then free, like htop
+    drawing::stacked_bar(ctx.image, (ctx.x + 5) as i32, *y_pos as i32, ctx.width - 10, BAR_HEIGHT, &segments);
+    *y_pos += BAR_HEIGHT + POST_BAR_SPACING;
+}
 
-    // Draw Disk usage
-    // Add Disk text
+/// System pressure, then one bar per configured cgroup, filled by the highest 10 second `some` stall.
+fn render_pressure_bars(ctx: &mut RenderContext, y_pos: &mut u32) {
+    let system = &ctx.info.pressure.system;
+    let highest = system.highest();
+    labelled_bar(ctx, y_pos, &format!("PSI {}", system.display), highest, colours::heat(highest));
+    for cgroup in &ctx.info.pressure.cgroups {
+        let cgroup_text = format!("{} {}", cgroup.name, cgroup.pressure.display);
+        let highest = cgroup.pressure.highest();
+        labelled_bar(ctx, y_pos, &cgroup_text, highest, colours::heat(highest));
+    }
+}
+
+fn render_disk_bar(ctx: &mut RenderContext, y_pos: &mut u32) {
     let disk_text = format!(
         "DISK {} | {}/{}",
         ctx.info.disk.percent_display, ctx.info.disk.used_display, ctx.info.disk.total_display
     );
+    labelled_bar(ctx, y_pos, &disk_text, ctx.info.disk.percent, Colours::default().disk);
+}
 
-    drawing::text(
-        ctx.image,
-        colours.text,
-        (ctx.x + 5) as i32,
-        y_pos as i32, // Position text above the progress bar
-        &fc_regular,
-        &disk_text,
-    );
-
-    y_pos += pre_bar_spacing;
-    drawing::progress_bar(
-        ctx.image,
-        (ctx.x + 5) as i32,
-        y_pos as i32,
-        ctx.width - 10,
-        bar_height,
-        ctx.info.disk.percent,
-        colours.disk,
-    );
-    y_pos += bar_height + post_bar_spacing;
-
-    // Draw GPU info if available
-    for gpu in &ctx.info.nvidia {
-        // Add GPU text
-        let gpu_text = format!(
+/// One usage bar per configured mount point; inode usage is shown once it outgrows space usage.
+fn render_mount_bars(ctx: &mut RenderContext, y_pos: &mut u32) {
+    for mount in &ctx.info.disk.mounts {
+        let mut mount_text = format!(
             "{} {} | {}/{}",
-            gpu.name,
-            gpu.load_display,
-            gpu.memory_used_display,
-            gpu.memory_total_display
+            mount.mount_point, mount.percent_display, mount.used_display, mount.total_display
         );
+        if mount.inodes_percent > mount.percent {
+            mount_text = format!(
+                "{} {} | inodes {}",
+                mount.mount_point, mount.percent_display, mount.inodes_display
+            );
+        }
+        if mount.read_only {
+            mount_text.push_str(" | ro");
+        }
+        let percent = mount.percent.max(mount.inodes_percent);
+        labelled_bar(ctx, y_pos, &mount_text, percent, Colours::default().disk);
+    }
+}
 
-        drawing::text(
-            ctx.image,
-            colours.text,
-            (ctx.x + 5) as i32,
-            y_pos as i32, // Position text above the progress bar
-            &fc_regular,
-            &gpu_text,
+/// One bar per configured block device, filled by how busy it is.
+fn render_device_bars(ctx: &mut RenderContext, y_pos: &mut u32) {
+    for device in &ctx.info.disk_io.devices {
+        let device_text = format!(
+            "{} {} | R {} W {}",
+            device.name, device.busy_display, device.read_display, device.write_display
         );
+        labelled_bar(ctx, y_pos, &device_text, device.busy, Colours::default().io);
+    }
+}
 
-        y_pos += pre_bar_spacing;
-        drawing::progress_bar(
+/// One text row per md RAID array and ZFS pool, red when degraded, then the ZFS ARC.
+fn render_raid_rows(ctx: &mut RenderContext, y_pos: &mut u32) {
+    let colours = Colours::default();
+    let fc_regular = fonts::regular_font_config();
+    let x = (ctx.x + 5) as i32;
+    let disk = &ctx.info.disk;
+
+    let mut rows: Vec<(String, Rgba<u8>)> = Vec::new();
+    for array in &disk.raid {
+        let colour = if array.degraded() {
+            colours.log_error
+        } else if array.action.is_some() {
+            colours.log_warning
+        } else {
+            colours.cpu
+        };
+        rows.push((format!("{} {} {}", array.name, array.level, array.display), colour));
+    }
+    if let Some(zfs) = &disk.zfs {
+        for pool in &zfs.pools {
+            let colour = if pool.online() { colours.cpu } else { colours.log_error };
+            rows.push((format!("{} {}", pool.name, pool.health), colour));
+        }
+        rows.push((zfs.arc_display.clone(), colours.text));
+    }
+    if rows.is_empty() {
+        rows.push(("No RAID arrays or ZFS pools".to_string(), colours.text));
+    }
+
+    for (text, colour) in rows {
+        drawing::text(ctx.image, colour, x, *y_pos as i32, &fc_regular, &text);
+        *y_pos += ROW_SPACING;
+    }
+    *y_pos += POST_BAR_SPACING;
+}
+
+/// One text row per configured network interface.
+fn render_interface_rows(ctx: &mut RenderContext, y_pos: &mut u32) {
+    let colours = Colours::default();
+    let fc_regular = fonts::regular_font_config();
+    let x = (ctx.x + 5) as i32;
+
+    for interface in &ctx.info.network.interfaces {
+        // Address and link first, so headless machines show where to reach them
+        let mut link_text = format!("{} {}", interface.name, interface.address_display);
+        let link_colour = if interface.operstate == "down" {
+            link_text = format!("{} down", interface.name);
+            colours.log_error
+        } else {
+            colours.io
+        };
+        let link = match &interface.wireless {
+            Some(wireless) if !wireless.display.is_empty() => &wireless.display,
+            _ => &interface.speed_display,
+        };
+        if !link.is_empty() {
+            link_text.push_str(&format!(" | {}", link));
+        }
+        drawing::text(ctx.image, link_colour, x, *y_pos as i32, &fc_regular, link_text.trim_end());
+        *y_pos += ROW_SPACING;
+
+        let mut interface_text = format!("  ↓{} ↑{}", interface.recv_display, interface.sent_display);
+        let errors = interface.recv_errors + interface.sent_errors;
+        let drops = interface.recv_drops + interface.sent_drops;
+        if errors > 0 || drops > 0 {
+            interface_text.push_str(&format!(" | E{} D{}", errors, drops));
+        }
+        drawing::text(ctx.image, colours.io, x, *y_pos as i32, &fc_regular, &interface_text);
+        *y_pos += ROW_SPACING;
+    }
+    *y_pos += POST_BAR_SPACING;
+}
+
+/// TCP sockets by state for the whole host.
+fn render_tcp_row(ctx: &mut RenderContext, y_pos: &mut u32) {
+    let colours = Colours::default();
+    let fc_regular = fonts::regular_font_config();
+    let tcp_text = &ctx.info.network.tcp.display;
+    drawing::text(ctx.image, colours.io, (ctx.x + 5) as i32, *y_pos as i32, &fc_regular, tcp_text);
+    *y_pos += ROW_SPACING + POST_BAR_SPACING;
+}
+
+/// One text row per failed or watched systemd unit: red when failed, orange while restarting.
+fn render_unit_rows(ctx: &mut RenderContext, y_pos: &mut u32) {
+    let colours = Colours::default();
+    let fc_regular = fonts::regular_font_config();
+
+    let (x, y) = ((ctx.x + 5) as i32, *y_pos as i32);
+    let units = match &ctx.info.units {
+        Some(units) if !units.is_empty() => units,
+        Some(_) => {
+            drawing::text(ctx.image, colours.cpu, x, y, &fc_regular, "No failed units");
+            *y_pos += ROW_SPACING + POST_BAR_SPACING;
+            return;
+        }
+        None => {
+            drawing::text(ctx.image, colours.log_warning, x, y, &fc_regular, "systemd unavailable");
+            *y_pos += ROW_SPACING + POST_BAR_SPACING;
+            return;
+        }
+    };
+    for unit in units {
+        let mut unit_text = format!("{} {}", unit.name, unit.sub_state);
+        if unit.restarts > 0 {
+            unit_text.push_str(&format!(" | x{}", unit.restarts));
+        }
+        if !unit.since_display.is_empty() {
+            unit_text.push_str(&format!(" | {}", unit.since_display));
+        }
+        let colour = if unit.failed() {
+            colours.log_error
+        } else if unit.restarting() {
+            colours.log_warning
+        } else {
+            colours.text
+        };
+        drawing::text(ctx.image, colour, (ctx.x + 5) as i32, *y_pos as i32, &fc_regular, &unit_text);
+        *y_pos += ROW_SPACING;
+    }
+    *y_pos += POST_BAR_SPACING;
+}
+
+/// One row per probe, green while up, red when down and plain until first checked, over its latency history.
+fn render_probe_rows(ctx: &mut RenderContext, y_pos: &mut u32) {
+    let colours = Colours::default();
+    let fc_regular = fonts::regular_font_config();
+    let x = (ctx.x + 5) as i32;
+
+    for probe in &ctx.info.probes {
+        let colour = match (probe.up, probe.history.is_empty()) {
+            (_, true) => colours.text,
+            (true, false) => colours.cpu,
+            (false, false) => colours.log_error,
+        };
+        let probe_text = format!("{} {}", probe.name, probe.latency_display);
+        drawing::text(ctx.image, colour, x, *y_pos as i32, &fc_regular, &probe_text);
+        *y_pos += PRE_BAR_SPACING;
+
+        drawing::sparkline(
             ctx.image,
-            (ctx.x + 5) as i32,
-            y_pos as i32,
+            x,
+            *y_pos as i32,
             ctx.width - 10,
-            bar_height,
-            gpu.load,
-            colours.gpu,
+            BAR_HEIGHT,
+            &probe.history,
+            ctx.config.collectors.probes.history,
+            colours.cpu,
         );
-        y_pos += bar_height + post_bar_spacing;
+        *y_pos += BAR_HEIGHT + POST_BAR_SPACING;
     }
+}
 
+fn render_gpu_bars(ctx: &mut RenderContext, y_pos: &mut u32) {
+    for gpu in &ctx.info.gpus {
+        let mut gpu_text = format!("{} {}", gpu.name, gpu.load_display);
+        // Integrated GPUs have no memory of their own
+        if gpu.memory_total > 0 {
+            gpu_text.push_str(&format!(" | {}/{}", gpu.memory_used_display, gpu.memory_total_display));
+        }
+        if gpu.power > 0.0 {
+            gpu_text.push_str(&format!(" | {}", gpu.power_display));
+        }
+        labelled_bar(ctx, y_pos, &gpu_text, gpu.load, Colours::default().gpu);
+    }
 }
 
-pub fn render_processes(ctx: &mut RenderContext) {
+/// One gauge per battery, green when full to red when empty, then AC and RAPL power.
+fn render_battery_gauges(ctx: &mut RenderContext, y_pos: &mut u32) {
     let colours = Colours::default();
+    let fc_regular = fonts::regular_font_config();
+    let x = (ctx.x + 5) as i32;
+    let power = &ctx.info.power;
+
+    for battery in &power.batteries {
+        let state = match battery.time_display.as_str() {
+            "" => &battery.status,
+            time => time,
+        };
+        let battery_text = format!("{} {} | {}", battery.name, battery.capacity_display, state);
+        drawing::text(ctx.image, colours.text, x, *y_pos as i32, &fc_regular, &battery_text);
+        *y_pos += PRE_BAR_SPACING;
+
+        let colour = colours::heat(1.0 - battery.capacity);
+        drawing::battery_gauge(ctx.image, x, *y_pos as i32, ctx.width - 10, BAR_HEIGHT, battery.capacity, colour);
+        *y_pos += BAR_HEIGHT + POST_BAR_SPACING;
+    }
 
+    let power_text = match (power.batteries.is_empty(), power.display.is_empty()) {
+        (true, true) => "No battery",
+        _ => &power.display,
+    };
+    if !power_text.is_empty() {
+        drawing::text(ctx.image, colours.sensor, x, *y_pos as i32, &fc_regular, power_text);
+        *y_pos += ROW_SPACING + POST_BAR_SPACING;
+    }
+}
+
+/// A `[CUSTOM.<name>]` value: a bar when it has a `max`, otherwise a text row, red while it
+/// cannot be fetched.
+fn render_custom_metric(ctx: &mut RenderContext, y_pos: &mut u32, name: &str) {
+    let colours = Colours::default();
     let fc_regular = fonts::regular_font_config();
-    let fc_title = fonts::title_font_config();
+    let x = (ctx.x + 5) as i32;
+
+    let Some(metric) = ctx.info.custom.get(name) else {
+        let unknown_text = format!("Unknown metric {}", name);
+        drawing::text(ctx.image, colours.log_warning, x, *y_pos as i32, &fc_regular, &unknown_text);
+        *y_pos += ROW_SPACING + POST_BAR_SPACING;
+        return;
+    };
+    let metric_text = format!("{} {}", metric.label, metric.display);
+    match metric.percent {
+        Some(percent) => labelled_bar(ctx, y_pos, &metric_text, percent, colours::heat(percent)),
+        None => {
+            let colour = if metric.value.is_some() { colours.text } else { colours.log_error };
+            drawing::text(ctx.image, colour, x, *y_pos as i32, &fc_regular, &metric_text);
+            *y_pos += ROW_SPACING + POST_BAR_SPACING;
+        }
+    }
+}
 
+pub fn render_processes(ctx: &mut RenderContext) {
     let mut y_pos = ctx.y;
 
+    let info = ctx.info;
+    let mut drawn = false;
+    for ranking in &ctx.config.dashboard.processes {
+        let (title, processes, value, columns): Ranking = match ranking.as_str() {
+            "cpu" => (
+                "TOP CPU PROCESSES",
+                &info.top_cpu_processes,
+                |proc| format!("{:>6.1}%", proc.cpu_percent),
+                PERCENT_COLUMNS,
+            ),
+            "memory" => (
+                "TOP MEMORY PROCESSES",
+                &info.top_memory_processes,
+                |proc| format!("{:>6.1}%", proc.memory_percent),
+                PERCENT_COLUMNS,
+            ),
+            "io" => (
+                "TOP DISK I/O PROCESSES",
+                &info.top_io_processes,
+                |proc| proc.io_display.clone(),
+                FIGURE_COLUMNS,
+            ),
+            "connections" => (
+                "TOP CONNECTIONS",
+                &info.top_connection_processes,
+                |proc| proc.connections.to_string(),
+                FIGURE_COLUMNS,
+            ),
+            "containers" => {
+                if drawn {
+                    y_pos += 8;
+                }
+                render_container_ranking(ctx, &mut y_pos);
+                drawn = true;
+                continue;
+            }
+            other => {
+                match other.strip_prefix("metric:") {
+                    Some(name) => {
+                        if drawn {
+                            y_pos += 8;
+                        }
+                        render_custom_metric(ctx, &mut y_pos, name);
+                        drawn = true;
+                    }
+                    None => warn!("Unknown process ranking: {}", other),
+                }
+                continue;
+            }
+        };
+
+        if drawn {
+            y_pos += 8; // Space between sections
+        }
+        render_process_ranking(ctx, &mut y_pos, title, processes, value, columns);
+        drawn = true;
+    }
+}
+
+/// A title followed by one row per process; `value` formats the ranked figure, in columns of
+/// `pid_width` and `value_width` characters.
+fn render_process_ranking(
+    ctx: &mut RenderContext,
+    y_pos: &mut u32,
+    title: &str,
+    processes: &[ProcessInfo],
+    value: fn(&ProcessInfo) -> String,
+    (pid_width, value_width): (usize, usize),
+) {
+    let colours = Colours::default();
+    let fc_regular = fonts::regular_font_config();
+    let fc_title = fonts::title_font_config();
     let process_gap = 23;
 
-    // Top CPU processes
     drawing::text(
         ctx.image,
         colours.process,
         (ctx.x + 5) as i32,
-        y_pos as i32,
+        *y_pos as i32,
         &fc_title,
-        "TOP CPU PROCESSES",
+        title,
     );
-    y_pos += 20;
+    *y_pos += 20;
 
-    for proc in &ctx.info.top_cpu_processes {
-        let proc_name = if proc.name.len() > 12 {
-            &proc.name[..12]
-        } else {
-            &proc.name
-        };
+    for proc in processes {
         let proc_text = format!(
-            "{:<12} {:<9} {:>6.1}%",
-            proc_name, proc.pid, proc.cpu_percent
+            "{:<12} {:<pid_width$} {:>value_width$}",
+            process_name(proc),
+            process_id(proc),
+            value(proc)
         );
         drawing::text(
             ctx.image,
             colours.text,
             (ctx.x + 20) as i32,
-            y_pos as i32,
+            *y_pos as i32,
             &fc_regular,
             &proc_text,
         );
-        y_pos += process_gap;
+        *y_pos += process_gap;
     }
+}
 
-    y_pos += 8; // Space between CPU and memory sections
+/// Busiest containers and slices: name, CPU and memory.
+fn render_container_ranking(ctx: &mut RenderContext, y_pos: &mut u32) {
+    let colours = Colours::default();
+    let fc_regular = fonts::regular_font_config();
 
-    // Top Memory processes
     drawing::text(
         ctx.image,
         colours.process,
         (ctx.x + 5) as i32,
-        y_pos as i32,
-        &fc_title,
-        "TOP MEMORY PROCESSES",
+        *y_pos as i32,
+        &fonts::title_font_config(),
+        "TOP CONTAINERS",
     );
-    y_pos += 20;
-
-    for proc in &ctx.info.top_memory_processes {
-        let proc_name = if proc.name.len() > 12 {
-            &proc.name[..12]
-        } else {
-            &proc.name
-        };
-        let proc_text = format!(
-            "{:<12} {:<9} {:>6.1}%",
-            proc_name, proc.pid, proc.memory_percent
+    *y_pos += 20;
+
+    for container in &ctx.info.containers {
+        let name: String = container.name.chars().take(12).collect();
+        let container_text = format!(
+            "{:<12} {:>7} {:>9}",
+            name,
+            container.cpu_display,
+            ByteSize::b(container.memory).to_string()
         );
         drawing::text(
             ctx.image,
             colours.text,
             (ctx.x + 20) as i32,
-            y_pos as i32,
+            *y_pos as i32,
             &fc_regular,
-            &proc_text,
+            &container_text,
         );
-        y_pos += process_gap;
+        *y_pos += 23;
+    }
+}
+
+/// First 12 characters of the name; slicing bytes would panic on multi-byte names.
+fn process_name(proc: &ProcessInfo) -> String {
+    proc.name.chars().take(12).collect()
+}
+
+/// The pid, or the number of processes for a group.
+fn process_id(proc: &ProcessInfo) -> String {
+    if proc.count > 1 {
+        format!("x{}", proc.count)
+    } else {
+        proc.pid.to_string()
     }
 }
 
@@ -273,7 +636,7 @@ pub fn render_footer(ctx: &mut RenderContext) {
             (ctx.x + 5) as i32,
             y_pos as i32,
             &fc_regular,
-            &sensors_text,
+            sensors_text,
         );
         y_pos += 28;
         drawing::horizonal_line(ctx.image, 0, y_pos, ctx.width);
@@ -318,13 +681,18 @@ pub fn render_footer(ctx: &mut RenderContext) {
 
     for (i, line) in ctx.info.syslog_lines.iter().enumerate() {
         let y_position = y_pos + (i as u32 * 18);
+        let colour = match line.severity {
+            LogSeverity::Normal => colours.log,
+            LogSeverity::Warning => colours.log_warning,
+            LogSeverity::Error => colours.log_error,
+        };
         drawing::text(
             ctx.image,
-            colours.log,
+            colour,
             (ctx.x + 5) as i32,
             y_position as i32,
             &fc_small,
-            line,
+            &line.text,
         );
     }
 }
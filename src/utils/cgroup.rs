@@ -0,0 +1,12 @@
+use std::path::{Path, PathBuf};
+
+/// The cgroup v2 hierarchy below `base` (normally `/sys/fs/cgroup`): `base` itself, or its
+/// `unified` directory on hybrid v1/v2 systems.
+pub fn unified_root(base: &Path) -> PathBuf {
+    let unified = base.join("unified");
+    if !base.join("cgroup.controllers").exists() && unified.join("cgroup.controllers").exists() {
+        unified
+    } else {
+        base.to_path_buf()
+    }
+}
@@ -1,34 +1,35 @@
-use std::fs::{read_dir, File};
+use std::fs::{canonicalize, read_dir, File};
 use std::io::Read;
 use std::path::Path;
-use crate::models::sensor::Sensor;
+use crate::models::sensor::{Sensor, SensorKind};
 
-/// Read out `hwmon` info (hardware monitor) from `folder`
-/// to get values' path to be used on refresh as well as files containing `max`,
-/// `critical value` and `label`. Then we store everything into `components`.
+/// Read out `hwmon` info (hardware monitor) from `folder`: every temperature, fan, voltage,
+/// power and current input, with its `label`, `max` and `critical value`. Then we store
+/// everything into `sensors`.
 ///
-/// Note that a thermal [Component] must have a way to read its temperature.
-/// If not, it will be ignored and not added into `components`.
+/// Note that a [Sensor] must have a way to read its value.
+/// If not, it will be ignored and not added into `sensors`.
 ///
 /// ## What is read:
 ///
 /// - Mandatory: `name` the name of the `hwmon`.
-/// - Mandatory: `tempN_input` Drop [Component] if missing
-/// - Optional: sensor `label`, in the general case content of `tempN_label`
-///   see below for special cases
-/// - Optional: `label`
+/// - Mandatory: `<type>N_input` where `<type>` is `temp`, `fan`, `in`, `power` or `curr`.
+///   Drop [Sensor] if missing; power inputs fall back to `powerN_average` (amdgpu).
+/// - Optional: sensor `label`, content of `<type>N_label`
 /// - Optional: `/device/model`
-/// - Optional: highest historic value in `tempN_highest`.
-/// - Optional: max threshold value defined in `tempN_max`
-/// - Optional: critical threshold value defined in `tempN_crit`
+/// - Optional: where `device` links to, identifying the chip by its PCI or platform path
+/// - Optional: max threshold value defined in `<type>N_max`
+/// - Optional: critical threshold value defined in `<type>N_crit`
 ///
-/// Where `N` is a `u32` associated to a sensor like `temp1_max`, `temp1_input`.
+/// Where `N` is a `u32` associated to a sensor like `temp1_max`, `fan2_input`.
+/// Values are converted from millidegrees, millivolts, milliamps and microwatts.
 ///
 /// ## Doc to Linux kernel API.
 ///
 /// Kernel hwmon API: https://www.kernel.org/doc/html/latest/hwmon/hwmon-kernel-api.html
-/// DriveTemp kernel API: https://docs.kernel.org/gpu/amdgpu/thermal.html#hwmon-interfaces
-/// Amdgpu hwmon interface: https://www.kernel.org/doc/html/latest/hwmon/drivetemp.html
+/// Sysfs interface: https://www.kernel.org/doc/html/latest/hwmon/sysfs-interface.html
+/// Amdgpu hwmon interface: https://docs.kernel.org/gpu/amdgpu/thermal.html#hwmon-interfaces
+/// DriveTemp kernel API: https://www.kernel.org/doc/html/latest/hwmon/drivetemp.html
 pub fn from_hwmon(sensors: &mut Vec<Sensor>, folder: &Path) -> Option<()> {
     let dir = read_dir(folder).ok()?;
     for entry in dir.flatten() {
@@ -38,29 +39,43 @@ pub fn from_hwmon(sensors: &mut Vec<Sensor>, folder: &Path) -> Option<()> {
 
         let entry = entry.path();
         let filename = entry.file_name().and_then(|x| x.to_str()).unwrap_or("");
-        let Some((id, item)) = filename
-            .strip_prefix("temp")
-            .and_then(|f| f.split_once('_'))
-            .and_then(|(id, item)| Some((id.parse::<u32>().ok()?, item)))
-        else {
+        let Some((kind, id, item)) = SensorKind::ALL.iter().find_map(|kind| {
+            let (id, item) = filename.strip_prefix(kind.prefix())?.split_once('_')?;
+            Some((*kind, id.parse::<u32>().ok()?, item))
+        }) else {
             continue;
         };
 
-        if item != "input" {
-            continue;       
+        let channel = format!("{}{}", kind.prefix(), id);
+        let is_input = item == "input"
+            || (kind == SensorKind::Power
+                && item == "average"
+                && !folder.join(format!("{}_input", channel)).exists());
+        if !is_input {
+            continue;
         }
 
+        let scaled = |file: &Path| read_number_from_file::<f64>(file).map(|n| (n / kind.scale()) as f32);
+        let threshold = |item: &str| scaled(&folder.join(format!("{}_{}", channel, item)));
+
         let name = get_file_line(&folder.join("name"), 16).unwrap_or("".into());
         let model = get_file_line(&folder.join("device/model"), 16).unwrap_or("".into());
-        let label = get_file_line(&folder.join(filename.replace("_input", "_label")), 16).unwrap_or("".into());
-        let temperature = get_temperature_from_file(&folder.join(filename)).unwrap_or(0.0);
+        let label = get_file_line(&folder.join(format!("{}_label", channel)), 16).unwrap_or("".into());
+        let device = canonicalize(folder.join("device"))
+            .map(|path| path.to_string_lossy().to_string())
+            .unwrap_or_default();
         let sensor = Sensor {
             id,
             path: entry.to_str().unwrap_or("").into(),
             name,
             label,
             model,
-            temperature,
+            device,
+            kind,
+            unit: kind.unit(),
+            value: scaled(&entry).unwrap_or(0.0),
+            max: threshold("max"),
+            crit: threshold("crit"),
         };
         sensors.push(sensor);
     }
@@ -98,18 +113,51 @@ where
     number.parse().ok()
 }
 
-// Read a temperature from a `tempN_item` sensor form the sysfs.
-// number returned will be in mili-celsius.
-//
-// Don't call it on `label`, `name` or `type` file.
-#[inline]
-fn get_temperature_from_file(file: &Path) -> Option<f32> {
-    let temp = read_number_from_file(file);
-    convert_temp_celsius(temp)
-}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
 
-/// Takes a raw temperature in mili-celsius and convert it to celsius.
-#[inline]
-fn convert_temp_celsius(temp: Option<i32>) -> Option<f32> {
-    temp.map(|n| (n as f32) / 1000f32)
+    #[test]
+    fn test_from_hwmon() {
+        let hwmon = tempfile::tempdir().unwrap();
+        for (file, value) in [
+            ("name", "nct6798"),
+            ("temp1_input", "45500"),
+            ("temp1_max", "80000"),
+            ("temp1_crit", "100000"),
+            ("temp1_label", "SYSTIN"),
+            ("fan2_input", "1450"),
+            ("fan2_label", "Pump"),
+            ("in1_input", "12096"),
+            ("in1_min", "11000"),
+            ("power1_average", "65250000"),
+            ("curr1_input", "1500"),
+            ("curr1_crit", "20000"),
+            ("intrusion0_alarm", "0"),
+        ] {
+            fs::write(hwmon.path().join(file), format!("{}\n", value)).unwrap();
+        }
+
+        let mut sensors = Vec::new();
+        from_hwmon(&mut sensors, hwmon.path());
+        sensors.sort_by_key(|sensor| sensor.channel());
+        let summary: Vec<(String, f32, &str)> =
+            sensors.iter().map(|s| (s.channel(), s.value, s.unit)).collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("curr1".to_string(), 1.5, "A"),
+                ("fan2".to_string(), 1450.0, "RPM"),
+                ("in1".to_string(), 12.096, "V"),
+                ("power1".to_string(), 65.25, "W"),
+                ("temp1".to_string(), 45.5, "°C"),
+            ]
+        );
+        assert_eq!(sensors[0].crit, Some(20.0));
+        assert_eq!(sensors[1].label, "Pump");
+        assert_eq!(sensors[1].kind, SensorKind::Fan);
+        assert_eq!(sensors[4].name, "nct6798");
+        assert_eq!((sensors[4].max, sensors[4].crit), (Some(80.0), Some(100.0)));
+    }
 }
@@ -1,3 +1,7 @@
-
+pub mod cgroup;
 pub mod file;
 pub mod hwmon;
+pub mod rate;
+pub mod tail;
+#[cfg(test)]
+pub mod testing;
@@ -0,0 +1,56 @@
+use std::time::Instant;
+use systemstat::ByteSize;
+
+/// Turns a monotonically increasing counter (bytes read, packets sent, ...) into a per-second rate
+/// between two consecutive samples.
+#[derive(Debug, Clone, Default)]
+pub struct RateCounter {
+    previous: Option<(u64, Instant)>,
+}
+
+impl RateCounter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records `value` taken at `now` and returns the rate since the previous sample.
+    ///
+    /// The first sample has nothing to compare against and returns `None`, as does a counter that
+    /// went backwards (device removed, counter reset), which restarts the measurement.
+    pub fn update(&mut self, value: u64, now: Instant) -> Option<f64> {
+        let previous = self.previous.replace((value, now));
+        let (last_value, last_time) = previous?;
+        if value < last_value {
+            return None;
+        }
+        let elapsed = now.saturating_duration_since(last_time).as_secs_f64();
+        if elapsed <= 0.0 {
+            return None;
+        }
+        Some((value - last_value) as f64 / elapsed)
+    }
+}
+
+/// Formats a byte rate, e.g. `1.2 MB/s`.
+pub fn bytes_per_second(bytes: u64) -> String {
+    format!("{}/s", ByteSize::b(bytes))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn test_rate_counter() {
+        let start = Instant::now();
+        let mut counter = RateCounter::new();
+        assert_eq!(counter.update(1_000, start), None);
+        assert_eq!(counter.update(3_000, start + Duration::from_secs(2)), Some(1_000.0));
+        assert_eq!(counter.update(3_500, start + Duration::from_millis(2_500)), Some(1_000.0));
+
+        // Counter reset: no rate, then measuring resumes from the new value
+        assert_eq!(counter.update(100, start + Duration::from_secs(3)), None);
+        assert_eq!(counter.update(600, start + Duration::from_secs(4)), Some(500.0));
+    }
+}
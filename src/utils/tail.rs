@@ -0,0 +1,123 @@
+use std::fs::File;
+use std::io::{self, Read, Seek, SeekFrom};
+use std::os::unix::fs::MetadataExt;
+use std::path::{Path, PathBuf};
+
+/// How much of an existing file is read to find its last lines.
+const BACKLOG_BYTES: u64 = 64 * 1024;
+
+/// Follows a growing file like `tail -F`: each call returns only the lines appended since the
+/// previous one, and a rotated (replaced) or truncated file is picked up from its start.
+#[derive(Debug)]
+pub struct Tail {
+    path: PathBuf,
+    /// Device and inode of the file being followed, `None` before the first read.
+    identity: Option<(u64, u64)>,
+    offset: u64,
+    /// Bytes after the last newline, completed by a later write.
+    partial: Vec<u8>,
+}
+
+impl Tail {
+    pub fn new<P: AsRef<Path>>(path: P) -> Self {
+        Self {
+            path: path.as_ref().to_path_buf(),
+            identity: None,
+            offset: 0,
+            partial: Vec::new(),
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Complete lines appended since the previous call. The first call returns up to the last
+    /// `backlog` lines already in the file.
+    pub fn read_lines(&mut self, backlog: usize) -> io::Result<Vec<String>> {
+        let mut file = File::open(&self.path)?;
+        let metadata = file.metadata()?;
+        let identity = (metadata.dev(), metadata.ino());
+
+        let first = self.identity.is_none();
+        if self.identity != Some(identity) || metadata.len() < self.offset {
+            // New file (rotated) or truncated: start over
+            self.identity = Some(identity);
+            self.offset = 0;
+            self.partial.clear();
+        }
+        let skip_partial_line = first && metadata.len() > BACKLOG_BYTES;
+        if first {
+            self.offset = metadata.len().saturating_sub(BACKLOG_BYTES);
+        }
+
+        file.seek(SeekFrom::Start(self.offset))?;
+        let mut bytes = Vec::new();
+        file.read_to_end(&mut bytes)?;
+        self.offset += bytes.len() as u64;
+
+        let mut data = std::mem::take(&mut self.partial);
+        data.extend_from_slice(&bytes);
+        let complete = match data.iter().rposition(|b| *b == b'\n') {
+            Some(end) => {
+                self.partial = data.split_off(end + 1);
+                data
+            }
+            None => {
+                self.partial = data;
+                Vec::new()
+            }
+        };
+
+        let mut lines: Vec<String> = complete
+            .split(|b| *b == b'\n')
+            .map(|line| String::from_utf8_lossy(line).trim_end_matches('\r').to_string())
+            .collect();
+        lines.pop(); // Empty remainder after the final newline
+        if skip_partial_line && !lines.is_empty() {
+            lines.remove(0); // Started reading mid-line
+        }
+        if first && lines.len() > backlog {
+            lines.drain(..lines.len() - backlog);
+        }
+        Ok(lines)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::{self, OpenOptions};
+    use std::io::Write;
+
+    fn append(path: &Path, text: &str) {
+        let mut file = OpenOptions::new().create(true).append(true).open(path).unwrap();
+        file.write_all(text.as_bytes()).unwrap();
+    }
+
+    #[test]
+    fn test_tail_follows_appends_and_rotation() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("app.log");
+        append(&path, "one\ntwo\nthree\n");
+
+        let mut tail = Tail::new(&path);
+        assert_eq!(tail.read_lines(2).unwrap(), vec!["two", "three"]);
+        assert!(tail.read_lines(2).unwrap().is_empty());
+
+        // A line is only returned once complete
+        append(&path, "four\nfi");
+        assert_eq!(tail.read_lines(2).unwrap(), vec!["four"]);
+        append(&path, "ve\r\n");
+        assert_eq!(tail.read_lines(2).unwrap(), vec!["five"]);
+
+        // Rotation: the old file is moved away and a new one created
+        fs::rename(&path, dir.path().join("app.log.1")).unwrap();
+        append(&path, "six\n");
+        assert_eq!(tail.read_lines(2).unwrap(), vec!["six"]);
+
+        // Truncation, e.g. logrotate's copytruncate
+        fs::write(&path, "7\n").unwrap();
+        assert_eq!(tail.read_lines(2).unwrap(), vec!["7"]);
+    }
+}
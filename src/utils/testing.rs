@@ -0,0 +1,11 @@
+use std::fs;
+use std::path::Path;
+
+/// Writes `contents` to `root/file` for a fake sysfs, procfs or cgroup tree, creating the
+/// directories on the way and ending the file with a newline like the kernel does.
+pub fn write(root: &Path, file: &str, contents: &str) {
+    let path = root.join(file);
+    fs::create_dir_all(path.parent().unwrap()).unwrap();
+    let newline = if contents.ends_with('\n') { "" } else { "\n" };
+    fs::write(path, format!("{}{}", contents, newline)).unwrap();
+}